@@ -0,0 +1,77 @@
+//! Example demonstrating a retrieval-then-answer flow inside a single chain
+//!
+//! This example shows how to:
+//! 1. Register a chat provider and an embedding provider
+//! 2. Embed the user question and look up the closest documents of a small corpus
+//! 3. Answer the question using only the retrieved documents
+
+use rllm::{
+    builder::{LLMBackend, LLMBuilder},
+    chain::{
+        EmbedStep, LLMRegistryBuilder, MultiChainStepBuilder, MultiChainStepMode, MultiPromptChain,
+    },
+};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let api_key = std::env::var("OPENAI_API_KEY").unwrap_or("sk-OPENAI".into());
+
+    // Chat model answering the question
+    let chat = LLMBuilder::new()
+        .backend(LLMBackend::OpenAI)
+        .api_key(api_key.clone())
+        .model("gpt-4o")
+        .build()?;
+
+    // Embedding model used for retrieval
+    let embedder = LLMBuilder::new()
+        .backend(LLMBackend::OpenAI)
+        .api_key(api_key)
+        .model("text-embedding-3-small")
+        .build()?;
+
+    let registry = LLMRegistryBuilder::new()
+        .register("chat", chat)
+        .register("embed", embedder)
+        .build();
+
+    // Small knowledge base to search
+    let corpus = vec![
+        "Our office is open Monday to Friday, 9am to 6pm.".to_string(),
+        "Refunds are processed within 14 days of receiving the returned item.".to_string(),
+        "Shipping to the EU takes 3 to 5 business days.".to_string(),
+    ];
+
+    let chain_result = MultiPromptChain::new(&registry)
+        // Step 1: Question (a function step could also read it from user input)
+        .step(
+            MultiChainStepBuilder::new(MultiChainStepMode::function(|_| {
+                Ok("How long does a refund take?")
+            }))
+            .id("question")
+            .build()?,
+        )
+        // Step 2: Retrieve the 2 most similar documents
+        .step(
+            MultiChainStepBuilder::new(MultiChainStepMode::Embed(EmbedStep::search(corpus, 2)))
+                .provider_id("embed")
+                .id("context")
+                .template("{{question}}")
+                .build()?,
+        )
+        // Step 3: Answer with the retrieved context
+        .step(
+            MultiChainStepBuilder::new(MultiChainStepMode::Chat)
+                .provider_id("chat")
+                .id("answer")
+                .template("Answer using only this context:\n{{context}}\n\nQuestion: {{question}}")
+                .build()?,
+        )
+        .run()
+        .await?;
+
+    println!("Context: {}", chain_result["context"]);
+    println!("Answer: {}", chain_result["answer"]);
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+//! Example demonstrating Rust function steps inside a multi-backend chain
+//!
+//! This example shows how to:
+//! 1. Ask an LLM for structured data
+//! 2. Parse and enrich that data with a synchronous Rust closure
+//! 3. Run an asynchronous lookup (e.g. a database query) between LLM steps
+//! 4. Feed the function outputs back into a final LLM step
+
+use rllm::{
+    builder::{LLMBackend, LLMBuilder},
+    chain::{LLMRegistryBuilder, MultiChainStepBuilder, MultiChainStepMode, MultiPromptChain},
+    error::LLMError,
+};
+use serde_json::json;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Initialize the OpenAI provider used by the LLM steps
+    let openai = LLMBuilder::new()
+        .backend(LLMBackend::OpenAI)
+        .api_key(std::env::var("OPENAI_API_KEY").unwrap_or("sk-OPENAI".into()))
+        .model("gpt-4o")
+        .build()?;
+
+    let registry = LLMRegistryBuilder::new().register("openai", openai).build();
+
+    let chain_result = MultiPromptChain::new(&registry)
+        // Step 1: Ask for a JSON list of cities
+        .step(
+            MultiChainStepBuilder::new(MultiChainStepMode::Chat)
+                .provider_id("openai")
+                .id("cities")
+                .template("Return a JSON array with the names of 3 European capitals. JSON only.")
+                .build()?,
+        )
+        // Step 2: Parse the JSON in Rust and keep the first city
+        .step(
+            MultiChainStepBuilder::new(MultiChainStepMode::function(|ctx| {
+                let cities = ctx.json("cities")?;
+                let first = cities
+                    .get(0)
+                    .and_then(|c| c.as_str())
+                    .ok_or_else(|| LLMError::InvalidRequest("Expected a JSON array".into()))?;
+                Ok(first.to_string())
+            }))
+            .id("city")
+            .build()?,
+        )
+        // Step 3: Async lookup, standing in for a database query
+        .step(
+            MultiChainStepBuilder::new(MultiChainStepMode::async_function(|ctx| async move {
+                let city = ctx.get("city").unwrap_or_default().to_string();
+                Ok::<_, LLMError>(json!({ "city": city, "visitors_last_year": 1_250_000 }))
+            }))
+            .id("stats")
+            .build()?,
+        )
+        // Step 4: Use the function outputs in a final LLM step
+        .step(
+            MultiChainStepBuilder::new(MultiChainStepMode::Chat)
+                .provider_id("openai")
+                .id("summary")
+                .template("Write one sentence about {{city}} using these statistics: {{stats}}")
+                .build()?,
+        )
+        .run()
+        .await?;
+
+    // Display results from all steps
+    for (id, output) in &chain_result {
+        println!("{}: {}", id, output);
+    }
+
+    Ok(())
+}
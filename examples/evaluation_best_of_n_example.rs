@@ -0,0 +1,55 @@
+//! Example demonstrating best-of-N selection as a regular provider
+//!
+//! This example shows how to:
+//! 1. Wrap an evaluator with several providers and samples into a `BestOfN` provider
+//! 2. Use it like any other backend through `ChatProvider::chat`
+//! 3. Inspect the scores of every candidate with `chat_scored`
+
+use rllm::{
+    builder::{LLMBackend, LLMBuilder},
+    chat::ChatMessage,
+    evaluator::{BestOfN, LLMEvaluator},
+};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let openai = LLMBuilder::new()
+        .backend(LLMBackend::OpenAI)
+        .api_key(std::env::var("OPENAI_API_KEY").unwrap_or("sk-OPENAI".into()))
+        .model("gpt-4o-mini")
+        .temperature(0.9)
+        .build()?;
+
+    let anthropic = LLMBuilder::new()
+        .backend(LLMBackend::Anthropic)
+        .api_key(std::env::var("ANTHROPIC_API_KEY").unwrap_or("anthro-key".into()))
+        .model("claude-3-5-haiku-20241022")
+        .temperature(0.9)
+        .build()?;
+
+    let evaluator = LLMEvaluator::named(vec![
+        ("openai".to_string(), openai),
+        ("anthropic".to_string(), anthropic),
+    ])
+    .named_scoring("short", |r| {
+        1.0 / (1.0 + r.split_whitespace().count() as f32 / 20.0)
+    })
+    .samples(3);
+
+    let best = BestOfN::new(evaluator);
+
+    let messages = vec![ChatMessage::user()
+        .content("Write a one-line slogan for a Rust web framework.")
+        .build()];
+
+    let response = best.chat_scored(&messages, None).await?;
+    for candidate in &response.candidates {
+        println!("{:>10}: {:.3}", candidate.provider_id, candidate.score);
+    }
+    println!(
+        "Selected from {} (score {:.3}): {}",
+        response.provider_id, response.score, response
+    );
+
+    Ok(())
+}
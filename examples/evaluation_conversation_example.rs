@@ -0,0 +1,74 @@
+//! Example demonstrating multi-turn conversation evaluation
+//!
+//! This example shows how to:
+//! 1. Script a conversation with fixed follow-up turns
+//! 2. Let a simulator provider play a user persona for several turns
+//! 3. Score whole transcripts with scoring functions and a judge
+
+use rllm::{
+    builder::{LLMBackend, LLMBuilder},
+    evaluator::{ConversationScript, JudgeScorer, LLMEvaluator},
+};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let build = |model: &str| {
+        LLMBuilder::new()
+            .backend(LLMBackend::OpenAI)
+            .api_key(std::env::var("OPENAI_API_KEY").unwrap_or("sk-OPENAI".into()))
+            .model(model)
+            .build()
+    };
+
+    let evaluator = LLMEvaluator::named(vec![
+        ("gpt-4o-mini".to_string(), build("gpt-4o-mini")?),
+        ("gpt-4o".to_string(), build("gpt-4o")?),
+    ])
+    .named_scoring("remembers_order_id", |transcript| {
+        // The assistant must reuse the order number given in the opening message
+        if transcript
+            .lines()
+            .any(|l| l.starts_with("Assistant:") && l.contains("A-1042"))
+        {
+            1.0
+        } else {
+            0.0
+        }
+    })
+    .judge(
+        JudgeScorer::new(build("gpt-4o")?)
+            .rubric("Grade the assistant over the whole conversation: helpfulness, consistency and politeness.")
+            .scale(1.0, 5.0),
+    );
+
+    // Fixed follow-ups
+    let scripted = ConversationScript::scripted(
+        "Hi, my order A-1042 arrived damaged.",
+        [
+            "Can I get a replacement instead of a refund?",
+            "How long will it take?",
+        ],
+    );
+
+    // Simulated impatient customer
+    let simulated = ConversationScript::simulated(
+        "Hi, my order A-1042 arrived damaged.",
+        build("gpt-4o-mini")?,
+        "An impatient customer who wants a replacement shipped today and dislikes long answers.",
+    )
+    .turns(4);
+
+    for (name, script) in [("scripted", &scripted), ("simulated", &simulated)] {
+        println!("=== {} ===", name);
+        for result in evaluator.evaluate_conversation(script).await? {
+            println!(
+                "{}: {} turns, score {:.2}",
+                result.provider_id,
+                result.turns(),
+                result.eval.score
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+//! Example demonstrating a dataset-driven evaluation across registered providers
+//!
+//! This example shows how to:
+//! 1. Load evaluation cases from a JSONL file
+//! 2. Evaluate every provider of a registry with bounded concurrency
+//! 3. Print per-provider aggregates and write JSON and JUnit XML reports for CI
+
+use rllm::{
+    builder::{LLMBackend, LLMBuilder},
+    chain::LLMRegistryBuilder,
+    evaluator::{EvalDataset, LLMEvaluator, NormalizedMatch, Pricing, RougeL, ScorerExt},
+};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Register the providers to compare
+    let registry = LLMRegistryBuilder::new()
+        .register(
+            "openai",
+            LLMBuilder::new()
+                .backend(LLMBackend::OpenAI)
+                .api_key(std::env::var("OPENAI_API_KEY").unwrap_or("sk-OPENAI".into()))
+                .model("gpt-4o-mini")
+                .build()?,
+        )
+        .register(
+            "anthropic",
+            LLMBuilder::new()
+                .backend(LLMBackend::Anthropic)
+                .api_key(std::env::var("ANTHROPIC_API_KEY").unwrap_or("anthro-key".into()))
+                .model("claude-3-5-haiku-20241022")
+                .build()?,
+        )
+        .build();
+
+    // Compare with the reference answers and score short, single-line answers higher
+    let evaluator = LLMEvaluator::from_registry(registry)
+        .scorer(NormalizedMatch.with_weight(2.0))
+        .scorer(RougeL)
+        .named_scoring("concise", |response| {
+            if response.lines().count() <= 1 {
+                1.0
+            } else {
+                0.0
+            }
+        })
+        .named_scoring("non_empty", |response| {
+            if response.trim().is_empty() {
+                0.0
+            } else {
+                1.0
+            }
+        });
+
+    let dataset = EvalDataset::from_jsonl("examples/data/eval_cases.jsonl")?;
+
+    let report = evaluator
+        .dataset(&dataset)
+        .concurrency(4)
+        .pass_threshold(3.0)
+        .pricing("openai", Pricing::per_1k(0.00015, 0.0006))
+        .pricing("anthropic", Pricing::per_1k(0.0008, 0.004))
+        .run()
+        .await?;
+
+    for provider in &report.providers {
+        println!(
+            "{}: mean={:?} failures={}/{} p90={}ms cost={:?}",
+            provider.provider_id,
+            provider.mean_score,
+            provider.failures,
+            provider.cases,
+            provider.latency.p90,
+            provider.estimated_cost
+        );
+        for (scorer, mean) in &provider.scorers {
+            println!("  {}: {:.3}", scorer, mean);
+        }
+    }
+
+    report.write_json("eval_report.json")?;
+    report.write_junit("eval_report.xml")?;
+
+    Ok(())
+}
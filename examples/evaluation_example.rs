@@ -4,15 +4,16 @@
 //! 1. Initialize multiple LLM providers (Anthropic, Phind, DeepSeek)
 //! 2. Configure scoring functions to evaluate responses
 //! 3. Send the same prompt to all providers
-//! 4. Compare and score the responses
+//! 4. Compare and score the responses, including a grade from a judge LLM
 
 use rllm::{
     builder::{LLMBackend, LLMBuilder},
-    chat::{ChatMessage, ChatRole},
-    evaluator::{EvalResult, LLMEvaluator},
+    chat::{ChatMessage, ChatRole, MessageType},
+    evaluator::{EvalResult, JudgeScorer, LLMEvaluator},
 };
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize Anthropic provider with Claude model
     let anthropic = LLMBuilder::new()
         .backend(LLMBackend::Anthropic)
@@ -33,7 +34,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .api_key(std::env::var("DEEPSEEK_API_KEY").unwrap_or("deepseek-key".into()))
         .build()?;
 
-    // Create evaluator with multiple scoring functions
+    // Initialize the judge grading each response, constrained to a JSON judgement
+    let judge = LLMBuilder::new()
+        .backend(LLMBackend::OpenAI)
+        .model("gpt-4o")
+        .api_key(std::env::var("OPENAI_API_KEY").unwrap_or("sk-OPENAI".into()))
+        .schema(JudgeScorer::response_format())
+        .build()?;
+
+    // Create evaluator with multiple scoring functions and a judge
     let evaluator = LLMEvaluator::new(vec![anthropic, phind, deepseek])
         // First scoring function: Evaluate code quality and completeness
         .scoring(|response| {
@@ -84,11 +93,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
 
             score
-        });
+        })
+        // Judge: grade the answer against a rubric on a 1-10 scale
+        .judge(JudgeScorer::new(judge).rubric(
+            "The code must compile, use Actix Web idiomatically, expose both routes \
+             and explain how to run the service.",
+        ));
 
     // Define the evaluation prompt requesting a Rust microservice implementation
     let messages = vec![ChatMessage {
         role: ChatRole::User,
+        message_type: MessageType::Text,
         content: "\
             Create a Rust microservice using Actix Web. 
             It should have at least two routes:
@@ -102,12 +117,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }];
 
     // Run evaluation across all providers
-    let results: Vec<EvalResult> = evaluator.evaluate_chat(&messages)?;
+    let results: Vec<EvalResult> = evaluator.evaluate_chat(&messages).await?;
 
     // Display results with scores
     for (i, item) in results.iter().enumerate() {
         println!("\n=== LLM #{} ===", i);
         println!("Score: {:.2}", item.score);
+        if let Some(judgement) = &item.judgement {
+            println!("Judge: {:.1} - {}", judgement.score, judgement.rationale);
+        }
         println!("Response:\n{}", item.text);
         println!("================\n");
     }
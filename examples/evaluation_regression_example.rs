@@ -0,0 +1,58 @@
+//! Example demonstrating regression gating against a saved evaluation baseline
+//!
+//! This example runs fully offline:
+//! 1. Replay recorded answers with `ReplayProvider` instead of a live backend
+//! 2. Save the per-case scores of a first run as a baseline file
+//! 3. Compare a second run with degraded answers and exit non-zero on regression
+
+use rllm::evaluator::{
+    Baseline, EvalDataset, EvalReport, LLMEvaluator, NormalizedMatch, RegressionPolicy,
+    ReplayProvider,
+};
+
+async fn evaluate(
+    dataset: &EvalDataset,
+    replay: ReplayProvider,
+) -> Result<EvalReport, Box<dyn std::error::Error>> {
+    let evaluator =
+        LLMEvaluator::named(vec![("replay".to_string(), Box::new(replay))]).scorer(NormalizedMatch);
+    Ok(evaluator.dataset(dataset).run().await?)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let dataset = EvalDataset::from_jsonl("examples/data/eval_cases.jsonl")?.with_tag("geography");
+    let baseline_path = std::env::temp_dir().join("rllm_eval_baseline.json");
+
+    // First run: record the baseline
+    let good = ReplayProvider::new()
+        .response(
+            "What is the capital of France? Answer with one word.",
+            "Paris",
+        )
+        .response(
+            "What is the capital of Japan? Answer with one word.",
+            "Tokyo",
+        );
+    evaluate(&dataset, good)
+        .await?
+        .baseline()
+        .save(&baseline_path)?;
+
+    // Second run: one answer regressed
+    let degraded = ReplayProvider::new()
+        .response(
+            "What is the capital of France? Answer with one word.",
+            "Paris",
+        )
+        .response(
+            "What is the capital of Japan? Answer with one word.",
+            "Kyoto",
+        );
+    let report = evaluate(&dataset, degraded).await?;
+
+    let comparison = Baseline::load(&baseline_path)?.compare(&report, &RegressionPolicy::default());
+    print!("{}", comparison);
+
+    std::process::exit(comparison.exit_code());
+}
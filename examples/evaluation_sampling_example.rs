@@ -0,0 +1,74 @@
+//! Example demonstrating repeated sampling and statistical comparison of two providers
+//!
+//! This example shows how to:
+//! 1. Sample each provider several times on the same prompt
+//! 2. Print mean, standard deviation and confidence interval per scorer
+//! 3. Test whether the two providers differ significantly
+
+use rllm::{
+    builder::{LLMBackend, LLMBuilder},
+    chat::ChatMessage,
+    evaluator::{LLMEvaluator, StatsConfig, TOTAL_SCORE},
+};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let openai = LLMBuilder::new()
+        .backend(LLMBackend::OpenAI)
+        .api_key(std::env::var("OPENAI_API_KEY").unwrap_or("sk-OPENAI".into()))
+        .model("gpt-4o-mini")
+        .temperature(0.7)
+        .build()?;
+
+    let anthropic = LLMBuilder::new()
+        .backend(LLMBackend::Anthropic)
+        .api_key(std::env::var("ANTHROPIC_API_KEY").unwrap_or("anthro-key".into()))
+        .model("claude-3-5-haiku-20241022")
+        .temperature(0.7)
+        .build()?;
+
+    let evaluator = LLMEvaluator::named(vec![
+        ("openai".to_string(), openai),
+        ("anthropic".to_string(), anthropic),
+    ])
+    .named_scoring("mentions_ownership", |r| {
+        if r.to_lowercase().contains("ownership") {
+            1.0
+        } else {
+            0.0
+        }
+    })
+    .named_scoring("brevity", |r| {
+        1.0 / (1.0 + r.split_whitespace().count() as f32 / 50.0)
+    })
+    .samples(10)
+    .stats_config(StatsConfig {
+        confidence: 0.95,
+        ..Default::default()
+    });
+
+    let messages = vec![ChatMessage::user()
+        .content("Explain in two sentences what makes Rust memory safe.")
+        .build()];
+
+    let results = evaluator.evaluate_chat_sampled(&messages).await?;
+
+    for result in &results {
+        println!("=== {} ({} failed samples) ===", result.provider_id, result.errors.len());
+        for (scorer, stats) in &result.stats {
+            println!(
+                "{:>20}: mean={:.3} sd={:.3} ci=[{:.3}, {:.3}]",
+                scorer, stats.mean, stats.std_dev, stats.ci_low, stats.ci_high
+            );
+        }
+    }
+
+    if let Some(test) = evaluator.compare(&results[0], &results[1], TOTAL_SCORE) {
+        println!(
+            "openai - anthropic: diff={:.3} p={:.4} significant={}",
+            test.mean_diff, test.p_value, test.significant
+        );
+    }
+
+    Ok(())
+}
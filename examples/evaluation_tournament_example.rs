@@ -0,0 +1,74 @@
+//! Example demonstrating a pairwise tournament between registered providers
+//!
+//! This example shows how to:
+//! 1. Load tagged evaluation cases, tags being used as task types
+//! 2. Let a judge compare every pair of responses, with position swapping
+//! 3. Rank providers with Elo and Bradley-Terry ratings, overall and per task type
+
+use rllm::{
+    builder::{LLMBackend, LLMBuilder},
+    chain::LLMRegistryBuilder,
+    evaluator::{EvalDataset, LLMEvaluator, PairwiseJudge},
+};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let openai_key = std::env::var("OPENAI_API_KEY").unwrap_or("sk-OPENAI".into());
+
+    let registry = LLMRegistryBuilder::new()
+        .register(
+            "gpt-4o-mini",
+            LLMBuilder::new()
+                .backend(LLMBackend::OpenAI)
+                .api_key(openai_key.clone())
+                .model("gpt-4o-mini")
+                .build()?,
+        )
+        .register(
+            "claude-haiku",
+            LLMBuilder::new()
+                .backend(LLMBackend::Anthropic)
+                .api_key(std::env::var("ANTHROPIC_API_KEY").unwrap_or("anthro-key".into()))
+                .model("claude-3-5-haiku-20241022")
+                .build()?,
+        )
+        .register(
+            "deepseek",
+            LLMBuilder::new()
+                .backend(LLMBackend::DeepSeek)
+                .api_key(std::env::var("DEEPSEEK_API_KEY").unwrap_or("deepseek-key".into()))
+                .model("deepseek-chat")
+                .build()?,
+        )
+        .build();
+
+    // Judge comparing responses two by two
+    let judge = PairwiseJudge::new(
+        LLMBuilder::new()
+            .backend(LLMBackend::OpenAI)
+            .api_key(openai_key)
+            .model("gpt-4o")
+            .build()?,
+    );
+
+    let evaluator = LLMEvaluator::from_registry(registry);
+    let dataset = EvalDataset::from_jsonl("examples/data/eval_cases.jsonl")?;
+    let report = evaluator.tournament(&dataset, &judge).await?;
+
+    println!("Overall (Bradley-Terry):");
+    for rating in report.bradley_terry(None) {
+        println!("  {:>14}: {:.0}", rating.provider_id, rating.rating);
+    }
+
+    for task in report.tasks() {
+        println!("{} (Elo):", task);
+        for rating in report.elo(Some(&task), 32.0) {
+            println!(
+                "  {:>14}: {:.0} ({}W/{}L/{}T)",
+                rating.provider_id, rating.rating, rating.wins, rating.losses, rating.ties
+            );
+        }
+    }
+
+    Ok(())
+}
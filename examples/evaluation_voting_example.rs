@@ -0,0 +1,58 @@
+//! Example demonstrating self-consistency majority voting
+//!
+//! This example shows how to:
+//! 1. Sample two providers several times on the same question
+//! 2. Extract the final answer of each response with a regex
+//! 3. Print the majority answer, vote distribution and agreement ratio
+
+use rllm::{
+    builder::{LLMBackend, LLMBuilder},
+    chat::ChatMessage,
+    evaluator::{AnswerExtractor, SelfConsistency},
+};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let openai = LLMBuilder::new()
+        .backend(LLMBackend::OpenAI)
+        .api_key(std::env::var("OPENAI_API_KEY").unwrap_or("sk-OPENAI".into()))
+        .model("gpt-4o-mini")
+        .temperature(0.8)
+        .build()?;
+
+    let anthropic = LLMBuilder::new()
+        .backend(LLMBackend::Anthropic)
+        .api_key(std::env::var("ANTHROPIC_API_KEY").unwrap_or("anthro-key".into()))
+        .model("claude-3-5-haiku-20241022")
+        .temperature(0.8)
+        .build()?;
+
+    let vote = SelfConsistency::named(
+        vec![
+            ("openai".to_string(), openai),
+            ("anthropic".to_string(), anthropic),
+        ],
+        AnswerExtractor::regex(r"(?i)answer:\s*(-?\d+)")?,
+    )
+    .samples(5);
+
+    let messages = vec![ChatMessage::user()
+        .content(
+            "A train leaves at 14:35 and arrives at 17:10. How many minutes is the trip? \
+             Think step by step, then finish with 'Answer: <number>'.",
+        )
+        .build()];
+
+    let result = vote.vote(&messages).await?;
+    for (answer, count) in &result.votes {
+        println!("{:>6}: {} votes", answer, count);
+    }
+    println!(
+        "Majority: {:?} (agreement {:.0}%, {} failed samples)",
+        result.answer,
+        result.agreement * 100.0,
+        result.errors.len()
+    );
+
+    Ok(())
+}
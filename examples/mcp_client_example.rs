@@ -0,0 +1,47 @@
+// Import required modules from the RLLM library
+use rllm::{
+    mcp::McpClient,                      // MCP server connection
+    tools::{ToolExecutor, ToolRegistry}, // Tool registry and call execution
+    FunctionCall,
+    ToolCall,
+};
+use std::sync::Arc;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Start the local test server as a child process and connect to it over stdio.
+    // A remote server would use `McpClient::http("https://example.com/mcp")` instead.
+    let client =
+        Arc::new(McpClient::stdio("cargo", ["run", "-q", "--example", "mcp_test_server"]).await?);
+    let server = client.server_info();
+    println!("Connected to {} {}", server.name, server.version);
+
+    // Expose the server's tools as function tools, as sent to the model
+    let registry = client.register(ToolRegistry::new()).await?;
+    for tool in registry.tools() {
+        println!("Tool: {}", serde_json::to_string(tool)?);
+    }
+
+    // Route calls to the server, as a ToolRunner does for the calls made by the model
+    let calls = [
+        call("1", "echo", r#"{"text": "hello"}"#),
+        call("2", "add", r#"{"a": 2, "b": 40}"#),
+        call("3", "fail", "{}"),
+    ];
+    for record in ToolExecutor::new().execute(&registry, &calls).await {
+        println!("{}: {:?}", record.call.function.name, record.result);
+    }
+
+    Ok(())
+}
+
+fn call(id: &str, name: &str, arguments: &str) -> ToolCall {
+    ToolCall {
+        id: id.to_string(),
+        call_type: "function".to_string(),
+        function: FunctionCall {
+            name: name.to_string(),
+            arguments: arguments.to_string(),
+        },
+    }
+}
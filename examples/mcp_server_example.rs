@@ -0,0 +1,78 @@
+// Import required modules from the RLLM library
+use rllm::{
+    builder::{LLMBackend, LLMBuilder}, // Builder pattern components
+    chain::{LLMRegistryBuilder, MultiChainStepBuilder, MultiChainStepMode, MultiPromptChain},
+    mcp::{McpServer, RegistryTools}, // MCP server and registry tools
+    structured::JsonSchema,
+};
+use serde::{Deserialize, Serialize};
+
+/// Arguments of the summarize tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct Summarize {
+    /// Text to summarize
+    text: String,
+    /// Language of the summary, e.g. "French"
+    language: Option<String>,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Providers shared with the MCP clients
+    let llms = LLMRegistryBuilder::new()
+        .register(
+            "openai",
+            LLMBuilder::new()
+                .backend(LLMBackend::OpenAI)
+                .api_key(std::env::var("OPENAI_API_KEY").unwrap_or("sk-OPENAI".into()))
+                .model("gpt-4o-mini")
+                .build()?,
+        )
+        .register(
+            "anthro",
+            LLMBuilder::new()
+                .backend(LLMBackend::Anthropic)
+                .api_key(std::env::var("ANTHROPIC_API_KEY").unwrap_or("anthro-key".into()))
+                .model("claude-3-5-sonnet-20240620")
+                .build()?,
+        )
+        .build();
+
+    // One tool per provider, plus a summarize tool backed by a two-step chain
+    let tools = RegistryTools::new(llms).providers().chain::<Summarize, _>(
+        "summarize",
+        "Summarize a text in three sentences",
+        "summary",
+        |llms| {
+            MultiPromptChain::new(llms)
+                .input("language", "English")
+                .step(
+                    MultiChainStepBuilder::new(MultiChainStepMode::Chat)
+                        .provider_id("openai")
+                        .fallback("anthro")
+                        .id("points")
+                        .template("List the key points of this text:\n{{text}}")
+                        .build()
+                        .unwrap(),
+                )
+                .step(
+                    MultiChainStepBuilder::new(MultiChainStepMode::Chat)
+                        .provider_id("anthro")
+                        .fallback("openai")
+                        .id("summary")
+                        .template("Write a three sentence summary in {{language}} of:\n{{points}}")
+                        .build()
+                        .unwrap(),
+                )
+        },
+    )?;
+
+    // Serve the tools to an MCP client that started this program, e.g. an IDE agent
+    McpServer::new("rllm-pipelines", env!("CARGO_PKG_VERSION"))
+        .instructions("Team LLM pipelines: call summarize for long texts")
+        .tools(tools.build())
+        .serve_stdio()
+        .await?;
+
+    Ok(())
+}
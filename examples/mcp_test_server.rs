@@ -0,0 +1,99 @@
+//! Minimal MCP server over stdio, used to try the MCP client offline.
+//!
+//! It reads JSON-RPC messages as lines on its standard input and answers on its standard
+//! output. Its tools are `echo`, `add` (answering with structured content) and `fail`
+//! (always reporting an error). Run it with `cargo run --example mcp_test_server`.
+
+use std::io::{BufRead, Write};
+
+use serde_json::{json, Value};
+
+fn main() {
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+
+    for line in stdin.lock().lines() {
+        let Ok(line) = line else { break };
+        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        // Notifications have no id and get no response
+        let Some(id) = message.get("id").cloned() else {
+            continue;
+        };
+
+        let response = match handle(
+            message["method"].as_str().unwrap_or_default(),
+            &message["params"],
+        ) {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, error)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": error },
+            }),
+        };
+        writeln!(stdout, "{}", response).unwrap();
+        stdout.flush().unwrap();
+    }
+}
+
+fn handle(method: &str, params: &Value) -> Result<Value, (i64, String)> {
+    match method {
+        "initialize" => Ok(json!({
+            "protocolVersion": params["protocolVersion"],
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": "rllm-test-server", "version": "0.1.0" },
+            "instructions": "Tools for testing MCP clients",
+        })),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": [
+            {
+                "name": "echo",
+                "description": "Repeat a text",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": { "text": { "type": "string", "description": "Text to repeat" } },
+                    "required": ["text"],
+                },
+            },
+            {
+                "name": "add",
+                "description": "Add two numbers",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": { "a": { "type": "number" }, "b": { "type": "number" } },
+                    "required": ["a", "b"],
+                },
+            },
+            {
+                "name": "fail",
+                "description": "Always fail, to test error reporting",
+                "inputSchema": { "type": "object", "properties": {} },
+            },
+        ]})),
+        "tools/call" => {
+            let arguments = &params["arguments"];
+            match params["name"].as_str() {
+                Some("echo") => Ok(text_result(
+                    arguments["text"].as_str().unwrap_or_default(),
+                    false,
+                )),
+                Some("add") => match (arguments["a"].as_f64(), arguments["b"].as_f64()) {
+                    (Some(a), Some(b)) => Ok(json!({
+                        "content": [{ "type": "text", "text": format!("{}", a + b) }],
+                        "structuredContent": { "sum": a + b },
+                    })),
+                    _ => Ok(text_result("a and b must be numbers", true)),
+                },
+                Some("fail") => Ok(text_result("This tool always fails", true)),
+                _ => Err((-32602, format!("Unknown tool: {}", params["name"]))),
+            }
+        }
+        _ => Err((-32601, format!("Method not found: {}", method))),
+    }
+}
+
+fn text_result(text: &str, is_error: bool) -> Value {
+    json!({ "content": [{ "type": "text", "text": text }], "isError": is_error })
+}
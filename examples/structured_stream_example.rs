@@ -0,0 +1,32 @@
+// Import required modules from the RLLM library
+use futures::{stream, StreamExt};
+use rllm::json::{partial_json_stream, PartialJsonEvent}; // Incremental JSON parsing
+
+#[tokio::main]
+async fn main() {
+    // Text deltas as a streaming backend would deliver them
+    let deltas = [
+        "Here is the form:\n```json\n{\"name\": \"Gar",
+        "field\", \"species\": \"cat\", \"ag",
+        "e\": 4, \"hobbies\": [\"eating lasa",
+        "gna\", \"napping\"]}\n```",
+    ];
+
+    // Render form fields as soon as they are complete
+    let mut events = Box::pin(partial_json_stream(stream::iter(
+        deltas.into_iter().map(|delta| Ok(delta.to_string())),
+    )));
+    while let Some(event) = events.next().await {
+        match event {
+            Ok(PartialJsonEvent::Snapshot(value)) => println!("snapshot: {}", value),
+            Ok(PartialJsonEvent::FieldComplete { name, value }) => {
+                println!("field {} complete: {}", name, value)
+            }
+            Ok(PartialJsonEvent::ElementComplete { index, value }) => {
+                println!("element {} complete: {}", index, value)
+            }
+            Ok(PartialJsonEvent::Complete(value)) => println!("complete: {}", value),
+            Err(e) => eprintln!("Stream error: {}", e),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+// Import required modules from the RLLM library
+use rllm::{
+    builder::{LLMBackend, LLMBuilder}, // Builder components for LLM configuration
+    chat::ChatMessage,                 // Chat-related structures
+    structured::{JsonSchema, TypedChat}, // Typed chat and schema derivation
+};
+use serde::Deserialize;
+
+/// A pet described by the model
+#[derive(Deserialize, JsonSchema)]
+struct Pet {
+    /// Name of the pet
+    name: String,
+    /// Species, e.g. "cat"
+    species: String,
+    /// Age in years
+    age: u32,
+    /// Favorite activities
+    hobbies: Vec<String>,
+}
+
+/// Renders a pet as a sentence
+fn describe(pet: &Pet) -> String {
+    format!(
+        "{} the {}, {} years old, likes {}",
+        pet.name,
+        pet.species,
+        pet.age,
+        pet.hobbies.join(", ")
+    )
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let messages = vec![ChatMessage::user()
+        .content("Invent a pet for a children's story")
+        .build()];
+
+    // OpenAI supports structured output: the schema of `Pet` is sent as response format
+    let openai = LLMBuilder::new()
+        .backend(LLMBackend::OpenAI)
+        .api_key(std::env::var("OPENAI_API_KEY").unwrap_or("sk-TESTKEY".into()))
+        .model("gpt-4o-mini")
+        .validator_attempts(3) // Repair up to 3 attempts
+        .build_structured()?;
+    match openai.chat_typed::<Pet>(&messages).await {
+        Ok(pet) => println!("OpenAI: {}", describe(&pet)),
+        Err(e) => eprintln!("OpenAI error: {}", e),
+    }
+
+    // Anthropic is forced to call a tool taking the schema of `Pet` as input
+    let anthropic = LLMBuilder::new()
+        .backend(LLMBackend::Anthropic)
+        .api_key(std::env::var("ANTHROPIC_API_KEY").unwrap_or("anthro-key".into()))
+        .model("claude-3-5-sonnet-20240620")
+        .build_structured()?;
+    match anthropic.chat_typed::<Pet>(&messages).await {
+        Ok(pet) => println!("Anthropic: {}", describe(&pet)),
+        Err(e) => eprintln!("Anthropic error: {}", e),
+    }
+
+    // Other providers get the schema in the prompt, and their answer is validated
+    let deepseek = LLMBuilder::new()
+        .backend(LLMBackend::DeepSeek)
+        .api_key(std::env::var("DEEPSEEK_API_KEY").unwrap_or("sk-TESTKEY".into()))
+        .model("deepseek-chat")
+        .build()?;
+    match deepseek.chat_typed::<Pet>(&messages).await {
+        Ok(pet) => println!("DeepSeek: {}", describe(&pet)),
+        Err(e) => eprintln!("DeepSeek error: {}", e),
+    }
+
+    Ok(())
+}
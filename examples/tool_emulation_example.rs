@@ -0,0 +1,56 @@
+// Import required modules from the RLLM library
+use rllm::{
+    builder::{FunctionBuilder, LLMBackend, LLMBuilder, ParamBuilder}, // LLM and tool configuration
+    chat::ChatMessage,                                                // Chat-related structures
+    tools::{ToolError, ToolRegistry, ToolRunner}, // Tool handlers and execution loop
+};
+use serde_json::json;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Phind has no native function calling: tools are described in the system prompt and
+    // calls are parsed from the response text. Ollama models without tool support can opt in
+    // with `.emulate_tools(true)`.
+    let llm = LLMBuilder::new()
+        .backend(LLMBackend::Phind)
+        .model("Phind-70B")
+        .max_tokens(512)
+        .build()?;
+
+    // The same registry works with native and emulated function calling
+    let registry = ToolRegistry::new().function(
+        FunctionBuilder::new("get_weather")
+            .description("Get the current weather in a city")
+            .param(
+                ParamBuilder::new("city")
+                    .type_of("string")
+                    .description("City name, e.g. 'Paris'"),
+            )
+            .required(vec!["city".to_string()]),
+        |args| async move {
+            match args["city"].as_str() {
+                Some("Paris") => Ok(json!({ "forecast": "sunny", "celsius": 24 })),
+                Some(city) => Err(ToolError::new(format!("No weather data for {}", city))),
+                None => Err(ToolError::new("The city argument is required")),
+            }
+        },
+    );
+
+    let run = ToolRunner::new(llm.as_ref(), registry)
+        .max_iterations(4)
+        .run(&[ChatMessage::user()
+            .content("Should I take an umbrella in Paris today?")
+            .build()])
+        .await?;
+
+    // Calls parsed from the text are reported like native tool calls
+    for record in &run.calls {
+        println!(
+            "{}({}) -> {:?}",
+            record.call.function.name, record.call.function.arguments, record.result
+        );
+    }
+    println!("Answer: {}", run.answer.unwrap_or_default());
+
+    Ok(())
+}
@@ -0,0 +1,67 @@
+// Import required modules from the RLLM library
+use rllm::{
+    builder::{LLMBackend, LLMBuilder}, // Builder components for LLM configuration
+    chat::ChatMessage,                 // Chat-related structures
+    tools::{tool, ToolError, ToolRegistry, ToolRunner}, // Tool macro and execution loop
+};
+use serde::Serialize;
+
+/// Forecast returned by the weather tool
+#[derive(Serialize)]
+struct Forecast {
+    city: String,
+    days: Vec<String>,
+}
+
+/// Get the weather forecast for a city
+///
+/// # Arguments
+///
+/// * `city` - City name, e.g. "Paris"
+/// * `days` - Number of days to forecast, 1 by default
+#[tool]
+async fn get_weather(city: String, days: Option<u32>) -> Result<Forecast, ToolError> {
+    if city.is_empty() {
+        return Err(ToolError::new("city must not be empty"));
+    }
+    let days = (0..days.unwrap_or(1))
+        .map(|day| if day % 2 == 0 { "sunny" } else { "cloudy" }.to_string())
+        .collect();
+    Ok(Forecast { city, days })
+}
+
+/// Convert a temperature from Celsius to Fahrenheit
+///
+/// # Arguments
+///
+/// * `celsius` - Temperature in degrees Celsius
+#[tool(name = "to_fahrenheit")]
+fn convert_temperature(celsius: f64) -> f64 {
+    celsius * 9.0 / 5.0 + 32.0
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // The generated definitions carry the schema derived from the parameters
+    let registry = ToolRegistry::new()
+        .register(get_weather_tool())
+        .register(convert_temperature_tool());
+    for tool in registry.tools() {
+        println!("{}", serde_json::to_string_pretty(tool)?);
+    }
+
+    let llm = LLMBuilder::new()
+        .backend(LLMBackend::OpenAI)
+        .api_key(std::env::var("OPENAI_API_KEY").unwrap_or("sk-TESTKEY".into()))
+        .model("gpt-4o-mini")
+        .build()?;
+
+    let messages = vec![ChatMessage::user()
+        .content("What's the weather in Paris for the next 3 days? It is 21°C, how much in °F?")
+        .build()];
+    match ToolRunner::new(llm.as_ref(), registry).run(&messages).await {
+        Ok(run) => println!("Answer: {}", run.answer.unwrap_or_default()),
+        Err(e) => eprintln!("Tool run error: {}", e),
+    }
+    Ok(())
+}
@@ -0,0 +1,77 @@
+// Import required modules from the RLLM library
+use rllm::{
+    builder::{FunctionBuilder, LLMBackend, LLMBuilder, ParamBuilder}, // LLM and tool configuration
+    chat::ChatMessage,                                                // Chat-related structures
+    tools::{ToolError, ToolRegistry, ToolRunner}, // Tool handlers and execution loop
+};
+use serde_json::json;
+use std::time::Duration;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Initialize and configure the LLM client
+    let llm = LLMBuilder::new()
+        .backend(LLMBackend::OpenAI)
+        .api_key(std::env::var("OPENAI_API_KEY").unwrap_or("sk-TESTKEY".into()))
+        .model("gpt-4o-mini")
+        .build()?;
+
+    // Register the tools the model may call, with their Rust handlers
+    let registry = ToolRegistry::new()
+        .function(
+            FunctionBuilder::new("get_weather")
+                .description("Get the current weather in a city")
+                .param(
+                    ParamBuilder::new("city")
+                        .type_of("string")
+                        .description("City name, e.g. 'Paris'"),
+                )
+                .required(vec!["city".to_string()]),
+            |args| async move {
+                match args["city"].as_str() {
+                    Some("Paris") => Ok(json!({ "forecast": "sunny", "celsius": 24 })),
+                    Some("London") => Ok(json!({ "forecast": "rain", "celsius": 17 })),
+                    // Errors are reported to the model, which can try another city
+                    Some(city) => Err(ToolError::new(format!("No weather data for {}", city))
+                        .details(json!({ "known_cities": ["Paris", "London"] }))),
+                    None => Err(ToolError::new("The city argument is required")),
+                }
+            },
+        )
+        .function(
+            FunctionBuilder::new("convert_temperature")
+                .description("Convert a temperature from Celsius to Fahrenheit")
+                .param(ParamBuilder::new("celsius").type_of("number"))
+                .required(vec!["celsius".to_string()]),
+            |args| async move {
+                let celsius = args["celsius"]
+                    .as_f64()
+                    .ok_or(ToolError::new("celsius must be a number"))?;
+                Ok(json!({ "fahrenheit": celsius * 9.0 / 5.0 + 32.0 }))
+            },
+        );
+
+    // Loop chat -> tool calls -> results until the model answers
+    let messages = vec![ChatMessage::user()
+        .content("Is it warmer in Paris or London today? Answer in Fahrenheit.")
+        .build()];
+    let run = ToolRunner::new(llm.as_ref(), registry)
+        .max_iterations(6)
+        .max_tool_calls(10)
+        .concurrency(4) // Run up to 4 calls of a response at once
+        .call_timeout(Duration::from_secs(5)) // Report slow calls as tool errors
+        .run(&messages)
+        .await?;
+
+    for record in &run.calls {
+        println!(
+            "{}({}) -> {}",
+            record.call.function.name,
+            record.call.function.arguments,
+            record.to_tool_result().function.arguments
+        );
+    }
+    println!("Stopped after {} requests: {:?}", run.iterations, run.stop);
+    println!("Answer: {}", run.answer.unwrap_or_default());
+    Ok(())
+}
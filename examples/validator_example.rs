@@ -1,10 +1,13 @@
 // Import required modules from the RLLM library
 use rllm::{
     builder::{LLMBackend, LLMBuilder}, // Builder components for LLM configuration
-    chat::{ChatMessage, ChatRole},     // Chat-related structures
+    chat::ChatMessage,                 // Chat-related structures
+    validation::{json_schema, RepairStrategy, ValidationAttempt}, // Validators and repair loop
 };
+use serde_json::json;
 
-fn main() {
+#[tokio::main]
+async fn main() {
     // Retrieve Anthropic API key from environment variable or use fallback
     let api_key = std::env::var("ANTHROPIC_API_KEY").unwrap_or("anthro-key".into());
 
@@ -16,27 +19,38 @@ fn main() {
         .max_tokens(512) // Limit response length
         .temperature(0.7) // Control response randomness
         .stream(false) // Disable streaming responses
-        .validator(|resp| {
-            // Add JSON validation
-            serde_json::from_str::<serde_json::Value>(resp)
-                .map(|_| ())
-                .map_err(|e| e.to_string())
-        })
-        .validator_attempts(3) // Allow up to 3 retries on validation failure
+        .validator(
+            // Require a JSON object with string `name` and `color` fields
+            json_schema(&json!({
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "color": { "type": "string" }
+                },
+                "required": ["name", "color"]
+            }))
+            .expect("Invalid schema"),
+        )
+        .validator_attempts(3) // Allow up to 3 attempts
+        .repair_strategy(RepairStrategy::Conversation) // Show the model its invalid output
+        .repair_template("Invalid answer: {{error}}. Answer again with the JSON object only.")
         .build()
-        .expect("Failed to build LLM (Phind)");
+        .expect("Failed to build LLM (Anthropic)");
 
     // Prepare the chat message requesting JSON output
-    let messages = vec![
-        ChatMessage {
-            role: ChatRole::User,
-            content: "Please give me a valid JSON describing a cat named Garfield, color 'orange'. with format {name: string, color: string}. Return only the JSON, no other text".into(),
-        },
-    ];
+    let messages = vec![ChatMessage::user()
+        .content("Please give me a valid JSON describing a cat named Garfield, color 'orange'. with format {name: string, color: string}. Return only the JSON, no other text")
+        .build()];
 
     // Send chat request and handle the response
-    match llm.chat(&messages) {
-        Ok(text) => println!("{}", text),
-        Err(e) => eprintln!("Chat error: {}", e),
+    match llm.chat(&messages).await {
+        Ok(response) => println!("{}", response),
+        Err(e) => {
+            eprintln!("Chat error: {}", e);
+            // Every rejected output is kept in the error
+            for attempt in ValidationAttempt::from_error(&e).unwrap_or_default() {
+                eprintln!("rejected: {:?} ({})", attempt.output, attempt.error);
+            }
+        }
     }
 }
@@ -0,0 +1,387 @@
+//! Procedural macros for rllm.
+//!
+//! [`macro@tool`] turns a documented Rust function into an rllm tool definition, so the
+//! parameter schema sent to the model always matches the implementation. Enable the
+//! `macros` feature of rllm and use it as `rllm::tools::tool`.
+
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    parse::Parser, parse_macro_input, punctuated::Punctuated, Attribute, Error, Expr, ExprLit,
+    FnArg, Ident, ItemFn, Lit, Meta, MetaNameValue, Pat, ReturnType, Token, Type,
+};
+
+/// Defines a tool from a function.
+///
+/// Next to the function, generates `<name>_tool() -> rllm::tools::ToolDefinition`, to be
+/// registered with `ToolRegistry::register`. The doc comment describes the tool, and the
+/// entries of its `# Arguments` section describe the parameters. Parameters must be owned
+/// types implementing `Deserialize` and `JsonSchema`; `Option` parameters are optional.
+///
+/// The function may be async or not, and return any `Serialize` value, or a `Result` whose
+/// error converts into `ToolError`. Arguments that do not match the parameters are reported
+/// to the model as a tool error. `#[tool(name = "...")]` overrides the tool name.
+///
+/// ```ignore
+/// use rllm::tools::{tool, ToolError, ToolRegistry};
+///
+/// /// Get the current weather in a city
+/// ///
+/// /// # Arguments
+/// ///
+/// /// * `city` - City name, e.g. "Paris"
+/// /// * `days` - Number of forecast days
+/// #[tool]
+/// async fn get_weather(city: String, days: Option<u32>) -> Result<Vec<String>, ToolError> {
+///     Ok(vec!["sunny".to_string(); days.unwrap_or(1) as usize])
+/// }
+///
+/// let registry = ToolRegistry::new().register(get_weather_tool());
+/// ```
+#[proc_macro_attribute]
+pub fn tool(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let function = parse_macro_input!(item as ItemFn);
+    match expand(attr.into(), &function) {
+        Ok(tokens) => quote!(#function #tokens).into(),
+        Err(error) => {
+            let error = error.to_compile_error();
+            quote!(#function #error).into()
+        }
+    }
+}
+
+fn expand(
+    attr: proc_macro2::TokenStream,
+    function: &ItemFn,
+) -> Result<proc_macro2::TokenStream, Error> {
+    let sig = &function.sig;
+    let fn_name = &sig.ident;
+    if !sig.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &sig.generics,
+            "tools cannot have generic parameters",
+        ));
+    }
+
+    let name = tool_name(attr)?.unwrap_or_else(|| fn_name.unraw());
+    let docs = Docs::parse(&function.attrs);
+    if docs.description.is_empty() {
+        return Err(Error::new_spanned(
+            fn_name,
+            "tools need a doc comment describing them to the model",
+        ));
+    }
+    let description = &docs.description;
+
+    let mut idents = Vec::new();
+    let mut types: Vec<&Type> = Vec::new();
+    let mut field_docs = Vec::new();
+    for input in &sig.inputs {
+        let FnArg::Typed(arg) = input else {
+            return Err(Error::new_spanned(input, "tools cannot take `self`"));
+        };
+        let Pat::Ident(pat) = arg.pat.as_ref() else {
+            return Err(Error::new_spanned(
+                &arg.pat,
+                "tool parameters must be plain identifiers",
+            ));
+        };
+        let ident = &pat.ident;
+        let doc = docs
+            .params
+            .iter()
+            .find(|(param, _)| *param == ident.unraw())
+            .map(|(_, doc)| quote!(#[doc = #doc]));
+        idents.push(ident.clone());
+        types.push(&arg.ty);
+        field_docs.push(doc);
+    }
+
+    let call_await = sig.asyncness.map(|_| quote!(.await));
+    let returns_result = match &sig.output {
+        ReturnType::Type(_, ty) => match ty.as_ref() {
+            Type::Path(path) => path
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "Result"),
+            _ => false,
+        },
+        ReturnType::Default => false,
+    };
+    let convert = if returns_result {
+        quote!(::rllm::tools::__private::into_tool_result)
+    } else {
+        quote!(::rllm::tools::__private::to_tool_value)
+    };
+
+    let vis = &function.vis;
+    let definition_fn = format_ident!("{}_tool", fn_name.unraw());
+    let definition_doc = format!(
+        "Tool definition of [`{}`], generated by `#[tool]`",
+        fn_name.unraw()
+    );
+    let args = format_ident!("__ToolArgs");
+
+    Ok(quote! {
+        #[doc = #definition_doc]
+        #vis fn #definition_fn() -> ::rllm::tools::ToolDefinition {
+            #[derive(
+                ::rllm::tools::__private::serde::Deserialize,
+                ::rllm::tools::__private::schemars::JsonSchema
+            )]
+            #[serde(crate = "::rllm::tools::__private::serde")]
+            #[schemars(crate = "::rllm::tools::__private::schemars")]
+            struct #args {
+                #( #field_docs #idents: #types, )*
+            }
+
+            ::rllm::tools::ToolDefinition::typed::<#args, _, _>(
+                #name,
+                #description,
+                |args: #args| async move {
+                    let #args { #( #idents ),* } = args;
+                    #convert(#fn_name( #( #idents ),* ) #call_await)
+                },
+            )
+        }
+    })
+}
+
+/// Reads the `name = "..."` argument of the attribute, if any
+fn tool_name(attr: proc_macro2::TokenStream) -> Result<Option<String>, Error> {
+    let metas = Punctuated::<MetaNameValue, Token![,]>::parse_terminated.parse2(attr)?;
+    let mut name = None;
+    for meta in metas {
+        match (&meta.value, meta.path.is_ident("name")) {
+            (
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(value),
+                    ..
+                }),
+                true,
+            ) => name = Some(value.value()),
+            _ => return Err(Error::new_spanned(meta, "expected `name = \"...\"`")),
+        }
+    }
+    Ok(name)
+}
+
+/// Tool and parameter descriptions read from a doc comment
+struct Docs {
+    /// Text before the first section heading
+    description: String,
+    /// Entries of the `# Arguments` section
+    params: Vec<(String, String)>,
+}
+
+impl Docs {
+    fn parse(attrs: &[Attribute]) -> Self {
+        let lines: Vec<String> = attrs
+            .iter()
+            .filter_map(|attr| match &attr.meta {
+                Meta::NameValue(MetaNameValue {
+                    path,
+                    value:
+                        Expr::Lit(ExprLit {
+                            lit: Lit::Str(doc), ..
+                        }),
+                    ..
+                }) if path.is_ident("doc") => Some(doc.value()),
+                _ => None,
+            })
+            .flat_map(|doc| {
+                doc.lines()
+                    .map(|line| line.strip_prefix(' ').unwrap_or(line).to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        let mut description = Vec::new();
+        let mut params: Vec<(String, String)> = Vec::new();
+        let mut section: Option<String> = None;
+        for line in &lines {
+            let trimmed = line.trim();
+            if let Some(heading) = trimmed.strip_prefix('#') {
+                section = Some(heading.trim_start_matches('#').trim().to_lowercase());
+                continue;
+            }
+            match section.as_deref() {
+                None => description.push(trimmed),
+                Some("arguments" | "parameters") => {
+                    if let Some(param) = parse_param(trimmed) {
+                        params.push(param);
+                    } else if let Some((_, doc)) = params.last_mut() {
+                        if !trimmed.is_empty() {
+                            doc.push(' ');
+                            doc.push_str(trimmed);
+                        }
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+
+        Docs {
+            description: description.join("\n").trim().to_string(),
+            params,
+        }
+    }
+}
+
+/// Parses an argument entry such as ``* `city` - City name``
+fn parse_param(line: &str) -> Option<(String, String)> {
+    let rest = line.strip_prefix(['*', '-'])?.trim_start();
+    let rest = rest.strip_prefix('`')?;
+    let (name, rest) = rest.split_once('`')?;
+    let doc = rest.trim_start().trim_start_matches(['-', ':']).trim();
+    Some((name.to_string(), doc.to_string()))
+}
+
+/// Identifier without its `r#` prefix
+trait Unraw {
+    fn unraw(&self) -> String;
+}
+
+impl Unraw for Ident {
+    fn unraw(&self) -> String {
+        let name = self.to_string();
+        name.strip_prefix("r#").map(str::to_string).unwrap_or(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    fn docs(function: ItemFn) -> Docs {
+        Docs::parse(&function.attrs)
+    }
+
+    #[test]
+    fn reads_description_and_arguments() {
+        let docs = docs(parse_quote! {
+            /// Get the current weather
+            /// in a city
+            ///
+            /// # Arguments
+            ///
+            /// * `city` - City name, e.g. "Paris"
+            /// - `days`: Number of forecast days
+            ///
+            /// # Errors
+            ///
+            /// * `unknown` - Not an argument
+            fn get_weather() {}
+        });
+
+        assert_eq!(docs.description, "Get the current weather\nin a city");
+        assert_eq!(
+            docs.params,
+            vec![
+                ("city".to_string(), "City name, e.g. \"Paris\"".to_string()),
+                ("days".to_string(), "Number of forecast days".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn joins_continuation_lines() {
+        let docs = docs(parse_quote! {
+            /// Search documents
+            ///
+            /// ## Parameters
+            /// * `query` - Words to look for,
+            ///   matched case-insensitively
+            ///
+            ///   in titles only
+            fn search() {}
+        });
+
+        assert_eq!(docs.description, "Search documents");
+        assert_eq!(
+            docs.params,
+            vec![(
+                "query".to_string(),
+                "Words to look for, matched case-insensitively in titles only".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn reads_block_doc_attributes() {
+        let docs = docs(parse_quote! {
+            #[doc = " Adds numbers\n\n # Arguments\n * `a` - First term"]
+            #[inline]
+            fn add() {}
+        });
+
+        assert_eq!(docs.description, "Adds numbers");
+        assert_eq!(
+            docs.params,
+            vec![("a".to_string(), "First term".to_string())]
+        );
+    }
+
+    #[test]
+    fn parses_argument_entries() {
+        assert_eq!(
+            parse_param("* `a` - First"),
+            Some(("a".to_string(), "First".to_string()))
+        );
+        assert_eq!(
+            parse_param("- `b`:Second"),
+            Some(("b".to_string(), "Second".to_string()))
+        );
+        assert_eq!(parse_param("* `c`"), Some(("c".to_string(), String::new())));
+        assert_eq!(parse_param("`d` - no bullet"), None);
+        assert_eq!(parse_param("* e - no backticks"), None);
+    }
+
+    #[test]
+    fn matches_raw_identifiers() {
+        let function: ItemFn = parse_quote! {
+            /// Runs a query
+            ///
+            /// # Arguments
+            ///
+            /// * `type` - Kind of query
+            fn r#match(r#type: String) {}
+        };
+        assert_eq!(function.sig.ident.unraw(), "match");
+
+        let tokens = expand(proc_macro2::TokenStream::new(), &function)
+            .unwrap()
+            .to_string();
+        assert!(tokens.contains("fn match_tool"), "{}", tokens);
+        assert!(tokens.contains("\"match\""), "{}", tokens);
+        assert!(
+            tokens.contains("# [doc = \"Kind of query\"] r#type"),
+            "{}",
+            tokens
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_tools() {
+        let undocumented: ItemFn = parse_quote!(
+            fn f() {}
+        );
+        let error = expand(proc_macro2::TokenStream::new(), &undocumented).unwrap_err();
+        assert!(error.to_string().contains("doc comment"));
+
+        let generic: ItemFn = parse_quote! {
+            /// Generic
+            fn f<T>(value: T) {}
+        };
+        assert!(expand(proc_macro2::TokenStream::new(), &generic).is_err());
+
+        assert_eq!(
+            tool_name(quote!(name = "custom")).unwrap().as_deref(),
+            Some("custom")
+        );
+        assert!(tool_name(quote!(label = "custom")).is_err());
+    }
+}
@@ -0,0 +1,548 @@
+//! Builder module for configuring and instantiating LLM providers.
+//!
+//! [`LLMBuilder`] mirrors the builder of the base crate. It records the provider
+//! configuration and replays it into the base builder on [`LLMBuilder::build`], so the
+//! same configuration can also back a [`StructuredLLM`] requesting a different output
+//! schema per call. Validation is handled here, so rejected responses are repaired with a
+//! configurable [`RepairStrategy`] instead of plain retries.
+
+use std::collections::HashMap;
+
+use crate::{
+    chat::{
+        FunctionTool, ParameterProperty, ParametersSchema, ReasoningEffort, StructuredOutputFormat,
+        Tool, ToolChoice,
+    },
+    error::LLMError,
+    structured::{self, StructuredLLM},
+    tools,
+    validation::{Repair, RepairStrategy, ValidatedLLM},
+    LLMProvider,
+};
+
+pub use llm::builder::{LLMBackend, ValidatorFn};
+
+/// Number of attempts of typed chats when no validator attempts are configured
+const DEFAULT_STRUCTURED_ATTEMPTS: usize = 3;
+
+/// Provider settings recorded by [`LLMBuilder`], replayable into the base crate builder
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ProviderConfig {
+    pub(crate) backend: Option<LLMBackend>,
+    api_key: Option<String>,
+    base_url: Option<String>,
+    model: Option<String>,
+    max_tokens: Option<u32>,
+    temperature: Option<f32>,
+    pub(crate) system: Option<String>,
+    timeout_seconds: Option<u64>,
+    stream: Option<bool>,
+    top_p: Option<f32>,
+    top_k: Option<u32>,
+    embedding_encoding_format: Option<String>,
+    embedding_dimensions: Option<u32>,
+    pub(crate) reasoning: Option<bool>,
+    reasoning_effort: Option<String>,
+    reasoning_budget_tokens: Option<u32>,
+    pub(crate) json_schema: Option<StructuredOutputFormat>,
+    pub(crate) tools: Vec<Tool>,
+    pub(crate) tool_choice: Option<ToolChoice>,
+    enable_parallel_tool_use: Option<bool>,
+    api_version: Option<String>,
+    deployment_id: Option<String>,
+    pub(crate) emulate_tools: Option<bool>,
+}
+
+impl ProviderConfig {
+    /// Builds the provider with the base crate builder
+    ///
+    /// Anthropic has no JSON schema response format: a schema is requested through a
+    /// forced tool call instead, whose input is returned as the response text. Phind has no
+    /// function calling: tools are described in the prompt and calls parsed from the text.
+    pub(crate) fn build(&self) -> Result<Box<dyn LLMProvider>, LLMError> {
+        let emulate = matches!(self.backend, Some(LLMBackend::Phind));
+        if self.emulate_tools.unwrap_or(emulate) {
+            return tools::build_emulated(self);
+        }
+        if let (Some(LLMBackend::Anthropic), Some(format)) = (&self.backend, &self.json_schema) {
+            return structured::build_with_tool(self, format);
+        }
+
+        let mut builder = llm::builder::LLMBuilder::new();
+        if let Some(backend) = &self.backend {
+            builder = builder.backend(backend.clone());
+        }
+        if let Some(api_key) = &self.api_key {
+            builder = builder.api_key(api_key);
+        }
+        if let Some(base_url) = &self.base_url {
+            builder = builder.base_url(base_url);
+        }
+        if let Some(model) = &self.model {
+            builder = builder.model(model);
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            builder = builder.max_tokens(max_tokens);
+        }
+        if let Some(temperature) = self.temperature {
+            builder = builder.temperature(temperature);
+        }
+        if let Some(system) = &self.system {
+            builder = builder.system(system);
+        }
+        if let Some(timeout_seconds) = self.timeout_seconds {
+            builder = builder.timeout_seconds(timeout_seconds);
+        }
+        if let Some(stream) = self.stream {
+            builder = builder.stream(stream);
+        }
+        if let Some(top_p) = self.top_p {
+            builder = builder.top_p(top_p);
+        }
+        if let Some(top_k) = self.top_k {
+            builder = builder.top_k(top_k);
+        }
+        if let Some(format) = &self.embedding_encoding_format {
+            builder = builder.embedding_encoding_format(format);
+        }
+        if let Some(dimensions) = self.embedding_dimensions {
+            builder = builder.embedding_dimensions(dimensions);
+        }
+        if let Some(reasoning) = self.reasoning {
+            builder = builder.reasoning(reasoning);
+        }
+        if let Some(effort) = &self.reasoning_effort {
+            builder = builder.reasoning_effort(match effort.as_str() {
+                "low" => ReasoningEffort::Low,
+                "high" => ReasoningEffort::High,
+                _ => ReasoningEffort::Medium,
+            });
+        }
+        if let Some(budget) = self.reasoning_budget_tokens {
+            builder = builder.reasoning_budget_tokens(budget);
+        }
+        if let Some(schema) = &self.json_schema {
+            builder = builder.schema(schema.clone());
+        }
+        for tool in &self.tools {
+            builder = builder.function(to_base_function(tool));
+        }
+        if let Some(choice) = &self.tool_choice {
+            builder = builder.tool_choice(choice.clone());
+        }
+        if let Some(enable) = self.enable_parallel_tool_use {
+            builder = builder.enable_parallel_tool_use(enable);
+        }
+        if let Some(api_version) = &self.api_version {
+            builder = builder.api_version(api_version);
+        }
+        if let Some(deployment_id) = &self.deployment_id {
+            builder = builder.deployment_id(deployment_id);
+        }
+        builder.build()
+    }
+}
+
+/// Converts a tool definition into a function builder of the base crate
+fn to_base_function(tool: &Tool) -> llm::builder::FunctionBuilder {
+    let mut function = llm::builder::FunctionBuilder::new(&tool.function.name)
+        .description(&tool.function.description);
+    for (name, property) in &tool.function.parameters.properties {
+        let mut param = llm::builder::ParamBuilder::new(name)
+            .type_of(&property.property_type)
+            .description(&property.description);
+        if let Some(items) = &property.items {
+            param = param.items(items.as_ref().clone());
+        }
+        if let Some(values) = &property.enum_list {
+            param = param.enum_values(values.clone());
+        }
+        function = function.param(param);
+    }
+    function.required(tool.function.parameters.required.clone())
+}
+
+/// Builder for configuring and instantiating LLM providers.
+///
+/// Provides a fluent interface for setting various configuration options
+/// like model selection, API keys, generation parameters, etc.
+#[derive(Default)]
+pub struct LLMBuilder {
+    /// Provider configuration
+    config: ProviderConfig,
+    /// Optional validation function for response content
+    validator: Option<Box<ValidatorFn>>,
+    /// Number of attempts when validation fails
+    validator_attempts: usize,
+    /// How rejected responses are reported back to the model
+    repair_strategy: RepairStrategy,
+    /// Template of the repair message, see [`ValidatedLLM::repair_template`]
+    repair_template: Option<String>,
+}
+
+impl LLMBuilder {
+    /// Creates a new empty builder instance with default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the backend provider to use.
+    pub fn backend(mut self, backend: LLMBackend) -> Self {
+        self.config.backend = Some(backend);
+        self
+    }
+
+    /// Sets the API key for authentication.
+    pub fn api_key(mut self, key: impl Into<String>) -> Self {
+        self.config.api_key = Some(key.into());
+        self
+    }
+
+    /// Sets the base URL for API requests.
+    pub fn base_url(mut self, url: impl Into<String>) -> Self {
+        self.config.base_url = Some(url.into());
+        self
+    }
+
+    /// Sets the model identifier to use.
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.config.model = Some(model.into());
+        self
+    }
+
+    /// Sets the maximum number of tokens to generate.
+    pub fn max_tokens(mut self, max_tokens: u32) -> Self {
+        self.config.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Sets the temperature for controlling response randomness (0.0-1.0).
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.config.temperature = Some(temperature);
+        self
+    }
+
+    /// Sets the system prompt/context.
+    pub fn system(mut self, system: impl Into<String>) -> Self {
+        self.config.system = Some(system.into());
+        self
+    }
+
+    /// Sets the reasoning effort.
+    pub fn reasoning_effort(mut self, reasoning_effort: ReasoningEffort) -> Self {
+        self.config.reasoning_effort = Some(reasoning_effort.to_string());
+        self
+    }
+
+    /// Sets the reasoning flag.
+    pub fn reasoning(mut self, reasoning: bool) -> Self {
+        self.config.reasoning = Some(reasoning);
+        self
+    }
+
+    /// Sets the reasoning budget tokens.
+    pub fn reasoning_budget_tokens(mut self, reasoning_budget_tokens: u32) -> Self {
+        self.config.reasoning_budget_tokens = Some(reasoning_budget_tokens);
+        self
+    }
+
+    /// Sets the request timeout in seconds.
+    pub fn timeout_seconds(mut self, timeout_seconds: u64) -> Self {
+        self.config.timeout_seconds = Some(timeout_seconds);
+        self
+    }
+
+    /// Enables or disables streaming responses.
+    pub fn stream(mut self, stream: bool) -> Self {
+        self.config.stream = Some(stream);
+        self
+    }
+
+    /// Sets the top-p (nucleus) sampling parameter.
+    pub fn top_p(mut self, top_p: f32) -> Self {
+        self.config.top_p = Some(top_p);
+        self
+    }
+
+    /// Sets the top-k sampling parameter.
+    pub fn top_k(mut self, top_k: u32) -> Self {
+        self.config.top_k = Some(top_k);
+        self
+    }
+
+    /// Sets the encoding format for embeddings.
+    pub fn embedding_encoding_format(
+        mut self,
+        embedding_encoding_format: impl Into<String>,
+    ) -> Self {
+        self.config.embedding_encoding_format = Some(embedding_encoding_format.into());
+        self
+    }
+
+    /// Sets the dimensions for embeddings.
+    pub fn embedding_dimensions(mut self, embedding_dimensions: u32) -> Self {
+        self.config.embedding_dimensions = Some(embedding_dimensions);
+        self
+    }
+
+    /// Sets the JSON schema for structured output.
+    pub fn schema(mut self, schema: impl Into<StructuredOutputFormat>) -> Self {
+        self.config.json_schema = Some(schema.into());
+        self
+    }
+
+    /// Sets a validation function to verify LLM responses.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - Function that takes a response string and returns Ok(()) if valid, or Err with error message if invalid
+    pub fn validator<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.validator = Some(Box::new(f));
+        self
+    }
+
+    /// Sets the number of attempts for validation failures.
+    ///
+    /// # Arguments
+    ///
+    /// * `attempts` - Maximum number of times to try generating a valid response
+    pub fn validator_attempts(mut self, attempts: usize) -> Self {
+        self.validator_attempts = attempts;
+        self
+    }
+
+    /// Sets how a rejected response is reported back to the model before the next attempt.
+    ///
+    /// Defaults to [`RepairStrategy::Conversation`].
+    pub fn repair_strategy(mut self, strategy: RepairStrategy) -> Self {
+        self.repair_strategy = strategy;
+        self
+    }
+
+    /// Sets the repair message sent after a rejected response.
+    ///
+    /// `{{error}}` is replaced by the validator message and `{{output}}` by the rejected
+    /// response.
+    pub fn repair_template(mut self, template: impl Into<String>) -> Self {
+        self.repair_template = Some(template.into());
+        self
+    }
+
+    /// Adds a function tool to the builder
+    pub fn function(mut self, function_builder: FunctionBuilder) -> Self {
+        self.config.tools.push(function_builder.build());
+        self
+    }
+
+    /// Enable parallel tool use
+    pub fn enable_parallel_tool_use(mut self, enable: bool) -> Self {
+        self.config.enable_parallel_tool_use = Some(enable);
+        self
+    }
+
+    /// Set tool choice.  Note that if the choice is given as Tool(name), and that
+    /// tool isn't available, the builder will fail.
+    pub fn tool_choice(mut self, choice: ToolChoice) -> Self {
+        self.config.tool_choice = Some(choice);
+        self
+    }
+
+    /// Explicitly disable the use of tools, even if they are provided.
+    pub fn disable_tools(mut self) -> Self {
+        self.config.tool_choice = Some(ToolChoice::None);
+        self
+    }
+
+    /// Emulates function calling through the prompt, for models without native tool calls.
+    ///
+    /// Tools are described in the system prompt with a strict JSON call format, and calls
+    /// are parsed from the response text, validated against the tool schemas and returned
+    /// as tool calls. Enabled by default for Phind; useful for Ollama models without tool
+    /// support.
+    pub fn emulate_tools(mut self, emulate: bool) -> Self {
+        self.config.emulate_tools = Some(emulate);
+        self
+    }
+
+    /// Set the API version.
+    pub fn api_version(mut self, api_version: impl Into<String>) -> Self {
+        self.config.api_version = Some(api_version.into());
+        self
+    }
+
+    /// Set the deployment id. Used in Azure OpenAI.
+    pub fn deployment_id(mut self, deployment_id: impl Into<String>) -> Self {
+        self.config.deployment_id = Some(deployment_id.into());
+        self
+    }
+
+    /// Repair loop settings configured on this builder
+    fn repair(&self, attempts: usize) -> Repair {
+        let mut repair = Repair::new(attempts);
+        repair.strategy = self.repair_strategy;
+        if let Some(template) = &self.repair_template {
+            repair.template = template.clone();
+        }
+        repair
+    }
+
+    /// Builds and returns a configured LLM provider instance.
+    ///
+    /// With a validator, the provider is wrapped in a [`ValidatedLLM`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - No backend is specified
+    /// - Required backend feature is not enabled
+    /// - Required configuration like API keys are missing
+    pub fn build(self) -> Result<Box<dyn LLMProvider>, LLMError> {
+        let provider = self.config.build()?;
+
+        let repair = self.repair(self.validator_attempts);
+        match self.validator {
+            Some(validator) => Ok(Box::new(ValidatedLLM::with_repair(
+                provider, validator, repair,
+            ))),
+            None => Ok(provider),
+        }
+    }
+
+    /// Builds a provider answering typed chats with [`StructuredLLM::chat_typed`].
+    ///
+    /// Typed chats use the configured repair strategy and template, with the configured
+    /// validator attempts or 3 attempts by default.
+    pub fn build_structured(self) -> Result<StructuredLLM, LLMError> {
+        let attempts = match self.validator_attempts {
+            0 => DEFAULT_STRUCTURED_ATTEMPTS,
+            n => n,
+        };
+        let repair = self.repair(attempts);
+        let config = self.config.clone();
+        Ok(StructuredLLM::new(config, self.build()?, repair))
+    }
+}
+
+/// Builder for function parameters
+#[derive(Debug, Clone)]
+pub struct ParamBuilder {
+    name: String,
+    property_type: String,
+    description: String,
+    items: Option<Box<ParameterProperty>>,
+    enum_list: Option<Vec<String>>,
+}
+
+impl ParamBuilder {
+    /// Creates a new parameter builder
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            property_type: "string".to_string(),
+            description: String::new(),
+            items: None,
+            enum_list: None,
+        }
+    }
+
+    /// Sets the parameter type
+    pub fn type_of(mut self, type_str: impl Into<String>) -> Self {
+        self.property_type = type_str.into();
+        self
+    }
+
+    /// Sets the parameter description
+    pub fn description(mut self, desc: impl Into<String>) -> Self {
+        self.description = desc.into();
+        self
+    }
+
+    /// Sets the array item type for array parameters
+    pub fn items(mut self, item_property: ParameterProperty) -> Self {
+        self.items = Some(Box::new(item_property));
+        self
+    }
+
+    /// Sets the enum values for enum parameters
+    pub fn enum_values(mut self, values: Vec<String>) -> Self {
+        self.enum_list = Some(values);
+        self
+    }
+
+    /// Builds the parameter property
+    fn build(self) -> (String, ParameterProperty) {
+        (
+            self.name,
+            ParameterProperty {
+                property_type: self.property_type,
+                description: self.description,
+                items: self.items,
+                enum_list: self.enum_list,
+            },
+        )
+    }
+}
+
+/// Builder for function tools
+#[derive(Debug, Clone)]
+pub struct FunctionBuilder {
+    name: String,
+    description: String,
+    parameters: Vec<ParamBuilder>,
+    required: Vec<String>,
+}
+
+impl FunctionBuilder {
+    /// Creates a new function builder
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: String::new(),
+            parameters: Vec::new(),
+            required: Vec::new(),
+        }
+    }
+
+    /// Sets the function description
+    pub fn description(mut self, desc: impl Into<String>) -> Self {
+        self.description = desc.into();
+        self
+    }
+
+    /// Adds a parameter to the function
+    pub fn param(mut self, param: ParamBuilder) -> Self {
+        self.parameters.push(param);
+        self
+    }
+
+    /// Marks parameters as required
+    pub fn required(mut self, param_names: Vec<String>) -> Self {
+        self.required = param_names;
+        self
+    }
+
+    /// Builds the function tool
+    pub fn build(self) -> Tool {
+        let mut properties = HashMap::new();
+        for param in self.parameters {
+            let (name, prop) = param.build();
+            properties.insert(name, prop);
+        }
+
+        Tool {
+            tool_type: "function".to_string(),
+            function: FunctionTool {
+                name: self.name,
+                description: self.description,
+                parameters: ParametersSchema {
+                    schema_type: "object".to_string(),
+                    properties,
+                    required: self.required,
+                },
+            },
+        }
+    }
+}
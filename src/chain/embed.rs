@@ -0,0 +1,319 @@
+//! Embedding steps for prompt chains.
+//!
+//! An embedding step embeds its rendered template (usually a previous step's output,
+//! e.g. `{{question}}`) and stores either the raw vector or the closest documents of
+//! a supplied corpus, so retrieval-then-answer flows can stay inside a single chain.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::OnceCell;
+
+use crate::{embedding::rank_by_similarity, error::LLMError, LLMProvider};
+
+/// Embeddings of the corpus documents, one per document
+type Embeddings = Arc<Vec<Vec<f32>>>;
+
+/// Documents searched by an embedding step, with their embeddings once known
+#[derive(Debug)]
+struct Corpus {
+    documents: Vec<String>,
+    /// Embeddings supplied with the documents
+    precomputed: Option<Embeddings>,
+    /// Embeddings computed by the first run on each provider, keyed by provider id
+    computed: Mutex<HashMap<String, Arc<OnceCell<Embeddings>>>>,
+}
+
+impl Corpus {
+    fn new(documents: Vec<String>, precomputed: Option<Vec<Vec<f32>>>) -> Self {
+        Self {
+            documents,
+            precomputed: precomputed.map(Arc::new),
+            computed: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Embeddings comparable to a query of `dimension` embedded by provider `provider_id`
+    async fn embeddings(
+        &self,
+        provider_id: &str,
+        llm: &dyn LLMProvider,
+        dimension: usize,
+    ) -> Result<Embeddings, LLMError> {
+        if let Some(precomputed) = &self.precomputed {
+            if precomputed.first().is_some_and(|e| e.len() == dimension) {
+                return Ok(precomputed.clone());
+            }
+        }
+        let cell = self
+            .computed
+            .lock()
+            .unwrap()
+            .entry(provider_id.to_string())
+            .or_default()
+            .clone();
+        let embeddings = cell
+            .get_or_try_init(|| async {
+                let embeddings = llm.embed(self.documents.clone()).await?;
+                if embeddings.len() != self.documents.len() {
+                    return Err(LLMError::ProviderError(format!(
+                        "Provider returned {} embeddings for {} documents",
+                        embeddings.len(),
+                        self.documents.len()
+                    )));
+                }
+                Ok(Arc::new(embeddings))
+            })
+            .await?;
+        Ok(embeddings.clone())
+    }
+}
+
+/// Configuration of an embedding step
+#[derive(Debug, Clone)]
+pub struct EmbedStep {
+    corpus: Option<Arc<Corpus>>,
+    top_k: usize,
+    separator: String,
+}
+
+impl Default for EmbedStep {
+    fn default() -> Self {
+        Self::vector()
+    }
+}
+
+impl EmbedStep {
+    /// Stores the embedding vector itself, serialized as a JSON array
+    pub fn vector() -> Self {
+        Self {
+            corpus: None,
+            top_k: 1,
+            separator: "\n\n".to_string(),
+        }
+    }
+
+    /// Stores the `top_k` documents most similar to the step input.
+    ///
+    /// The documents are embedded the first time the step runs on a provider, and these
+    /// embeddings are reused by later runs on the same provider id, including runs of
+    /// other chains sharing the step. A fallback provider embeds the documents with its
+    /// own model, as embeddings of different models cannot be compared. The single
+    /// provider of a [`PromptChain`](super::PromptChain) counts as one id.
+    pub fn search(documents: Vec<String>, top_k: usize) -> Self {
+        Self {
+            corpus: Some(Arc::new(Corpus::new(documents, None))),
+            ..Self::vector()
+        }
+        .top_k(top_k)
+    }
+
+    /// Same as [`EmbedStep::search`] with embeddings computed ahead of time.
+    ///
+    /// `embeddings[i]` must be the embedding of `documents[i]`, computed with the model of
+    /// the step providers. Providers whose embeddings have another dimension embed the
+    /// documents themselves.
+    pub fn search_embedded(
+        documents: Vec<String>,
+        embeddings: Vec<Vec<f32>>,
+        top_k: usize,
+    ) -> Result<Self, LLMError> {
+        if documents.len() != embeddings.len() {
+            return Err(LLMError::InvalidRequest(format!(
+                "Corpus has {} documents but {} embeddings",
+                documents.len(),
+                embeddings.len()
+            )));
+        }
+        Ok(Self {
+            corpus: Some(Arc::new(Corpus::new(documents, Some(embeddings)))),
+            ..Self::vector()
+        }
+        .top_k(top_k))
+    }
+
+    /// Sets how many documents a search step returns
+    pub fn top_k(mut self, top_k: usize) -> Self {
+        self.top_k = top_k.max(1);
+        self
+    }
+
+    /// Sets the separator used to join the retrieved documents (defaults to a blank line)
+    pub fn separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    /// Embeds `input` with `llm`, the provider registered as `provider_id`, and returns
+    /// the string to store in chain memory
+    pub(crate) async fn run(
+        &self,
+        provider_id: &str,
+        llm: &dyn LLMProvider,
+        input: String,
+    ) -> Result<String, LLMError> {
+        let query = llm
+            .embed(vec![input])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| LLMError::ProviderError("Provider returned no embedding".into()))?;
+
+        let Some(corpus) = &self.corpus else {
+            return Ok(serde_json::to_string(&query)?);
+        };
+
+        if corpus.documents.is_empty() {
+            return Ok(String::new());
+        }
+        let embeddings = corpus.embeddings(provider_id, llm, query.len()).await?;
+
+        Ok(rank_by_similarity(&query, &embeddings, self.top_k)
+            .into_iter()
+            .map(|(i, _)| corpus.documents[i].as_str())
+            .collect::<Vec<_>>()
+            .join(&self.separator))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{embedding::EmbeddingProvider, testing::MockLLM};
+
+    fn documents() -> Vec<String> {
+        ["zzz zzz", "the cat sat on the mat", "xylophone"]
+            .map(String::from)
+            .to_vec()
+    }
+
+    #[tokio::test]
+    async fn search_embeds_corpus_once() {
+        let llm = MockLLM::default();
+        let step = EmbedStep::search(documents(), 1);
+
+        let first = step
+            .run("mock", &llm, "a cat on a mat".into())
+            .await
+            .unwrap();
+        let second = step
+            .clone()
+            .run("mock", &llm, "the mat".into())
+            .await
+            .unwrap();
+
+        assert_eq!(first, "the cat sat on the mat");
+        assert_eq!(second, "the cat sat on the mat");
+        // Two queries and a single corpus embedding
+        assert_eq!(llm.embed_calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn search_embedded_uses_precomputed_embeddings() {
+        let llm = MockLLM::default();
+        let embeddings = llm.embed(documents()).await.unwrap();
+        let step = EmbedStep::search_embedded(documents(), embeddings, 2)
+            .unwrap()
+            .separator(" | ");
+
+        let found = step.run("mock", &llm, "zzz cat".into()).await.unwrap();
+
+        assert_eq!(found, "zzz zzz | the cat sat on the mat");
+        assert_eq!(llm.embed_calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn embeddings_of_another_dimension_are_computed_once() {
+        let llm = MockLLM::default();
+        let step = EmbedStep::search_embedded(documents(), vec![vec![1.0]; 3], 1).unwrap();
+
+        let found = step.run("mock", &llm, "xylophone".into()).await.unwrap();
+        step.run("mock", &llm, "xylophone".into()).await.unwrap();
+
+        assert_eq!(found, "xylophone");
+        // Two queries and a single corpus embedding
+        assert_eq!(llm.embed_calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn each_provider_embeds_the_corpus() {
+        let (primary, fallback) = (MockLLM::default(), MockLLM::default());
+        let step = EmbedStep::search(documents(), 1);
+
+        step.run("primary", &primary, "cat".into()).await.unwrap();
+        step.run("fallback", &fallback, "cat".into()).await.unwrap();
+        step.run("fallback", &fallback, "mat".into()).await.unwrap();
+
+        assert_eq!(primary.embed_calls(), 2);
+        assert_eq!(fallback.embed_calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn extra_corpus_embeddings_are_an_error() {
+        let llm = MockLLM::default().surplus_embeddings(1);
+        let step = EmbedStep::search(documents(), 1);
+
+        let result = step.run("mock", &llm, "cat".into()).await;
+
+        assert!(matches!(
+            result,
+            Err(LLMError::ProviderError(message)) if message.contains("4 embeddings for 3")
+        ));
+    }
+
+    #[test]
+    fn search_embedded_rejects_mismatched_lengths() {
+        assert!(EmbedStep::search_embedded(documents(), vec![vec![1.0]], 1).is_err());
+    }
+
+    #[tokio::test]
+    async fn vector_stores_the_embedding() {
+        let llm = MockLLM::default();
+        let vector = EmbedStep::vector()
+            .run("mock", &llm, "ab".into())
+            .await
+            .unwrap();
+        let parsed: Vec<f32> = serde_json::from_str(&vector).unwrap();
+        assert_eq!(&parsed[..3], &[1.0, 1.0, 0.0]);
+    }
+
+    #[tokio::test]
+    async fn chain_step_retrieves_documents_for_the_next_step() {
+        use crate::chain::{
+            LLMRegistryBuilder, MultiChainStepBuilder, MultiChainStepMode, MultiPromptChain,
+        };
+
+        let llms = LLMRegistryBuilder::new()
+            .register("mock", Box::new(MockLLM::new(["It sat on the mat"])))
+            .build();
+        let outputs = MultiPromptChain::new(&llms)
+            .input("question", "where did the cat sit")
+            .step(
+                MultiChainStepBuilder::new(MultiChainStepMode::Embed(EmbedStep::search(
+                    documents(),
+                    1,
+                )))
+                .provider_id("mock")
+                .id("docs")
+                .template("{{question}}")
+                .build()
+                .unwrap(),
+            )
+            .step(
+                MultiChainStepBuilder::new(MultiChainStepMode::Chat)
+                    .provider_id("mock")
+                    .id("answer")
+                    .template("{{docs}}\n{{question}}")
+                    .build()
+                    .unwrap(),
+            )
+            .run()
+            .await
+            .unwrap();
+
+        assert_eq!(outputs["docs"], "the cat sat on the mat");
+        assert_eq!(outputs["answer"], "It sat on the mat");
+    }
+}
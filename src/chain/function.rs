@@ -0,0 +1,198 @@
+//! Custom Rust function steps for prompt chains.
+//!
+//! A function step runs a user-supplied closure between LLM steps, e.g. to parse a
+//! previous answer, look something up or query a database, and stores its result in
+//! the chain memory like any other step.
+
+use std::{collections::HashMap, fmt, future::Future, pin::Pin, sync::Arc};
+
+use serde_json::Value;
+
+use crate::error::LLMError;
+
+/// Input handed to a function step
+#[derive(Debug, Clone)]
+pub struct StepContext {
+    /// The step template with {{variable}} placeholders already replaced
+    pub prompt: String,
+    /// Outputs of the previous steps, keyed by step id
+    pub memory: HashMap<String, String>,
+}
+
+impl StepContext {
+    /// Returns the output of a previous step, if any
+    pub fn get(&self, step_id: &str) -> Option<&str> {
+        self.memory.get(step_id).map(|s| s.as_str())
+    }
+
+    /// Parses the output of a previous step as JSON
+    pub fn json(&self, step_id: &str) -> Result<Value, LLMError> {
+        let raw = self.get(step_id).ok_or_else(|| {
+            LLMError::InvalidRequest(format!("No output for step '{}' in chain memory", step_id))
+        })?;
+        Ok(serde_json::from_str(raw)?)
+    }
+}
+
+/// Result produced by a function step
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepOutput {
+    /// Plain text, stored as is
+    Text(String),
+    /// JSON value, stored serialized so later templates can embed it
+    Json(Value),
+}
+
+impl StepOutput {
+    /// Converts the output into the string stored in chain memory
+    pub fn into_memory(self) -> String {
+        match self {
+            StepOutput::Text(text) => text,
+            StepOutput::Json(Value::String(text)) => text,
+            StepOutput::Json(value) => value.to_string(),
+        }
+    }
+}
+
+impl From<String> for StepOutput {
+    fn from(text: String) -> Self {
+        StepOutput::Text(text)
+    }
+}
+
+impl From<&str> for StepOutput {
+    fn from(text: &str) -> Self {
+        StepOutput::Text(text.to_string())
+    }
+}
+
+impl From<Value> for StepOutput {
+    fn from(value: Value) -> Self {
+        StepOutput::Json(value)
+    }
+}
+
+/// Boxed future returned by asynchronous step functions
+pub type StepFuture = Pin<Box<dyn Future<Output = Result<StepOutput, LLMError>> + Send>>;
+
+type SyncStepFn = dyn Fn(&StepContext) -> Result<StepOutput, LLMError> + Send + Sync;
+type AsyncStepFn = dyn Fn(StepContext) -> StepFuture + Send + Sync;
+
+/// A user-supplied function executed as a chain step
+#[derive(Clone)]
+pub enum StepFn {
+    /// Blocking closure over the step context
+    Sync(Arc<SyncStepFn>),
+    /// Closure returning a future, for I/O such as database queries
+    Async(Arc<AsyncStepFn>),
+}
+
+impl StepFn {
+    /// Wraps a synchronous closure
+    pub fn sync<F, O>(f: F) -> Self
+    where
+        F: Fn(&StepContext) -> Result<O, LLMError> + Send + Sync + 'static,
+        O: Into<StepOutput>,
+    {
+        StepFn::Sync(Arc::new(move |ctx| f(ctx).map(Into::into)))
+    }
+
+    /// Wraps an asynchronous closure
+    pub fn asynchronous<F, Fut, O>(f: F) -> Self
+    where
+        F: Fn(StepContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<O, LLMError>> + Send + 'static,
+        O: Into<StepOutput>,
+    {
+        StepFn::Async(Arc::new(move |ctx| {
+            let fut = f(ctx);
+            Box::pin(async move { fut.await.map(Into::into) })
+        }))
+    }
+
+    /// Runs the function and returns the string to store in chain memory
+    pub async fn call(&self, ctx: StepContext) -> Result<String, LLMError> {
+        let output = match self {
+            StepFn::Sync(f) => f(&ctx)?,
+            StepFn::Async(f) => f(ctx).await?,
+        };
+        Ok(output.into_memory())
+    }
+}
+
+impl fmt::Debug for StepFn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StepFn::Sync(_) => write!(f, "StepFn::Sync"),
+            StepFn::Async(_) => write!(f, "StepFn::Async"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn context() -> StepContext {
+        StepContext {
+            prompt: "Count {{items}}".to_string(),
+            memory: HashMap::from([
+                ("items".to_string(), "[1, 2, 3]".to_string()),
+                ("name".to_string(), "rllm".to_string()),
+            ]),
+        }
+    }
+
+    #[test]
+    fn context_reads_previous_outputs() {
+        let ctx = context();
+        assert_eq!(ctx.get("name"), Some("rllm"));
+        assert_eq!(ctx.get("missing"), None);
+        assert_eq!(ctx.json("items").unwrap(), json!([1, 2, 3]));
+        assert!(matches!(
+            ctx.json("missing"),
+            Err(LLMError::InvalidRequest(_))
+        ));
+        assert!(ctx.json("name").is_err());
+    }
+
+    #[test]
+    fn outputs_are_stored_as_text() {
+        assert_eq!(StepOutput::from("plain").into_memory(), "plain");
+        assert_eq!(StepOutput::from(json!("quoted")).into_memory(), "quoted");
+        assert_eq!(
+            StepOutput::from(json!({ "n": 3 })).into_memory(),
+            "{\"n\":3}"
+        );
+    }
+
+    #[tokio::test]
+    async fn sync_functions_see_the_context() {
+        let count = StepFn::sync(|ctx| {
+            let items = ctx.json("items")?;
+            Ok(json!({ "count": items.as_array().map_or(0, Vec::len) }))
+        });
+        assert_eq!(count.call(context()).await.unwrap(), "{\"count\":3}");
+        assert_eq!(format!("{:?}", count), "StepFn::Sync");
+    }
+
+    #[tokio::test]
+    async fn async_functions_are_awaited() {
+        let echo = StepFn::asynchronous(|ctx: StepContext| async move {
+            tokio::task::yield_now().await;
+            Ok(ctx.prompt)
+        });
+        assert_eq!(echo.call(context()).await.unwrap(), "Count {{items}}");
+        assert_eq!(format!("{:?}", echo), "StepFn::Async");
+
+        let failing = StepFn::asynchronous(|_| async {
+            Err::<String, _>(LLMError::ProviderError("lookup failed".to_string()))
+        });
+        assert!(matches!(
+            failing.call(context()).await,
+            Err(LLMError::ProviderError(message)) if message == "lookup failed"
+        ));
+    }
+}
@@ -0,0 +1,272 @@
+//! Prompt chains executing a sequence of steps against one or several LLM providers.
+//!
+//! Besides chat and completion steps, a chain can run custom Rust functions over the
+//! outputs of previous steps (see [`StepFn`]) and embed text, optionally looking up the
+//! closest documents of a corpus (see [`EmbedStep`]).
+
+mod embed;
+mod function;
+mod multi;
+
+use crate::{error::LLMError, LLMProvider};
+use std::collections::HashMap;
+
+pub use embed::EmbedStep;
+pub use function::{StepContext, StepFn, StepFuture, StepOutput};
+pub use multi::{
+    LLMRegistry, LLMRegistryBuilder, MultiChainOutput, MultiChainStep, MultiChainStepBuilder,
+    MultiChainStepMode, MultiPromptChain,
+};
+
+/// Execution mode for a chain step
+#[derive(Debug, Clone)]
+pub enum ChainStepMode {
+    /// Execute step using chat completion
+    Chat,
+    /// Execute step using text completion
+    Completion,
+    /// Execute step by calling a Rust function over the chain memory
+    Function(StepFn),
+    /// Embed the rendered template, storing the vector or a corpus lookup result
+    Embed(EmbedStep),
+}
+
+impl ChainStepMode {
+    /// Creates a function step mode from a synchronous closure
+    pub fn function<F, O>(f: F) -> Self
+    where
+        F: Fn(&StepContext) -> Result<O, LLMError> + Send + Sync + 'static,
+        O: Into<StepOutput>,
+    {
+        ChainStepMode::Function(StepFn::sync(f))
+    }
+
+    /// Creates a function step mode from an asynchronous closure
+    pub fn async_function<F, Fut, O>(f: F) -> Self
+    where
+        F: Fn(StepContext) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<O, LLMError>> + Send + 'static,
+        O: Into<StepOutput>,
+    {
+        ChainStepMode::Function(StepFn::asynchronous(f))
+    }
+}
+
+/// Represents a single step in a prompt chain
+#[derive(Debug, Clone)]
+pub struct ChainStep {
+    /// Unique identifier for this step
+    pub id: String,
+    /// Prompt template with {{variable}} placeholders
+    pub template: String,
+    /// Execution mode (chat, completion, function or embed)
+    pub mode: ChainStepMode,
+    /// Optional temperature parameter (0.0-1.0) controlling randomness
+    pub temperature: Option<f32>,
+    /// Optional maximum tokens to generate in response
+    pub max_tokens: Option<u32>,
+    /// Optional top_p parameter for nucleus sampling
+    pub top_p: Option<f32>,
+}
+
+/// Builder pattern for constructing ChainStep instances
+pub struct ChainStepBuilder {
+    id: String,
+    template: String,
+    mode: ChainStepMode,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
+    top_p: Option<f32>,
+    top_k: Option<u32>,
+}
+
+impl ChainStepBuilder {
+    /// Creates a new ChainStepBuilder
+    ///
+    /// # Arguments
+    /// * `id` - Unique identifier for the step
+    /// * `template` - Prompt template with {{variable}} placeholders
+    /// * `mode` - Execution mode (chat, completion, function or embed)
+    pub fn new(id: impl Into<String>, template: impl Into<String>, mode: ChainStepMode) -> Self {
+        Self {
+            id: id.into(),
+            template: template.into(),
+            mode,
+            temperature: None,
+            max_tokens: None,
+            top_p: None,
+            top_k: None,
+        }
+    }
+
+    /// Sets the temperature parameter
+    pub fn temperature(mut self, temp: f32) -> Self {
+        self.temperature = Some(temp);
+        self
+    }
+
+    /// Sets the maximum tokens parameter
+    pub fn max_tokens(mut self, mt: u32) -> Self {
+        self.max_tokens = Some(mt);
+        self
+    }
+
+    /// Sets the top_p parameter
+    pub fn top_p(mut self, val: f32) -> Self {
+        self.top_p = Some(val);
+        self
+    }
+
+    /// Sets the top_k parameter
+    pub fn top_k(mut self, val: u32) -> Self {
+        self.top_k = Some(val);
+        self
+    }
+
+    /// Builds and returns a ChainStep instance
+    pub fn build(self) -> ChainStep {
+        ChainStep {
+            id: self.id,
+            template: self.template,
+            mode: self.mode,
+            temperature: self.temperature,
+            max_tokens: self.max_tokens,
+            top_p: self.top_p,
+        }
+    }
+}
+
+/// Manages a sequence of prompt steps with variable substitution
+pub struct PromptChain<'a> {
+    llm: &'a dyn LLMProvider,
+    steps: Vec<ChainStep>,
+    memory: HashMap<String, String>,
+}
+
+impl<'a> PromptChain<'a> {
+    /// Creates a new PromptChain with the given LLM provider
+    pub fn new(llm: &'a dyn LLMProvider) -> Self {
+        Self {
+            llm,
+            steps: Vec::new(),
+            memory: HashMap::new(),
+        }
+    }
+
+    /// Adds a step to the chain
+    pub fn step(mut self, step: ChainStep) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// Executes all steps in the chain and returns the results
+    pub async fn run(mut self) -> Result<HashMap<String, String>, LLMError> {
+        for step in &self.steps {
+            let prompt = apply_template(&self.memory, &step.template);
+
+            let response_text = match &step.mode {
+                ChainStepMode::Chat => {
+                    let messages = vec![crate::chat::ChatMessage {
+                        role: crate::chat::ChatRole::User,
+                        message_type: crate::chat::MessageType::Text,
+                        content: prompt,
+                    }];
+                    self.llm.chat(&messages).await?.text().unwrap_or_default()
+                }
+                ChainStepMode::Completion => {
+                    let mut req = crate::completion::CompletionRequest::new(prompt);
+                    req.max_tokens = step.max_tokens;
+                    req.temperature = step.temperature;
+                    self.llm.complete(&req).await?.text
+                }
+                ChainStepMode::Function(f) => {
+                    f.call(StepContext {
+                        prompt,
+                        memory: self.memory.clone(),
+                    })
+                    .await?
+                }
+                // The chain has a single provider, so it needs no id
+                ChainStepMode::Embed(embed) => embed.run("", self.llm, prompt).await?,
+            };
+
+            self.memory.insert(step.id.clone(), response_text);
+        }
+
+        Ok(self.memory)
+    }
+}
+
+/// Replaces {{variable}} placeholders in template with values from memory
+pub(crate) fn apply_template(memory: &HashMap<String, String>, input: &str) -> String {
+    let mut result = input.to_string();
+    for (k, v) in memory {
+        let pattern = format!("{{{{{}}}}}", k);
+        result = result.replace(&pattern, v);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockLLM;
+
+    #[tokio::test]
+    async fn function_steps_run_between_llm_steps() {
+        let llm = MockLLM::new(["red, green, blue", "Three colors"]);
+        let memory = PromptChain::new(&llm)
+            .step(ChainStepBuilder::new("colors", "List colors", ChainStepMode::Chat).build())
+            .step(
+                ChainStepBuilder::new(
+                    "count",
+                    "{{colors}}",
+                    ChainStepMode::function(|ctx| Ok(ctx.prompt.split(", ").count().to_string())),
+                )
+                .build(),
+            )
+            .step(
+                ChainStepBuilder::new(
+                    "upper",
+                    "",
+                    ChainStepMode::async_function(|ctx: StepContext| async move {
+                        Ok(ctx.get("colors").unwrap_or_default().to_uppercase())
+                    }),
+                )
+                .build(),
+            )
+            .step(
+                ChainStepBuilder::new("summary", "Summarize {{count}}", ChainStepMode::Completion)
+                    .build(),
+            )
+            .run()
+            .await
+            .unwrap();
+
+        assert_eq!(memory["count"], "3");
+        assert_eq!(memory["upper"], "RED, GREEN, BLUE");
+        assert_eq!(memory["summary"], "Three colors");
+        // Only the chat step reached the provider
+        assert_eq!(llm.requests.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn function_errors_stop_the_chain() {
+        let llm = MockLLM::new(["unused"]);
+        let result = PromptChain::new(&llm)
+            .step(
+                ChainStepBuilder::new(
+                    "parse",
+                    "",
+                    ChainStepMode::function(|ctx| ctx.json("missing")),
+                )
+                .build(),
+            )
+            .step(ChainStepBuilder::new("next", "Go", ChainStepMode::Chat).build())
+            .run()
+            .await;
+
+        assert!(matches!(result, Err(LLMError::InvalidRequest(_))));
+        assert!(llm.requests.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,509 @@
+//! Module for chaining multiple LLM backends in a single prompt sequence.
+//! Each step can reference a distinct provider_id ("openai", "anthro", etc.),
+//! optionally followed by fallback providers tried in order when a call fails.
+
+use std::collections::HashMap;
+
+use crate::{
+    chat::{ChatMessage, ChatRole, MessageType},
+    completion::CompletionRequest,
+    error::LLMError,
+    LLMProvider,
+};
+
+use super::{apply_template, EmbedStep, StepContext, StepFn, StepOutput};
+
+pub use llm::chain::{LLMRegistry, LLMRegistryBuilder};
+
+/// Response transformation function
+type ResponseTransform = Box<dyn Fn(String) -> String + Send + Sync>;
+
+/// Execution mode for a step: Chat, Completion, SpeechToText, Function or Embed
+#[derive(Debug, Clone)]
+pub enum MultiChainStepMode {
+    Chat,
+    Completion,
+    SpeechToText,
+    /// Runs a Rust function over the chain memory, no provider involved
+    Function(StepFn),
+    /// Embeds the rendered template with the step provider
+    Embed(EmbedStep),
+}
+
+impl MultiChainStepMode {
+    /// Creates a function step mode from a synchronous closure
+    pub fn function<F, O>(f: F) -> Self
+    where
+        F: Fn(&StepContext) -> Result<O, LLMError> + Send + Sync + 'static,
+        O: Into<StepOutput>,
+    {
+        MultiChainStepMode::Function(StepFn::sync(f))
+    }
+
+    /// Creates a function step mode from an asynchronous closure
+    pub fn async_function<F, Fut, O>(f: F) -> Self
+    where
+        F: Fn(StepContext) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<O, LLMError>> + Send + 'static,
+        O: Into<StepOutput>,
+    {
+        MultiChainStepMode::Function(StepFn::asynchronous(f))
+    }
+
+    /// Whether steps in this mode are executed by a registry provider
+    fn needs_provider(&self) -> bool {
+        !matches!(self, MultiChainStepMode::Function(_))
+    }
+}
+
+/// Multi-backend chain step
+pub struct MultiChainStep {
+    provider_ids: Vec<String>,
+    id: String,
+    template: String,
+    mode: MultiChainStepMode,
+
+    // Override parameters
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
+
+    // Response transformation
+    response_transform: Option<ResponseTransform>,
+}
+
+/// Builder for MultiChainStep (Stripe-style)
+pub struct MultiChainStepBuilder {
+    provider_id: Option<String>,
+    fallbacks: Vec<String>,
+    id: Option<String>,
+    template: Option<String>,
+    mode: MultiChainStepMode,
+
+    temperature: Option<f32>,
+    top_p: Option<f32>,
+    max_tokens: Option<u32>,
+    response_transform: Option<ResponseTransform>,
+}
+
+impl MultiChainStepBuilder {
+    pub fn new(mode: MultiChainStepMode) -> Self {
+        Self {
+            provider_id: None,
+            fallbacks: Vec::new(),
+            id: None,
+            template: None,
+            mode,
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
+            response_transform: None,
+        }
+    }
+
+    /// Backend identifier to use, e.g. "openai"
+    ///
+    /// Replaces the primary provider, keeping any fallbacks already set.
+    pub fn provider_id(mut self, pid: impl Into<String>) -> Self {
+        self.provider_id = Some(pid.into());
+        self
+    }
+
+    /// Adds a fallback backend, tried when all previous providers failed
+    pub fn fallback(mut self, pid: impl Into<String>) -> Self {
+        self.fallbacks.push(pid.into());
+        self
+    }
+
+    /// Sets the ordered list of backends to try, e.g. ["openai", "anthro"]
+    ///
+    /// The first one becomes the primary provider and the others its fallbacks.
+    pub fn provider_ids<I, S>(mut self, pids: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut pids = pids.into_iter().map(Into::into);
+        self.provider_id = pids.next();
+        self.fallbacks = pids.collect();
+        self
+    }
+
+    /// Unique identifier for the step, e.g. "calc1"
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// The prompt or template (e.g. "2 * 4 = ?")
+    pub fn template(mut self, tmpl: impl Into<String>) -> Self {
+        self.template = Some(tmpl.into());
+        self
+    }
+
+    // Parameters
+    pub fn temperature(mut self, t: f32) -> Self {
+        self.temperature = Some(t);
+        self
+    }
+
+    pub fn top_p(mut self, p: f32) -> Self {
+        self.top_p = Some(p);
+        self
+    }
+
+    pub fn max_tokens(mut self, mt: u32) -> Self {
+        self.max_tokens = Some(mt);
+        self
+    }
+
+    pub fn response_transform<F>(mut self, func: F) -> Self
+    where
+        F: Fn(String) -> String + Send + Sync + 'static,
+    {
+        self.response_transform = Some(Box::new(func));
+        self
+    }
+
+    /// Builds the step
+    ///
+    /// Function steps don't need a provider_id, and their template defaults to empty.
+    pub fn build(self) -> Result<MultiChainStep, LLMError> {
+        let provider_ids: Vec<String> =
+            self.provider_id.into_iter().chain(self.fallbacks).collect();
+        if self.mode.needs_provider() && provider_ids.is_empty() {
+            return Err(LLMError::InvalidRequest("No provider_id set".into()));
+        }
+        let id = self
+            .id
+            .ok_or_else(|| LLMError::InvalidRequest("No step id set".into()))?;
+        let tmpl = match (self.template, &self.mode) {
+            (Some(tmpl), _) => tmpl,
+            (None, MultiChainStepMode::Function(_)) => String::new(),
+            (None, _) => return Err(LLMError::InvalidRequest("No template set".into())),
+        };
+
+        Ok(MultiChainStep {
+            provider_ids,
+            id,
+            template: tmpl,
+            mode: self.mode,
+            temperature: self.temperature,
+            max_tokens: self.max_tokens,
+            response_transform: self.response_transform,
+        })
+    }
+}
+
+/// Outputs of a multi-backend chain run
+#[derive(Debug, Clone, Default)]
+pub struct MultiChainOutput {
+    /// Response of each step, keyed by step id
+    pub outputs: HashMap<String, String>,
+    /// Provider that produced each provider-backed step, keyed by step id
+    pub providers: HashMap<String, String>,
+}
+
+/// The multi-backend chain
+pub struct MultiPromptChain<'a> {
+    registry: &'a LLMRegistry,
+    steps: Vec<MultiChainStep>,
+    memory: HashMap<String, String>, // stores responses
+}
+
+impl<'a> MultiPromptChain<'a> {
+    pub fn new(registry: &'a LLMRegistry) -> Self {
+        Self {
+            registry,
+            steps: vec![],
+            memory: HashMap::new(),
+        }
+    }
+
+    /// Stores a value in the chain memory, available to templates as `{{key}}`
+    pub fn input(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.memory.insert(key.into(), value.into());
+        self
+    }
+
+    /// Adds a step
+    pub fn step(mut self, step: MultiChainStep) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// Whether a step has this id
+    pub(crate) fn has_step(&self, id: &str) -> bool {
+        self.steps.iter().any(|step| step.id == id)
+    }
+
+    /// Executes all steps
+    pub async fn run(self) -> Result<HashMap<String, String>, LLMError> {
+        Ok(self.run_detailed().await?.outputs)
+    }
+
+    /// Executes all steps, also reporting which provider answered each step
+    pub async fn run_detailed(mut self) -> Result<MultiChainOutput, LLMError> {
+        let mut providers = HashMap::new();
+
+        for step in &self.steps {
+            // 1) Replace {{xyz}} in template with existing memory
+            let prompt_text = apply_template(&self.memory, &step.template);
+
+            // 2) Execute, trying the step providers in order for provider-backed modes
+            let mut response = match &step.mode {
+                MultiChainStepMode::Function(f) => {
+                    f.call(StepContext {
+                        prompt: prompt_text,
+                        memory: self.memory.clone(),
+                    })
+                    .await?
+                }
+                _ => {
+                    let (provider_id, response) =
+                        self.execute_with_fallback(step, &prompt_text).await?;
+                    providers.insert(step.id.clone(), provider_id);
+                    response
+                }
+            };
+
+            if let Some(transform) = &step.response_transform {
+                response = transform(response);
+            }
+
+            // 3) Store the response
+            self.memory.insert(step.id.clone(), response);
+        }
+
+        Ok(MultiChainOutput {
+            outputs: self.memory,
+            providers,
+        })
+    }
+
+    /// Runs a step against each of its providers until one succeeds
+    async fn execute_with_fallback(
+        &self,
+        step: &MultiChainStep,
+        prompt_text: &str,
+    ) -> Result<(String, String), LLMError> {
+        let mut failures = Vec::new();
+
+        for provider_id in &step.provider_ids {
+            let result = match self.registry.get(provider_id) {
+                Some(llm) => Self::execute(provider_id, llm, step, prompt_text.to_string()).await,
+                None => Err(LLMError::InvalidRequest(format!(
+                    "No provider with id '{}' found in registry",
+                    provider_id
+                ))),
+            };
+
+            match result {
+                Ok(response) => return Ok((provider_id.clone(), response)),
+                Err(e) => failures.push((provider_id, e)),
+            }
+        }
+
+        // Without fallbacks, surface the provider error unchanged
+        if failures.len() == 1 {
+            return Err(failures.remove(0).1);
+        }
+
+        Err(LLMError::ProviderError(format!(
+            "All providers failed for step '{}': {}",
+            step.id,
+            failures
+                .iter()
+                .map(|(pid, e)| format!("{}: {}", pid, e))
+                .collect::<Vec<_>>()
+                .join("; ")
+        )))
+    }
+
+    /// Executes a provider-backed step on the provider registered as `provider_id`
+    async fn execute(
+        provider_id: &str,
+        llm: &dyn LLMProvider,
+        step: &MultiChainStep,
+        prompt_text: String,
+    ) -> Result<String, LLMError> {
+        Ok(match &step.mode {
+            MultiChainStepMode::Chat => {
+                let messages = vec![ChatMessage {
+                    role: ChatRole::User,
+                    message_type: MessageType::Text,
+                    content: prompt_text,
+                }];
+                llm.chat(&messages).await?.text().unwrap_or_default()
+            }
+            MultiChainStepMode::Completion => {
+                let mut req = CompletionRequest::new(prompt_text);
+                req.temperature = step.temperature;
+                req.max_tokens = step.max_tokens;
+                let c = llm.complete(&req).await?;
+                c.text.to_string()
+            }
+            MultiChainStepMode::SpeechToText => llm.transcribe_file(&prompt_text).await?,
+            MultiChainStepMode::Embed(embed) => embed.run(provider_id, llm, prompt_text).await?,
+            MultiChainStepMode::Function(f) => {
+                return Err(LLMError::InvalidRequest(format!(
+                    "Function step '{}' ({:?}) cannot run on a provider",
+                    step.id, f
+                )))
+            }
+        })
+    }
+
+    /// Adds multiple steps at once
+    pub fn chain(mut self, steps: Vec<MultiChainStep>) -> Self {
+        self.steps.extend(steps);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockLLM;
+
+    fn registry() -> LLMRegistry {
+        LLMRegistryBuilder::new()
+            .register("mock", Box::new(MockLLM::new(["4"])))
+            .build()
+    }
+
+    #[tokio::test]
+    async fn function_steps_need_no_provider_or_template() {
+        let step = MultiChainStepBuilder::new(MultiChainStepMode::function(|ctx| {
+            Ok(format!("{} doubled", ctx.get("calc").unwrap_or_default()))
+        }))
+        .id("double")
+        .build()
+        .unwrap();
+        assert!(step.template.is_empty());
+        assert!(step.provider_ids.is_empty());
+
+        let missing = MultiChainStepBuilder::new(MultiChainStepMode::Chat)
+            .id("calc")
+            .template("2 * 2 = ?")
+            .build();
+        assert!(matches!(missing, Err(LLMError::InvalidRequest(_))));
+
+        let registry = registry();
+        let output = MultiPromptChain::new(&registry)
+            .step(
+                MultiChainStepBuilder::new(MultiChainStepMode::Chat)
+                    .provider_id("mock")
+                    .id("calc")
+                    .template("2 * 2 = ?")
+                    .build()
+                    .unwrap(),
+            )
+            .step(step)
+            .run_detailed()
+            .await
+            .unwrap();
+
+        assert_eq!(output.outputs["double"], "4 doubled");
+        assert!(!output.providers.contains_key("double"));
+    }
+
+    #[tokio::test]
+    async fn function_steps_cannot_run_on_a_provider() {
+        let step = MultiChainStepBuilder::new(MultiChainStepMode::function(|_| Ok("never")))
+            .id("local")
+            .build()
+            .unwrap();
+        let llm = MockLLM::new(["unused"]);
+
+        let result = MultiPromptChain::execute("mock", &llm, &step, String::new()).await;
+        assert!(matches!(
+            result,
+            Err(LLMError::InvalidRequest(message)) if message.contains("cannot run on a provider")
+        ));
+    }
+
+    fn chat_step(builder: MultiChainStepBuilder) -> MultiChainStep {
+        builder.id("answer").template("2 * 2 = ?").build().unwrap()
+    }
+
+    fn fallback_registry() -> LLMRegistry {
+        LLMRegistryBuilder::new()
+            .register("down", Box::new(MockLLM::failing("outage")))
+            .register("busy", Box::new(MockLLM::failing("rate limited")))
+            .register("up", Box::new(MockLLM::new(["4"])))
+            .build()
+    }
+
+    #[test]
+    fn provider_id_keeps_fallbacks_set_before() {
+        let step = chat_step(
+            MultiChainStepBuilder::new(MultiChainStepMode::Chat)
+                .fallback("b")
+                .fallback("c")
+                .provider_id("x")
+                .provider_id("a"),
+        );
+        assert_eq!(step.provider_ids, ["a", "b", "c"]);
+
+        let step = chat_step(
+            MultiChainStepBuilder::new(MultiChainStepMode::Chat)
+                .provider_ids(["a", "b"])
+                .fallback("c"),
+        );
+        assert_eq!(step.provider_ids, ["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn fallbacks_are_tried_in_order() {
+        let registry = fallback_registry();
+        let output = MultiPromptChain::new(&registry)
+            .step(chat_step(
+                MultiChainStepBuilder::new(MultiChainStepMode::Chat)
+                    .provider_id("down")
+                    .fallback("missing")
+                    .fallback("up")
+                    .fallback("busy"),
+            ))
+            .run_detailed()
+            .await
+            .unwrap();
+
+        assert_eq!(output.outputs["answer"], "4");
+        assert_eq!(output.providers["answer"], "up");
+    }
+
+    #[tokio::test]
+    async fn all_failures_are_reported() {
+        let registry = fallback_registry();
+        let result = MultiPromptChain::new(&registry)
+            .step(chat_step(
+                MultiChainStepBuilder::new(MultiChainStepMode::Chat)
+                    .provider_ids(["down", "missing", "busy"]),
+            ))
+            .run()
+            .await;
+
+        let Err(LLMError::ProviderError(message)) = result else {
+            panic!("expected a provider error, got {:?}", result);
+        };
+        assert_eq!(
+            message,
+            "All providers failed for step 'answer': down: Provider Error: outage; \
+             missing: Invalid Request: No provider with id 'missing' found in registry; \
+             busy: Provider Error: rate limited"
+        );
+    }
+
+    #[tokio::test]
+    async fn a_single_failure_is_returned_unchanged() {
+        let registry = fallback_registry();
+        let result = MultiPromptChain::new(&registry)
+            .step(chat_step(
+                MultiChainStepBuilder::new(MultiChainStepMode::Completion).provider_id("down"),
+            ))
+            .run()
+            .await;
+
+        assert!(matches!(result, Err(LLMError::ProviderError(message)) if message == "outage"));
+    }
+}
@@ -0,0 +1,45 @@
+//! Vector embeddings generation for text, plus helpers to compare embeddings.
+
+pub use llm::embedding::*;
+
+/// Computes the cosine similarity between two vectors.
+///
+/// Returns 0.0 when the vectors have different lengths or one of them is all zeros.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+
+    let mut dot = 0.0;
+    let mut norm_a = 0.0;
+    let mut norm_b = 0.0;
+    for (x, y) in a.iter().zip(b) {
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+/// Ranks candidate vectors by cosine similarity to a query vector.
+///
+/// # Returns
+/// Up to `top_k` (index, similarity) pairs, most similar first
+pub fn rank_by_similarity(
+    query: &[f32],
+    candidates: &[Vec<f32>],
+    top_k: usize,
+) -> Vec<(usize, f32)> {
+    let mut scored: Vec<(usize, f32)> = candidates
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (i, cosine_similarity(query, c)))
+        .collect();
+    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    scored.truncate(top_k);
+    scored
+}
@@ -0,0 +1,382 @@
+//! Evaluation baselines and regression gating.
+//!
+//! A [`Baseline`] stores the per-case scores of an [`EvalReport`] so a later run can be
+//! compared against it. The resulting [`RegressionReport`] flags cases whose score dropped
+//! beyond a threshold and fails when a provider's mean score regressed, which lets CI gate
+//! prompt and model changes, typically with a [`ReplayProvider`](super::ReplayProvider).
+
+use std::{collections::BTreeMap, fmt, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::LLMError;
+
+use super::report::{read_file, write_file, EvalReport};
+
+/// Recorded scores of one case on one provider
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BaselineCase {
+    /// Dataset case identifier
+    pub case_id: String,
+    /// Provider identifier
+    pub provider_id: String,
+    /// Total score, `None` when the case failed in the baseline run
+    pub score: Option<f32>,
+    /// Score of each scoring function
+    #[serde(default)]
+    pub scorers: BTreeMap<String, f32>,
+}
+
+/// Per-case scores of a reference evaluation run
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub cases: Vec<BaselineCase>,
+}
+
+/// Score differences below this are rounding noise, never regressions
+const SCORE_EPSILON: f32 = 1e-6;
+
+/// Thresholds deciding what counts as a regression
+///
+/// A drop counts only when it exceeds its threshold, so rounding noise of the scores
+/// never fails a run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RegressionPolicy {
+    /// Score drop beyond which a case is flagged (defaults to 0.1)
+    pub case_drop: f32,
+    /// Mean score drop beyond which a provider regressed (defaults to 0.01)
+    pub mean_drop: f32,
+}
+
+impl Default for RegressionPolicy {
+    fn default() -> Self {
+        Self {
+            case_drop: 0.1,
+            mean_drop: 0.01,
+        }
+    }
+}
+
+/// Score change of one case between the baseline and the current run
+#[derive(Debug, Clone, Serialize)]
+pub struct CaseComparison {
+    pub case_id: String,
+    pub provider_id: String,
+    /// Baseline score
+    pub baseline: f32,
+    /// Current score, `None` when the case failed
+    pub current: Option<f32>,
+    /// Current minus baseline score, a failed case counting as 0
+    pub delta: f32,
+    /// Whether the score dropped by more than [`RegressionPolicy::case_drop`]
+    pub regressed: bool,
+}
+
+/// Mean score change of one provider over the cases scored in both runs
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderComparison {
+    pub provider_id: String,
+    /// Number of compared cases
+    pub cases: usize,
+    /// Mean baseline score
+    pub baseline_mean: f32,
+    /// Mean current score, failed cases counting as 0
+    pub current_mean: f32,
+    /// Current minus baseline mean
+    pub delta: f32,
+    /// Whether the mean dropped by more than [`RegressionPolicy::mean_drop`]
+    pub regressed: bool,
+}
+
+/// Comparison of an evaluation run against a baseline
+#[derive(Debug, Clone, Serialize)]
+pub struct RegressionReport {
+    /// Cases scored in the baseline and run again, in current report order
+    pub cases: Vec<CaseComparison>,
+    /// Per-provider aggregates, in current report order
+    pub providers: Vec<ProviderComparison>,
+    /// (provider id, case id) pairs of the baseline missing from the current run
+    pub missing: Vec<(String, String)>,
+    /// (provider id, case id) pairs of the current run absent from the baseline
+    pub added: Vec<(String, String)>,
+}
+
+impl Baseline {
+    /// Records the per-case scores of a report
+    pub fn from_report(report: &EvalReport) -> Self {
+        Self {
+            cases: report
+                .results
+                .iter()
+                .map(|r| BaselineCase {
+                    case_id: r.case_id.clone(),
+                    provider_id: r.provider_id.clone(),
+                    score: r.score,
+                    scorers: r
+                        .breakdown
+                        .iter()
+                        .map(|d| (d.name.clone(), d.score))
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Loads a baseline saved with [`Baseline::save`]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LLMError> {
+        Ok(serde_json::from_str(&read_file(path.as_ref())?)?)
+    }
+
+    /// Saves the baseline as JSON
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), LLMError> {
+        write_file(path.as_ref(), &serde_json::to_string_pretty(self)?)
+    }
+
+    /// Returns the recorded case of a provider
+    pub fn case(&self, provider_id: &str, case_id: &str) -> Option<&BaselineCase> {
+        self.cases
+            .iter()
+            .find(|c| c.provider_id == provider_id && c.case_id == case_id)
+    }
+
+    /// Compares a later run against this baseline
+    ///
+    /// Cases that failed in the baseline are not compared; cases that fail in the current
+    /// run count as a score of 0.
+    pub fn compare(&self, report: &EvalReport, policy: &RegressionPolicy) -> RegressionReport {
+        let mut cases = Vec::new();
+        let mut added = Vec::new();
+        for result in &report.results {
+            let Some(recorded) = self.case(&result.provider_id, &result.case_id) else {
+                added.push((result.provider_id.clone(), result.case_id.clone()));
+                continue;
+            };
+            let Some(baseline) = recorded.score else {
+                continue;
+            };
+            let delta = result.score.unwrap_or(0.0) - baseline;
+            cases.push(CaseComparison {
+                case_id: result.case_id.clone(),
+                provider_id: result.provider_id.clone(),
+                baseline,
+                current: result.score,
+                delta,
+                regressed: -delta > policy.case_drop + SCORE_EPSILON,
+            });
+        }
+
+        let missing = self
+            .cases
+            .iter()
+            .filter(|c| {
+                !report
+                    .results
+                    .iter()
+                    .any(|r| r.provider_id == c.provider_id && r.case_id == c.case_id)
+            })
+            .map(|c| (c.provider_id.clone(), c.case_id.clone()))
+            .collect();
+
+        let providers = report
+            .providers
+            .iter()
+            .filter_map(|summary| {
+                let compared: Vec<&CaseComparison> = cases
+                    .iter()
+                    .filter(|c| c.provider_id == summary.provider_id)
+                    .collect();
+                if compared.is_empty() {
+                    return None;
+                }
+                let n = compared.len() as f32;
+                let baseline_mean = compared.iter().map(|c| c.baseline).sum::<f32>() / n;
+                let current_mean = compared
+                    .iter()
+                    .map(|c| c.current.unwrap_or(0.0))
+                    .sum::<f32>()
+                    / n;
+                let delta = current_mean - baseline_mean;
+                Some(ProviderComparison {
+                    provider_id: summary.provider_id.clone(),
+                    cases: compared.len(),
+                    baseline_mean,
+                    current_mean,
+                    delta,
+                    regressed: -delta > policy.mean_drop + SCORE_EPSILON,
+                })
+            })
+            .collect();
+
+        RegressionReport {
+            cases,
+            providers,
+            missing,
+            added,
+        }
+    }
+}
+
+impl EvalReport {
+    /// Records the per-case scores of this report as a baseline
+    pub fn baseline(&self) -> Baseline {
+        Baseline::from_report(self)
+    }
+}
+
+impl RegressionReport {
+    /// Cases whose score dropped beyond the case threshold
+    pub fn regressions(&self) -> impl Iterator<Item = &CaseComparison> {
+        self.cases.iter().filter(|c| c.regressed)
+    }
+
+    /// Whether no provider's mean score regressed
+    pub fn passed(&self) -> bool {
+        !self.providers.iter().any(|p| p.regressed)
+    }
+
+    /// Process exit code for CI: 0 when passed, 1 when quality regressed
+    pub fn exit_code(&self) -> i32 {
+        if self.passed() {
+            0
+        } else {
+            1
+        }
+    }
+
+    /// Serializes the comparison as pretty-printed JSON
+    pub fn to_json(&self) -> Result<String, LLMError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Human-readable summary, one line per provider then one per regressed case
+impl fmt::Display for RegressionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for p in &self.providers {
+            writeln!(
+                f,
+                "{} {}: mean {:.3} -> {:.3} ({:+.3}) over {} cases",
+                if p.regressed { "REGRESSED" } else { "ok" },
+                p.provider_id,
+                p.baseline_mean,
+                p.current_mean,
+                p.delta,
+                p.cases
+            )?;
+        }
+        for c in self.regressions() {
+            writeln!(
+                f,
+                "  {} / {}: {:.3} -> {} ({:+.3})",
+                c.provider_id,
+                c.case_id,
+                c.baseline,
+                c.current
+                    .map(|s| format!("{:.3}", s))
+                    .unwrap_or_else(|| "failed".to_string()),
+                c.delta
+            )?;
+        }
+        if !self.missing.is_empty() {
+            writeln!(f, "{} baseline cases were not run", self.missing.len())?;
+        }
+        if !self.added.is_empty() {
+            writeln!(f, "{} cases have no baseline", self.added.len())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::{EvalDataset, LLMEvaluator, NormalizedMatch, ReplayProvider};
+
+    async fn run(answers: [&str; 3]) -> EvalReport {
+        let dataset = EvalDataset::from_reader(
+            [
+                r#"{"id": "a", "messages": [{"role": "user", "content": "A?"}], "reference": "a"}"#,
+                r#"{"id": "b", "messages": [{"role": "user", "content": "B?"}], "reference": "b"}"#,
+                r#"{"id": "c", "messages": [{"role": "user", "content": "C?"}], "reference": "c"}"#,
+            ]
+            .join("\n")
+            .as_bytes(),
+        )
+        .unwrap();
+        let replay = ReplayProvider::new()
+            .response("A?", answers[0])
+            .response("B?", answers[1])
+            .response("C?", answers[2]);
+        LLMEvaluator::named(vec![("replay".to_string(), Box::new(replay))])
+            .scorer(NormalizedMatch)
+            .dataset(&dataset)
+            .run()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn unchanged_scores_pass_the_default_policy() {
+        let baseline = run(["a", "b", "c"]).await.baseline();
+        let comparison =
+            baseline.compare(&run(["a", "b", "c"]).await, &RegressionPolicy::default());
+
+        assert!(comparison.passed());
+        assert_eq!(comparison.exit_code(), 0);
+        assert_eq!(comparison.regressions().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn dropped_case_fails_the_run() {
+        let baseline = run(["a", "b", "c"]).await.baseline();
+        let comparison =
+            baseline.compare(&run(["a", "x", "c"]).await, &RegressionPolicy::default());
+
+        assert!(!comparison.passed());
+        let regressed: Vec<&str> = comparison
+            .regressions()
+            .map(|c| c.case_id.as_str())
+            .collect();
+        assert_eq!(regressed, vec!["b"]);
+        assert!(comparison.to_string().contains("REGRESSED replay"));
+    }
+
+    #[tokio::test]
+    async fn rounding_noise_is_not_a_regression() {
+        let policy = RegressionPolicy {
+            case_drop: 0.0,
+            mean_drop: 0.0,
+        };
+        let mut baseline_report = run(["a", "b", "c"]).await;
+        let mut report = baseline_report.clone();
+        for (recorded, current) in baseline_report.results.iter_mut().zip(&mut report.results) {
+            recorded.score = Some(0.3);
+            current.score = Some(0.1 + 0.2 - 1e-7);
+        }
+
+        let comparison = baseline_report.baseline().compare(&report, &policy);
+
+        assert!(comparison
+            .cases
+            .iter()
+            .all(|c| c.delta < 0.0 && !c.regressed));
+        assert!(comparison.passed());
+    }
+
+    #[test]
+    fn baseline_round_trips_through_a_file() {
+        let baseline = Baseline {
+            cases: vec![BaselineCase {
+                case_id: "a".to_string(),
+                provider_id: "p".to_string(),
+                score: None,
+                scorers: BTreeMap::from([("exact".to_string(), 1.0)]),
+            }],
+        };
+        let path = std::env::temp_dir().join(format!("rllm_baseline_{}.json", std::process::id()));
+        baseline.save(&path).unwrap();
+        let loaded = Baseline::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, baseline);
+    }
+}
@@ -0,0 +1,365 @@
+//! Best-of-N selection as a drop-in provider.
+//!
+//! [`BestOfN`] implements [`LLMProvider`] by fanning each chat or completion request out
+//! to every provider of an [`LLMEvaluator`] (each sampled [`LLMEvaluator::samples`] times),
+//! scoring the candidates with the evaluator scorers and judge, and returning the
+//! highest-scoring one. It can be registered in a chain registry or served over REST like
+//! any other backend.
+
+use std::fmt;
+
+use async_trait::async_trait;
+use futures::future::join_all;
+
+use crate::{
+    chat::{ChatMessage, ChatProvider, ChatResponse, ChatRole, MessageType, Tool},
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    stt::SpeechToTextProvider,
+    LLMProvider, ToolCall,
+};
+
+use super::{EvalResult, LLMEvaluator, ScoreDetail};
+
+/// Score of one candidate response
+#[derive(Debug, Clone, PartialEq)]
+pub struct CandidateScore {
+    /// Provider that produced the candidate
+    pub provider_id: String,
+    /// Total score of the candidate
+    pub score: f32,
+}
+
+/// Owned copy of a provider response
+///
+/// `Box<dyn ChatResponse>` is not `Send`, so candidates are snapshotted as soon as
+/// they arrive to keep the fan-out future sendable.
+#[derive(Debug, Clone)]
+struct ResponseSnapshot {
+    text: Option<String>,
+    tool_calls: Option<Vec<ToolCall>>,
+    thinking: Option<String>,
+    display: String,
+}
+
+impl ResponseSnapshot {
+    fn new(response: Box<dyn ChatResponse>) -> Self {
+        Self {
+            text: response.text(),
+            tool_calls: response.tool_calls(),
+            thinking: response.thinking(),
+            display: response.to_string(),
+        }
+    }
+}
+
+/// Chat response selected by [`BestOfN`], with its scores attached
+#[derive(Debug, Clone)]
+pub struct ScoredResponse {
+    inner: ResponseSnapshot,
+    /// Provider that produced the selected response
+    pub provider_id: String,
+    /// Total score of the selected response
+    pub score: f32,
+    /// Per-scorer scores of the selected response
+    pub breakdown: Vec<ScoreDetail>,
+    /// Scores of every candidate, in provider and sample order
+    pub candidates: Vec<CandidateScore>,
+}
+
+impl fmt::Display for ScoredResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.inner.display)
+    }
+}
+
+impl ChatResponse for ScoredResponse {
+    fn text(&self) -> Option<String> {
+        self.inner.text.clone()
+    }
+
+    fn tool_calls(&self) -> Option<Vec<ToolCall>> {
+        self.inner.tool_calls.clone()
+    }
+
+    fn thinking(&self) -> Option<String> {
+        self.inner.thinking.clone()
+    }
+}
+
+/// Provider returning the best of several candidate responses
+pub struct BestOfN {
+    evaluator: LLMEvaluator,
+}
+
+impl BestOfN {
+    /// Creates a best-of-N provider from an evaluator
+    ///
+    /// The evaluator providers are the candidates, [`LLMEvaluator::samples`] sets how many
+    /// responses each of them produces, and its scorers and judge rank the candidates.
+    pub fn new(evaluator: LLMEvaluator) -> Self {
+        Self { evaluator }
+    }
+
+    /// Sends the messages to every candidate and returns the best response with its scores
+    pub async fn chat_scored(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+    ) -> Result<ScoredResponse, LLMError> {
+        let requests = self.candidates().map(|(provider_id, llm)| async move {
+            let response = ResponseSnapshot::new(llm.chat_with_tools(messages, tools).await?);
+            let eval = self
+                .evaluator
+                .score(messages, response.text.clone().unwrap_or_default(), None)
+                .await?;
+            Ok::<_, LLMError>((provider_id, response, eval))
+        });
+
+        let mut best: Option<(&str, ResponseSnapshot, EvalResult)> = None;
+        let mut candidates = Vec::new();
+        let mut errors = Vec::new();
+        for outcome in join_all(requests).await {
+            let (provider_id, response, eval) = match outcome {
+                Ok(candidate) => candidate,
+                Err(e) => {
+                    errors.push(e.to_string());
+                    continue;
+                }
+            };
+            candidates.push(CandidateScore {
+                provider_id: provider_id.to_string(),
+                score: eval.score,
+            });
+            if best.as_ref().is_none_or(|(_, _, b)| eval.score > b.score) {
+                best = Some((provider_id, response, eval));
+            }
+        }
+
+        let (provider_id, inner, eval) = best.ok_or_else(|| {
+            LLMError::ProviderError(format!("All candidates failed: {}", errors.join("; ")))
+        })?;
+        Ok(ScoredResponse {
+            inner,
+            provider_id: provider_id.to_string(),
+            score: eval.score,
+            breakdown: eval.breakdown,
+            candidates,
+        })
+    }
+
+    /// Every (provider id, provider) pair to query, repeated once per sample
+    fn candidates(&self) -> impl Iterator<Item = (&str, &dyn LLMProvider)> {
+        self.evaluator.llms.iter().flat_map(move |(id, llm)| {
+            std::iter::repeat_n((id.as_str(), llm.as_ref()), self.evaluator.samples)
+        })
+    }
+
+    /// First candidate provider, used for requests that are not selected among candidates
+    fn primary(&self) -> Result<&dyn LLMProvider, LLMError> {
+        self.evaluator
+            .llms
+            .first()
+            .map(|(_, llm)| llm.as_ref())
+            .ok_or_else(|| LLMError::InvalidRequest("BestOfN has no candidate provider".into()))
+    }
+}
+
+impl LLMProvider for BestOfN {}
+
+#[async_trait]
+impl ChatProvider for BestOfN {
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        Ok(Box::new(self.chat_scored(messages, tools).await?))
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for BestOfN {
+    /// Completes with every candidate and returns the best-scoring completion
+    async fn complete(&self, req: &CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        let prompt = [ChatMessage {
+            role: ChatRole::User,
+            message_type: MessageType::Text,
+            content: req.prompt.clone(),
+        }];
+        let requests = self.candidates().map(|(_, llm)| {
+            let prompt = &prompt;
+            async move {
+                let response = llm.complete(req).await?;
+                let eval = self
+                    .evaluator
+                    .score(prompt, response.text.clone(), None)
+                    .await?;
+                Ok::<_, LLMError>((response, eval.score))
+            }
+        });
+
+        let mut best: Option<(CompletionResponse, f32)> = None;
+        let mut errors = Vec::new();
+        for outcome in join_all(requests).await {
+            match outcome {
+                Ok((response, score)) if best.as_ref().is_none_or(|(_, b)| score > *b) => {
+                    best = Some((response, score))
+                }
+                Ok(_) => {}
+                Err(e) => errors.push(e.to_string()),
+            }
+        }
+
+        best.map(|(response, _)| response).ok_or_else(|| {
+            LLMError::ProviderError(format!("All candidates failed: {}", errors.join("; ")))
+        })
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for BestOfN {
+    /// Embeddings are not selected among candidates, the first provider computes them
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        self.primary()?.embed(input).await
+    }
+}
+
+#[async_trait]
+impl SpeechToTextProvider for BestOfN {
+    async fn transcribe(&self, audio: Vec<u8>) -> Result<String, LLMError> {
+        self.primary()?.transcribe(audio).await
+    }
+
+    async fn transcribe_file(&self, file_path: &str) -> Result<String, LLMError> {
+        self.primary()?.transcribe_file(file_path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockLLM;
+
+    /// Scores responses by length, up to 10 characters
+    fn best_of(llms: Vec<(&str, MockLLM)>) -> BestOfN {
+        let llms = llms
+            .into_iter()
+            .map(|(id, llm)| (id.to_string(), Box::new(llm) as Box<dyn LLMProvider>))
+            .collect();
+        BestOfN::new(
+            LLMEvaluator::named(llms).named_scoring("length", |text| text.len() as f32 / 10.0),
+        )
+    }
+
+    fn ask() -> Vec<ChatMessage> {
+        vec![ChatMessage::user().content("Name a color").build()]
+    }
+
+    #[tokio::test]
+    async fn chat_selects_the_highest_score() {
+        let best = best_of(vec![
+            ("short", MockLLM::new(["red"])),
+            ("long", MockLLM::new(["turquoise"])),
+            ("mid", MockLLM::new(["green"])),
+        ]);
+        let response = best.chat_scored(&ask(), None).await.unwrap();
+
+        assert_eq!(response.text().as_deref(), Some("turquoise"));
+        assert_eq!(response.to_string(), "turquoise");
+        assert_eq!(response.provider_id, "long");
+        assert!((response.score - 0.9).abs() < 1e-6);
+        assert_eq!(response.breakdown[0].name, "length");
+        let ids: Vec<&str> = response
+            .candidates
+            .iter()
+            .map(|c| c.provider_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["short", "long", "mid"]);
+    }
+
+    #[tokio::test]
+    async fn each_provider_is_sampled() {
+        let llms = vec![(
+            "mock".to_string(),
+            Box::new(MockLLM::new(["blue", "indigo", "red"])) as Box<dyn LLMProvider>,
+        )];
+        let best = BestOfN::new(
+            LLMEvaluator::named(llms)
+                .samples(3)
+                .scoring(|text| text.len() as f32),
+        );
+        let response = best.chat(&ask()).await.unwrap();
+
+        assert_eq!(response.text().as_deref(), Some("indigo"));
+    }
+
+    #[tokio::test]
+    async fn ties_keep_the_first_candidate() {
+        let best = best_of(vec![
+            ("first", MockLLM::new(["cyan"])),
+            ("second", MockLLM::new(["pink"])),
+        ]);
+        let response = best.chat_scored(&ask(), None).await.unwrap();
+        assert_eq!(response.provider_id, "first");
+
+        let completion = best
+            .complete(&CompletionRequest::new("Name a color"))
+            .await
+            .unwrap();
+        assert_eq!(completion.text, "cyan");
+    }
+
+    #[tokio::test]
+    async fn failed_candidates_are_skipped() {
+        let best = best_of(vec![
+            ("down", MockLLM::failing("outage")),
+            ("up", MockLLM::new(["red"])),
+        ]);
+        let response = best.chat_scored(&ask(), None).await.unwrap();
+        assert_eq!(response.provider_id, "up");
+        assert_eq!(response.candidates.len(), 1);
+
+        let completion = best
+            .complete(&CompletionRequest::new("Name a color"))
+            .await
+            .unwrap();
+        assert_eq!(completion.text, "red");
+    }
+
+    #[tokio::test]
+    async fn all_failures_are_reported() {
+        let best = best_of(vec![
+            ("a", MockLLM::failing("outage")),
+            ("b", MockLLM::failing("rate limited")),
+        ]);
+        let chat = best.chat(&ask()).await;
+        assert!(matches!(
+            chat,
+            Err(LLMError::ProviderError(message))
+                if message == "All candidates failed: Provider Error: outage; \
+                               Provider Error: rate limited"
+        ));
+        let completion = best.complete(&CompletionRequest::new("Name a color")).await;
+        assert!(matches!(completion, Err(LLMError::ProviderError(_))));
+
+        let empty = best_of(Vec::new());
+        assert!(matches!(
+            empty.embed(vec!["red".to_string()]).await,
+            Err(LLMError::InvalidRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn embeddings_come_from_the_first_provider() {
+        let best = best_of(vec![
+            ("first", MockLLM::default().surplus_embeddings(1)),
+            ("second", MockLLM::default()),
+        ]);
+
+        let embeddings = best.embed(vec!["ab".to_string()]).await.unwrap();
+        assert_eq!(embeddings.len(), 2);
+        assert_eq!(&embeddings[0][..3], &[1.0, 1.0, 0.0]);
+    }
+}
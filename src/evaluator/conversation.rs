@@ -0,0 +1,320 @@
+//! Scripted multi-turn conversation evaluation.
+//!
+//! A [`ConversationScript`] drives a conversation with each evaluated provider for several
+//! turns, the user side being either fixed follow-up messages or a simulator provider
+//! playing a persona. Scorers and the judge then grade the whole transcript.
+
+use futures::future::join_all;
+
+use crate::{
+    chat::{ChatMessage, ChatRole, MessageType},
+    error::LLMError,
+    LLMProvider,
+};
+
+use super::{EvalResult, LLMEvaluator};
+
+/// Answer of the user simulator ending the conversation early
+pub const END_OF_CONVERSATION: &str = "[END]";
+
+/// Instructions given to the user simulator, after its persona
+const SIMULATOR_INSTRUCTIONS: &str = "You are role-playing the user in a conversation with an AI \
+    assistant. Stay in character and write only the user's next message, without any prefix. \
+    If the user would have nothing more to say, answer exactly [END].";
+
+/// Source of the user turns
+enum SimulatedUser {
+    /// Fixed follow-up messages, sent in order
+    Scripted(Vec<String>),
+    /// Provider playing a persona, reacting to the transcript so far
+    Persona {
+        llm: Box<dyn LLMProvider>,
+        persona: String,
+    },
+}
+
+/// Multi-turn conversation to play with every evaluated provider
+pub struct ConversationScript {
+    opening: String,
+    user: SimulatedUser,
+    turns: usize,
+}
+
+impl ConversationScript {
+    /// Creates a script with an opening message followed by fixed follow-ups
+    ///
+    /// The conversation lasts one assistant turn per user message.
+    pub fn scripted<I, S>(opening: impl Into<String>, follow_ups: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let follow_ups: Vec<String> = follow_ups.into_iter().map(Into::into).collect();
+        Self {
+            opening: opening.into(),
+            turns: follow_ups.len() + 1,
+            user: SimulatedUser::Scripted(follow_ups),
+        }
+    }
+
+    /// Creates a script where `simulator` plays a user described by `persona`
+    ///
+    /// The conversation lasts 3 assistant turns unless changed with
+    /// [`ConversationScript::turns`], or until the simulator answers [`END_OF_CONVERSATION`].
+    pub fn simulated(
+        opening: impl Into<String>,
+        simulator: Box<dyn LLMProvider>,
+        persona: impl Into<String>,
+    ) -> Self {
+        Self {
+            opening: opening.into(),
+            user: SimulatedUser::Persona {
+                llm: simulator,
+                persona: persona.into(),
+            },
+            turns: 3,
+        }
+    }
+
+    /// Sets the maximum number of assistant turns
+    ///
+    /// Scripted conversations also stop when they run out of follow-ups.
+    pub fn turns(mut self, turns: usize) -> Self {
+        self.turns = turns.max(1);
+        self
+    }
+
+    /// Plays the conversation with one provider and returns the transcript
+    pub async fn play(&self, llm: &dyn LLMProvider) -> Result<Vec<ChatMessage>, LLMError> {
+        let mut transcript = vec![text_message(ChatRole::User, self.opening.clone())];
+
+        for turn in 0..self.turns {
+            let response = llm.chat(&transcript).await?.text().unwrap_or_default();
+            transcript.push(text_message(ChatRole::Assistant, response));
+
+            if turn + 1 == self.turns {
+                break;
+            }
+            match self.next_user_message(turn, &transcript).await? {
+                Some(message) => transcript.push(text_message(ChatRole::User, message)),
+                None => break,
+            }
+        }
+        Ok(transcript)
+    }
+
+    /// User message following assistant turn `turn`, `None` when the user is done
+    async fn next_user_message(
+        &self,
+        turn: usize,
+        transcript: &[ChatMessage],
+    ) -> Result<Option<String>, LLMError> {
+        match &self.user {
+            SimulatedUser::Scripted(follow_ups) => Ok(follow_ups.get(turn).cloned()),
+            SimulatedUser::Persona { llm, persona } => {
+                let prompt = format!(
+                    "{}\n\nPersona:\n{}\n\nConversation so far:\n{}\n\nUser:",
+                    SIMULATOR_INSTRUCTIONS,
+                    persona,
+                    render_transcript(transcript)
+                );
+                let message = llm
+                    .chat(&[text_message(ChatRole::User, prompt)])
+                    .await?
+                    .text()
+                    .unwrap_or_default();
+                let message = message.trim();
+                if message.is_empty() || message == END_OF_CONVERSATION {
+                    Ok(None)
+                } else {
+                    Ok(Some(message.to_string()))
+                }
+            }
+        }
+    }
+}
+
+/// Scored conversation of one provider
+#[derive(Debug, Clone)]
+pub struct ConversationResult {
+    /// Provider identifier
+    pub provider_id: String,
+    /// Every message of the conversation, starting with the opening user message
+    pub transcript: Vec<ChatMessage>,
+    /// Scores of the whole transcript; `text` holds the rendered transcript
+    pub eval: EvalResult,
+}
+
+impl ConversationResult {
+    /// Number of assistant turns played
+    pub fn turns(&self) -> usize {
+        self.transcript
+            .iter()
+            .filter(|m| m.role == ChatRole::Assistant)
+            .count()
+    }
+}
+
+impl LLMEvaluator {
+    /// Plays a conversation script with every provider and scores the transcripts
+    ///
+    /// Scorers and the judge receive the opening message as `messages` and the whole
+    /// transcript, rendered as "User: ..." / "Assistant: ..." lines, as the response.
+    pub async fn evaluate_conversation(
+        &self,
+        script: &ConversationScript,
+    ) -> Result<Vec<ConversationResult>, LLMError> {
+        let plays = join_all(self.llms.iter().map(|(_, llm)| script.play(llm.as_ref()))).await;
+
+        let mut results = Vec::with_capacity(plays.len());
+        for ((provider_id, _), transcript) in self.llms.iter().zip(plays) {
+            let transcript = transcript?;
+            let eval = self
+                .score(&transcript[..1], render_transcript(&transcript), None)
+                .await?;
+            results.push(ConversationResult {
+                provider_id: provider_id.clone(),
+                transcript,
+                eval,
+            });
+        }
+        Ok(results)
+    }
+}
+
+/// Renders text messages as "User: ..." / "Assistant: ..." lines
+pub(super) fn render_transcript(messages: &[ChatMessage]) -> String {
+    messages
+        .iter()
+        .filter(|m| m.message_type == MessageType::Text)
+        .map(|m| {
+            let role = match m.role {
+                ChatRole::User => "User",
+                ChatRole::Assistant => "Assistant",
+            };
+            format!("{}: {}", role, m.content)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn text_message(role: ChatRole, content: String) -> ChatMessage {
+    ChatMessage {
+        role,
+        message_type: MessageType::Text,
+        content,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockLLM;
+
+    fn contents(transcript: &[ChatMessage]) -> Vec<(ChatRole, &str)> {
+        transcript
+            .iter()
+            .map(|m| (m.role.clone(), m.content.as_str()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn scripted_turns_follow_each_answer() {
+        let llm = MockLLM::new(["Hi!", "Paris.", "You're welcome."]);
+        let script = ConversationScript::scripted("Hello", ["Capital of France?", "Thanks"]);
+
+        let transcript = script.play(&llm).await.unwrap();
+
+        assert_eq!(
+            contents(&transcript),
+            vec![
+                (ChatRole::User, "Hello"),
+                (ChatRole::Assistant, "Hi!"),
+                (ChatRole::User, "Capital of France?"),
+                (ChatRole::Assistant, "Paris."),
+                (ChatRole::User, "Thanks"),
+                (ChatRole::Assistant, "You're welcome."),
+            ]
+        );
+        // Each turn sends the whole conversation so far
+        let sent: Vec<usize> = llm.requests.lock().unwrap().iter().map(Vec::len).collect();
+        assert_eq!(sent, vec![1, 3, 5]);
+    }
+
+    #[tokio::test]
+    async fn turns_cap_scripted_conversations() {
+        let llm = MockLLM::new(["ok"]);
+        let script = ConversationScript::scripted("One", ["Two", "Three"]).turns(2);
+
+        let transcript = script.play(&llm).await.unwrap();
+
+        assert_eq!(transcript.len(), 4);
+        assert_eq!(transcript[3].role, ChatRole::Assistant);
+    }
+
+    #[tokio::test]
+    async fn simulated_user_stops_at_end_of_conversation() {
+        let simulator = MockLLM::new(["How much is it?", " [END] "]);
+        let script =
+            ConversationScript::simulated("I want a bike", Box::new(simulator), "A thrifty buyer")
+                .turns(5);
+        let llm = MockLLM::new(["Which kind?", "About 300 euros."]);
+
+        let transcript = script.play(&llm).await.unwrap();
+
+        assert_eq!(
+            contents(&transcript),
+            vec![
+                (ChatRole::User, "I want a bike"),
+                (ChatRole::Assistant, "Which kind?"),
+                (ChatRole::User, "How much is it?"),
+                (ChatRole::Assistant, "About 300 euros."),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn empty_simulator_answers_end_the_conversation() {
+        let script = ConversationScript::simulated("Hi", Box::new(MockLLM::new([" "])), "Shy");
+        let transcript = script.play(&MockLLM::new(["Hello"])).await.unwrap();
+        assert_eq!(transcript.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn transcripts_are_scored_per_provider() {
+        let llms: Vec<(String, Box<dyn LLMProvider>)> = vec![
+            ("brief".to_string(), Box::new(MockLLM::new(["Yes."]))),
+            (
+                "chatty".to_string(),
+                Box::new(MockLLM::new(["Yes, of course!"])),
+            ),
+        ];
+        let evaluator = LLMEvaluator::named(llms).named_scoring("length", |text| text.len() as f32);
+        let script = ConversationScript::scripted("Ready?", ["Sure?"]);
+
+        let results = evaluator.evaluate_conversation(&script).await.unwrap();
+
+        assert_eq!(results[0].provider_id, "brief");
+        assert_eq!(results[0].turns(), 2);
+        assert_eq!(
+            results[0].eval.text,
+            "User: Ready?\nAssistant: Yes.\nUser: Sure?\nAssistant: Yes."
+        );
+        assert_eq!(results[0].eval.score, results[0].eval.text.len() as f32);
+        assert!(results[1].eval.score > results[0].eval.score);
+    }
+
+    #[test]
+    fn render_transcript_skips_non_text_messages() {
+        let messages = vec![
+            text_message(ChatRole::User, "Look".to_string()),
+            ChatMessage {
+                role: ChatRole::User,
+                message_type: MessageType::ImageURL("https://example.com/a.png".to_string()),
+                content: String::new(),
+            },
+            text_message(ChatRole::Assistant, "A cat".to_string()),
+        ];
+        assert_eq!(render_transcript(&messages), "User: Look\nAssistant: A cat");
+    }
+}
@@ -0,0 +1,333 @@
+//! Dataset-driven evaluation.
+//!
+//! An [`EvalDataset`] is loaded from JSONL, one case per line:
+//!
+//! ```json
+//! {"id": "greeting", "messages": [{"role": "user", "content": "Say hi"}], "reference": "Hi!", "tags": ["smoke"]}
+//! ```
+//!
+//! Every case is sent to every provider of an [`LLMEvaluator`] with bounded concurrency,
+//! and the scored results are aggregated into an [`EvalReport`].
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read},
+    path::Path,
+    time::Instant,
+};
+
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    chat::{ChatMessage, ChatRole, MessageType},
+    error::LLMError,
+};
+
+use super::{
+    report::{CaseResult, EvalReport, Pricing},
+    LLMEvaluator,
+};
+
+/// Chat message as written in a dataset file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CaseMessage {
+    /// "user" or "assistant"
+    pub role: String,
+    /// Text content of the message
+    pub content: String,
+}
+
+impl CaseMessage {
+    /// Converts the message into a chat message
+    pub fn to_chat_message(&self) -> Result<ChatMessage, LLMError> {
+        let role = match self.role.to_lowercase().as_str() {
+            "user" => ChatRole::User,
+            "assistant" => ChatRole::Assistant,
+            other => {
+                return Err(LLMError::InvalidRequest(format!(
+                    "Unknown message role in dataset: {}",
+                    other
+                )))
+            }
+        };
+        Ok(ChatMessage {
+            role,
+            message_type: MessageType::Text,
+            content: self.content.clone(),
+        })
+    }
+}
+
+/// A single evaluation case
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvalCase {
+    /// Case identifier, defaults to the line number when missing
+    #[serde(default)]
+    pub id: String,
+    /// Conversation sent to each provider
+    pub messages: Vec<CaseMessage>,
+    /// Optional reference answer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    /// Free-form tags used to slice reports
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl EvalCase {
+    /// Returns the case conversation as chat messages
+    pub fn chat_messages(&self) -> Result<Vec<ChatMessage>, LLMError> {
+        self.messages.iter().map(|m| m.to_chat_message()).collect()
+    }
+}
+
+/// A collection of evaluation cases
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EvalDataset {
+    /// Cases in file order
+    pub cases: Vec<EvalCase>,
+}
+
+impl EvalDataset {
+    /// Creates a dataset from in-memory cases
+    pub fn new(cases: Vec<EvalCase>) -> Self {
+        Self { cases }
+    }
+
+    /// Loads a dataset from a JSONL file
+    pub fn from_jsonl(path: impl AsRef<Path>) -> Result<Self, LLMError> {
+        let file = std::fs::File::open(path.as_ref()).map_err(|e| {
+            LLMError::InvalidRequest(format!(
+                "Cannot open dataset {}: {}",
+                path.as_ref().display(),
+                e
+            ))
+        })?;
+        Self::from_reader(file)
+    }
+
+    /// Loads a dataset from JSONL content, skipping blank lines
+    ///
+    /// Results, baselines and replays are keyed by case id, so duplicate ids are rejected.
+    pub fn from_reader(reader: impl Read) -> Result<Self, LLMError> {
+        let mut cases = Vec::new();
+        let mut lines_by_id = HashMap::new();
+        for (i, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line.map_err(|e| LLMError::InvalidRequest(e.to_string()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut case: EvalCase = serde_json::from_str(&line).map_err(|e| {
+                LLMError::JsonError(format!("Invalid case on line {}: {}", i + 1, e))
+            })?;
+            if case.id.is_empty() {
+                case.id = format!("case_{}", i + 1);
+            }
+            if let Some(first) = lines_by_id.insert(case.id.clone(), i + 1) {
+                return Err(LLMError::InvalidRequest(format!(
+                    "Duplicate case id '{}' on line {}, already used on line {}",
+                    case.id,
+                    i + 1,
+                    first
+                )));
+            }
+            cases.push(case);
+        }
+        Ok(Self { cases })
+    }
+
+    /// Returns the cases carrying the given tag
+    pub fn with_tag(&self, tag: &str) -> Self {
+        Self {
+            cases: self
+                .cases
+                .iter()
+                .filter(|c| c.tags.iter().any(|t| t == tag))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// Evaluation of every provider of an evaluator over a dataset
+pub struct DatasetEvaluation<'a> {
+    evaluator: &'a LLMEvaluator,
+    dataset: &'a EvalDataset,
+    concurrency: usize,
+    pass_threshold: Option<f32>,
+    pricing: HashMap<String, Pricing>,
+}
+
+impl<'a> DatasetEvaluation<'a> {
+    pub(super) fn new(evaluator: &'a LLMEvaluator, dataset: &'a EvalDataset) -> Self {
+        Self {
+            evaluator,
+            dataset,
+            concurrency: 4,
+            pass_threshold: None,
+            pricing: HashMap::new(),
+        }
+    }
+
+    /// Sets the maximum number of requests in flight (defaults to 4)
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Marks cases scoring below `threshold` as failed in reports
+    pub fn pass_threshold(mut self, threshold: f32) -> Self {
+        self.pass_threshold = Some(threshold);
+        self
+    }
+
+    /// Sets the token pricing of a provider, used to estimate run cost
+    pub fn pricing(mut self, provider_id: impl Into<String>, pricing: Pricing) -> Self {
+        self.pricing.insert(provider_id.into(), pricing);
+        self
+    }
+
+    /// Runs every case against every provider and aggregates the results
+    pub async fn run(self) -> Result<EvalReport, LLMError> {
+        let mut jobs = Vec::new();
+        for case in &self.dataset.cases {
+            let messages = case.chat_messages()?;
+            for (provider_id, llm) in &self.evaluator.llms {
+                jobs.push((case, messages.clone(), provider_id.as_str(), llm.as_ref()));
+            }
+        }
+
+        let mut results: Vec<CaseResult> = stream::iter(jobs)
+            .map(|(case, messages, provider_id, llm)| async move {
+                let start = Instant::now();
+                let response = llm.chat(&messages).await;
+                let latency_ms = start.elapsed().as_millis();
+
+                let mut result = CaseResult::new(case, provider_id, latency_ms);
+                match response {
+                    Ok(response) => {
+                        let text = response.text().unwrap_or_default();
+                        match self
+                            .evaluator
+                            .score(&messages, text.clone(), case.reference.as_deref())
+                            .await
+                        {
+                            Ok(eval) => result.record(&messages, eval),
+                            Err(e) => {
+                                result.text = text;
+                                result.error = Some(format!("Scoring failed: {}", e));
+                            }
+                        }
+                    }
+                    Err(e) => result.error = Some(e.to_string()),
+                }
+                result
+            })
+            .buffer_unordered(self.concurrency)
+            .collect()
+            .await;
+
+        // Restore dataset order, independent of completion order
+        let order: HashMap<(&str, &str), usize> = self
+            .dataset
+            .cases
+            .iter()
+            .flat_map(|c| {
+                self.evaluator
+                    .llms
+                    .iter()
+                    .map(move |(pid, _)| (c.id.as_str(), pid.as_str()))
+            })
+            .enumerate()
+            .map(|(i, key)| (key, i))
+            .collect();
+        results.sort_by_key(|r| {
+            order
+                .get(&(r.case_id.as_str(), r.provider_id.as_str()))
+                .copied()
+                .unwrap_or(usize::MAX)
+        });
+
+        Ok(EvalReport::from_results(
+            results,
+            &self.evaluator.provider_ids(),
+            self.pass_threshold,
+            &self.pricing,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(content: &str) -> Result<EvalDataset, LLMError> {
+        EvalDataset::from_reader(content.as_bytes())
+    }
+
+    #[test]
+    fn loads_cases_and_defaults_ids_to_line_numbers() {
+        let dataset = load(concat!(
+            r#"{"id": "greeting", "messages": [{"role": "user", "content": "Say hi"}], "tags": ["smoke"]}"#,
+            "\n\n",
+            r#"{"messages": [{"role": "User", "content": "2+2?"}], "reference": "4"}"#,
+        ))
+        .unwrap();
+
+        assert_eq!(dataset.cases.len(), 2);
+        assert_eq!(dataset.cases[0].id, "greeting");
+        assert_eq!(dataset.cases[1].id, "case_3");
+        assert_eq!(dataset.cases[1].reference.as_deref(), Some("4"));
+        assert_eq!(dataset.cases[1].chat_messages().unwrap()[0].content, "2+2?");
+        assert_eq!(dataset.with_tag("smoke").cases.len(), 1);
+    }
+
+    #[test]
+    fn rejects_duplicate_ids_naming_the_lines() {
+        let error = load(concat!(
+            r#"{"id": "a", "messages": []}"#,
+            "\n",
+            r#"{"id": "b", "messages": []}"#,
+            "\n",
+            r#"{"id": "a", "messages": []}"#,
+        ))
+        .unwrap_err();
+        match error {
+            LLMError::InvalidRequest(message) => {
+                assert_eq!(
+                    message,
+                    "Duplicate case id 'a' on line 3, already used on line 1"
+                )
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_explicit_ids_colliding_with_default_ids() {
+        let content = concat!(
+            r#"{"messages": []}"#,
+            "\n",
+            r#"{"id": "case_1", "messages": []}"#,
+        );
+        assert!(matches!(load(content), Err(LLMError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn reports_invalid_lines() {
+        match load("{\"messages\": []}\nnot json") {
+            Err(LLMError::JsonError(message)) => assert!(message.contains("line 2")),
+            other => panic!("unexpected result: {:?}", other.map(|d| d.cases.len())),
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_roles() {
+        let message = CaseMessage {
+            role: "system".into(),
+            content: "Be brief".into(),
+        };
+        assert!(message.to_chat_message().is_err());
+    }
+}
@@ -0,0 +1,220 @@
+//! LLM-as-judge scoring.
+//!
+//! A [`JudgeScorer`] sends the evaluated prompt, a candidate response and a rubric to a
+//! designated judge provider and parses a numeric score and a rationale from its answer.
+
+use std::sync::{Arc, LazyLock};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    chat::{ChatMessage, ChatRole, MessageType, StructuredOutputFormat},
+    error::LLMError,
+    json::extract_json,
+    LLMProvider,
+};
+
+use super::conversation::render_transcript;
+
+/// Default instructions given to the judge
+const DEFAULT_RUBRIC: &str = "Rate how well the response answers the conversation: \
+correctness, completeness, clarity and adherence to the instructions.";
+
+/// Score and rationale returned by a judge provider
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Judgement {
+    /// Score given by the judge, clamped to the judge scale
+    pub score: f32,
+    /// Judge explanation for the score
+    pub rationale: String,
+}
+
+/// Scorer delegating the evaluation of a response to a judge LLM
+#[derive(Clone)]
+pub struct JudgeScorer {
+    judge: Arc<dyn LLMProvider>,
+    rubric: String,
+    min_score: f32,
+    max_score: f32,
+    weight: f32,
+}
+
+impl JudgeScorer {
+    /// Creates a judge scorer using a 1-10 scale and a generic quality rubric
+    ///
+    /// # Arguments
+    /// * `judge` - Provider asked to grade responses. Build it with
+    ///   [`JudgeScorer::response_format`] as schema on backends supporting structured output.
+    pub fn new(judge: Box<dyn LLMProvider>) -> Self {
+        Self {
+            judge: Arc::from(judge),
+            rubric: DEFAULT_RUBRIC.to_string(),
+            min_score: 1.0,
+            max_score: 10.0,
+            weight: 1.0,
+        }
+    }
+
+    /// Sets the rubric the judge grades against
+    pub fn rubric(mut self, rubric: impl Into<String>) -> Self {
+        self.rubric = rubric.into();
+        self
+    }
+
+    /// Sets the score scale, e.g. 0-1 or 1-5
+    pub fn scale(mut self, min_score: f32, max_score: f32) -> Self {
+        self.min_score = min_score.min(max_score);
+        self.max_score = max_score.max(min_score);
+        self
+    }
+
+    /// Sets the factor applied to the judge score when added to the total score
+    pub fn weight(mut self, weight: f32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Returns the weight applied to the judge score
+    pub fn get_weight(&self) -> f32 {
+        self.weight
+    }
+
+    /// Structured output format matching the answer expected from the judge
+    pub fn response_format() -> StructuredOutputFormat {
+        StructuredOutputFormat {
+            name: "Judgement".to_string(),
+            description: Some("Score and rationale for a graded response".to_string()),
+            schema: Some(json!({
+                "type": "object",
+                "properties": {
+                    "score": { "type": "number" },
+                    "rationale": { "type": "string" }
+                },
+                "required": ["score", "rationale"],
+                "additionalProperties": false
+            })),
+            strict: Some(true),
+        }
+    }
+
+    /// Asks the judge to grade `response` as an answer to `messages`
+    pub async fn judge(
+        &self,
+        messages: &[ChatMessage],
+        response: &str,
+    ) -> Result<Judgement, LLMError> {
+        let prompt = self.build_prompt(messages, response);
+        let answer = self
+            .judge
+            .chat(&[ChatMessage {
+                role: ChatRole::User,
+                message_type: MessageType::Text,
+                content: prompt,
+            }])
+            .await?
+            .text()
+            .unwrap_or_default();
+
+        let mut judgement =
+            parse_judgement(&answer).ok_or_else(|| LLMError::ResponseFormatError {
+                message: "Judge answer contains no score".to_string(),
+                raw_response: answer.clone(),
+            })?;
+        judgement.score = judgement.score.clamp(self.min_score, self.max_score);
+        Ok(judgement)
+    }
+
+    /// Builds the grading prompt sent to the judge
+    fn build_prompt(&self, messages: &[ChatMessage], response: &str) -> String {
+        let conversation = render_transcript(messages);
+
+        format!(
+            "You are an impartial judge grading an AI assistant response.\n\n\
+             Rubric:\n{rubric}\n\n\
+             Conversation:\n{conversation}\n\n\
+             Response to grade:\n{response}\n\n\
+             Give a score between {min} and {max} (higher is better). Answer only with a JSON \
+             object of the form {{\"score\": <number>, \"rationale\": \"<short explanation>\"}}.",
+            rubric = self.rubric,
+            conversation = conversation,
+            response = response,
+            min = self.min_score,
+            max = self.max_score,
+        )
+    }
+}
+
+/// Score following the word "score", in any case, e.g. "Score: 7" or "SCORE = 8.5"
+static SCORE_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)score\D*?(-?\d+(?:\.\d+)?)").unwrap());
+
+/// Parses a judge answer, accepting a JSON object (possibly surrounded by prose)
+/// or a plain "Score: N" line.
+fn parse_judgement(answer: &str) -> Option<Judgement> {
+    if let Ok(extracted) = extract_json(answer) {
+        if let Ok(judgement) = serde_json::from_value::<Judgement>(extracted.value) {
+            return Some(judgement);
+        }
+    }
+
+    let score = SCORE_PATTERN.captures(answer)?.get(1)?.as_str();
+    Some(Judgement {
+        score: score.parse().ok()?,
+        rationale: answer.trim().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockLLM;
+
+    #[test]
+    fn parses_json_judgement_surrounded_by_prose() {
+        let answer = "Using {braces} in prose: {\"score\": 8, \"rationale\": \"Good\"} done {}";
+        let judgement = parse_judgement(answer).unwrap();
+        assert_eq!(judgement.score, 8.0);
+        assert_eq!(judgement.rationale, "Good");
+    }
+
+    #[test]
+    fn parses_fenced_json_judgement() {
+        let answer = "```json\n{\"score\": 6.5, \"rationale\": \"Partly right\",}\n```";
+        assert_eq!(parse_judgement(answer).unwrap().score, 6.5);
+    }
+
+    #[test]
+    fn parses_plain_score_line() {
+        let judgement = parse_judgement("Clear answer.\nSCORE = 7.5.").unwrap();
+        assert_eq!(judgement.score, 7.5);
+        assert_eq!(judgement.rationale, "Clear answer.\nSCORE = 7.5.");
+        assert_eq!(parse_judgement("score: -2").unwrap().score, -2.0);
+    }
+
+    #[test]
+    fn parses_score_after_text_changing_length_when_lowercased() {
+        // "İ" takes 2 bytes but 3 once lowercased
+        let judgement = parse_judgement("İİİİ Ünïcödé İ score: 9").unwrap();
+        assert_eq!(judgement.score, 9.0);
+        let judgement = parse_judgement("İİİİİİİİ Score 4 of 10").unwrap();
+        assert_eq!(judgement.score, 4.0);
+    }
+
+    #[test]
+    fn rejects_answers_without_score() {
+        assert_eq!(parse_judgement("Looks fine to me"), None);
+        assert_eq!(parse_judgement("The score is unclear"), None);
+    }
+
+    #[tokio::test]
+    async fn judge_clamps_score_to_scale() {
+        let judge = JudgeScorer::new(Box::new(MockLLM::new([
+            "{\"score\": 42, \"rationale\": \"Perfect\"}",
+        ])));
+        let messages = [ChatMessage::user().content("Hi").build()];
+        let judgement = judge.judge(&messages, "Hello").await.unwrap();
+        assert_eq!(judgement.score, 10.0);
+    }
+}
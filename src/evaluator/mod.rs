@@ -0,0 +1,288 @@
+//! Module for evaluating and comparing responses from multiple LLM providers.
+//!
+//! This module provides functionality to run the same prompt through multiple LLMs
+//! and score their responses using custom evaluation functions or a judge LLM, either
+//! one prompt at a time, over a whole dataset of cases, or over scripted multi-turn
+//! conversations. Providers can be sampled several times to compare score distributions
+//! rather than single anecdotes, or ranked against each other through judged pairwise
+//! comparisons. [`BestOfN`] turns an evaluator into a provider answering with its
+//! best-scoring candidate, and [`SelfConsistency`] answers with the majority vote of
+//! repeated samples. Dataset runs can be saved as a [`Baseline`] and later runs gated
+//! against it, replaying recorded responses offline with [`ReplayProvider`].
+
+mod baseline;
+mod best_of_n;
+mod conversation;
+mod dataset;
+mod judge;
+mod replay;
+mod report;
+mod sampling;
+mod scorers;
+mod stats;
+mod tournament;
+mod voting;
+
+use serde::Serialize;
+
+use crate::{chain::LLMRegistry, chat::ChatMessage, error::LLMError, LLMProvider};
+
+pub use baseline::{
+    Baseline, BaselineCase, CaseComparison, ProviderComparison, RegressionPolicy, RegressionReport,
+};
+pub use best_of_n::{BestOfN, CandidateScore, ScoredResponse};
+pub use conversation::{ConversationResult, ConversationScript, END_OF_CONVERSATION};
+pub use dataset::{CaseMessage, DatasetEvaluation, EvalCase, EvalDataset};
+pub use judge::{JudgeScorer, Judgement};
+pub use llm::evaluator::{ParallelEvalResult, ParallelEvaluator};
+pub use replay::ReplayProvider;
+pub use report::{CaseResult, EvalReport, LatencyStats, Pricing, ProviderSummary};
+pub use sampling::SampledEvalResult;
+pub use scorers::{
+    bleu, code_blocks, detect_language, normalize_text, rouge_l, Bleu, CodeBlockLanguage,
+    Configured, EmbeddingSimilarity, ExactMatch, FnScorer, JsonParses, JsonSchemaConformance,
+    LengthBounds, LengthUnit, NormalizedMatch, RegexPresence, RougeL, ScoreInput, Scorer,
+    ScorerExt,
+};
+pub use stats::{
+    compare_samples, ScoreStats, SignificanceTest, StatsConfig, JUDGE_SCORE, TOTAL_SCORE,
+};
+pub use tournament::{
+    PairwiseJudge, PairwiseMatch, PairwiseVerdict, Preference, Rating, TournamentReport,
+};
+pub use voting::{AnswerExtractor, SelfConsistency, VoteResult, VoteSample};
+
+/// Type alias for scoring functions that evaluate LLM responses
+pub type ScoringFn = dyn Fn(&str) -> f32 + Send + Sync + 'static;
+
+/// Evaluator for comparing responses from multiple LLM providers
+pub struct LLMEvaluator {
+    /// Collection of LLM providers to evaluate, with their identifiers
+    llms: Vec<(String, Box<dyn LLMProvider>)>,
+    /// Scorers evaluating responses
+    scorings_fns: Vec<Box<dyn Scorer>>,
+    /// Optional judge LLM grading responses
+    judge: Option<JudgeScorer>,
+    /// Number of samples per provider in sampled evaluations
+    samples: usize,
+    /// Maximum number of samples of a provider requested at once
+    sample_concurrency: usize,
+    /// Settings of sampled evaluation statistics
+    stats: StatsConfig,
+}
+
+impl LLMEvaluator {
+    /// Creates a new evaluator with the given LLM providers
+    ///
+    /// Providers are identified as "llm_0", "llm_1", ... in reports.
+    ///
+    /// # Arguments
+    /// * `llms` - Vector of LLM providers to evaluate
+    pub fn new(llms: Vec<Box<dyn LLMProvider>>) -> Self {
+        Self::named(
+            llms.into_iter()
+                .enumerate()
+                .map(|(i, llm)| (format!("llm_{}", i), llm))
+                .collect(),
+        )
+    }
+
+    /// Creates a new evaluator with identified LLM providers
+    ///
+    /// # Arguments
+    /// * `llms` - Vector of (id, provider) tuples to evaluate
+    pub fn named(llms: Vec<(String, Box<dyn LLMProvider>)>) -> Self {
+        Self {
+            llms,
+            scorings_fns: Vec::new(),
+            judge: None,
+            samples: 1,
+            sample_concurrency: 4,
+            stats: StatsConfig::default(),
+        }
+    }
+
+    /// Creates a new evaluator over every provider of a registry, ordered by id
+    pub fn from_registry(registry: LLMRegistry) -> Self {
+        let mut llms: Vec<_> = registry.backends.into_iter().collect();
+        llms.sort_by(|a, b| a.0.cmp(&b.0));
+        Self::named(llms)
+    }
+
+    /// Adds a scoring function to evaluate LLM responses
+    ///
+    /// # Arguments
+    /// * `f` - Function that takes a response string and returns a score
+    pub fn scoring<F>(self, f: F) -> Self
+    where
+        F: Fn(&str) -> f32 + Send + Sync + 'static,
+    {
+        let name = format!("scoring_{}", self.scorings_fns.len());
+        self.named_scoring(name, f)
+    }
+
+    /// Adds a scoring function reported under `name` in score breakdowns
+    ///
+    /// "total" and "judge" are reserved for the total and judge scores of sampled
+    /// evaluations, which reject scorers with these names.
+    pub fn named_scoring<F>(self, name: impl Into<String>, f: F) -> Self
+    where
+        F: Fn(&str) -> f32 + Send + Sync + 'static,
+    {
+        self.scorer(FnScorer::new(name, f))
+    }
+
+    /// Adds a scorer, e.g. one of the built-in scorers of this module
+    ///
+    /// ```no_run
+    /// use rllm::evaluator::{LLMEvaluator, NormalizedMatch, RougeL, ScorerExt};
+    ///
+    /// let evaluator = LLMEvaluator::new(vec![])
+    ///     .scorer(NormalizedMatch.with_weight(2.0))
+    ///     .scorer(RougeL);
+    /// ```
+    pub fn scorer<S: Scorer + 'static>(mut self, scorer: S) -> Self {
+        self.scorings_fns.push(Box::new(scorer));
+        self
+    }
+
+    /// Sets a judge LLM grading each response against a rubric
+    ///
+    /// The weighted judge score is added to the scoring functions total, and the judge
+    /// rationale is reported in [`EvalResult::judgement`].
+    pub fn judge(mut self, judge: JudgeScorer) -> Self {
+        self.judge = Some(judge);
+        self
+    }
+
+    /// Sets how many times each provider is sampled by
+    /// [`LLMEvaluator::evaluate_chat_sampled`] (defaults to 1)
+    pub fn samples(mut self, n: usize) -> Self {
+        self.samples = n.max(1);
+        self
+    }
+
+    /// Sets how many samples of a provider are requested at once by
+    /// [`LLMEvaluator::evaluate_chat_sampled`] (defaults to 4)
+    pub fn sample_concurrency(mut self, concurrency: usize) -> Self {
+        self.sample_concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Sets the confidence level, resample count and seed of sampled statistics
+    pub fn stats_config(mut self, config: StatsConfig) -> Self {
+        self.stats = config;
+        self
+    }
+
+    /// Identifiers of the evaluated providers, in evaluation order
+    pub fn provider_ids(&self) -> Vec<&str> {
+        self.llms.iter().map(|(id, _)| id.as_str()).collect()
+    }
+
+    /// Evaluates chat responses from all providers for the given messages
+    ///
+    /// # Arguments
+    /// * `messages` - Chat messages to send to each provider
+    ///
+    /// # Returns
+    /// Vector of evaluation results containing responses and scores
+    pub async fn evaluate_chat(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<Vec<EvalResult>, LLMError> {
+        let mut results = Vec::new();
+        for (_, llm) in &self.llms {
+            let response = llm.chat(messages).await?;
+            let text = response.text().unwrap_or_default();
+            results.push(self.score(messages, text, None).await?);
+        }
+        Ok(results)
+    }
+
+    /// Evaluates chat responses from all providers against a reference answer
+    ///
+    /// Reference-based scorers such as [`ExactMatch`] or [`RougeL`] compare each
+    /// response with `reference`.
+    pub async fn evaluate_chat_with_reference(
+        &self,
+        messages: &[ChatMessage],
+        reference: &str,
+    ) -> Result<Vec<EvalResult>, LLMError> {
+        let mut results = Vec::new();
+        for (_, llm) in &self.llms {
+            let response = llm.chat(messages).await?;
+            let text = response.text().unwrap_or_default();
+            results.push(self.score(messages, text, Some(reference)).await?);
+        }
+        Ok(results)
+    }
+
+    /// Prepares the evaluation of every provider over a dataset of cases
+    pub fn dataset<'a>(&'a self, dataset: &'a EvalDataset) -> DatasetEvaluation<'a> {
+        DatasetEvaluation::new(self, dataset)
+    }
+
+    /// Scores a response to `messages` with the scorers and the judge
+    pub async fn score(
+        &self,
+        messages: &[ChatMessage],
+        text: String,
+        reference: Option<&str>,
+    ) -> Result<EvalResult, LLMError> {
+        let input = ScoreInput {
+            messages,
+            response: &text,
+            reference,
+        };
+        let mut breakdown = Vec::with_capacity(self.scorings_fns.len());
+        for scorer in &self.scorings_fns {
+            breakdown.push(ScoreDetail {
+                name: scorer.name().to_string(),
+                score: scorer.score(&input).await?,
+                weight: scorer.weight(),
+            });
+        }
+        let mut score = breakdown.iter().map(|d| d.weight * d.score).sum();
+
+        let judgement = match &self.judge {
+            Some(judge) => {
+                let judgement = judge.judge(messages, &text).await?;
+                score += judge.get_weight() * judgement.score;
+                Some(judgement)
+            }
+            None => None,
+        };
+
+        Ok(EvalResult {
+            text,
+            score,
+            breakdown,
+            judgement,
+        })
+    }
+}
+
+/// Score given by a single scorer
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScoreDetail {
+    /// Name of the scorer
+    pub name: String,
+    /// Unweighted score it assigned
+    pub score: f32,
+    /// Weight applied to the score in the total
+    pub weight: f32,
+}
+
+/// Result of evaluating an LLM response
+#[derive(Debug, Clone)]
+pub struct EvalResult {
+    /// The text response from the LLM
+    pub text: String,
+    /// Weighted sum of the scorer scores, plus the weighted judge score
+    pub score: f32,
+    /// Score of each scorer, in registration order
+    pub breakdown: Vec<ScoreDetail>,
+    /// Judge score and rationale, when a judge is configured
+    pub judgement: Option<Judgement>,
+}
@@ -0,0 +1,350 @@
+//! Offline provider replaying recorded responses.
+//!
+//! [`ReplayProvider`] answers each prompt with a response recorded earlier, for instance
+//! from a previous dataset evaluation, so evaluations can run deterministically in CI
+//! without network access or API keys.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    path::Path,
+};
+
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    chat::{ChatMessage, ChatProvider, ChatResponse, ChatRole, MessageType, Tool},
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    stt::SpeechToTextProvider,
+    LLMProvider, ToolCall,
+};
+
+use super::{
+    dataset::EvalDataset,
+    report::{read_file, write_file, EvalReport},
+};
+
+/// Response returned by [`ReplayProvider`]
+#[derive(Debug, Clone)]
+struct ReplayResponse(String);
+
+impl fmt::Display for ReplayResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl ChatResponse for ReplayResponse {
+    fn text(&self) -> Option<String> {
+        Some(self.0.clone())
+    }
+
+    fn tool_calls(&self) -> Option<Vec<ToolCall>> {
+        None
+    }
+}
+
+/// Request a recorded response is looked up by, serialized as the recording key
+#[derive(Serialize)]
+struct ReplayKey<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<&'a str>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    parameters: &'a BTreeMap<String, Value>,
+    messages: Vec<ReplayMessage<'a>>,
+}
+
+#[derive(Serialize)]
+struct ReplayMessage<'a> {
+    role: &'static str,
+    content: &'a str,
+    /// Tool calls or results, and a placeholder for attachments
+    #[serde(skip_serializing_if = "Option::is_none")]
+    payload: Option<Value>,
+}
+
+/// Provider answering prompts with recorded responses
+///
+/// Chat requests are matched on their whole conversation, together with the system
+/// prompt and parameters set on the provider; completion requests are matched as a
+/// conversation made of their prompt. Unknown requests fail unless a default response
+/// is set.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayProvider {
+    responses: HashMap<String, String>,
+    default: Option<String>,
+    system: Option<String>,
+    parameters: BTreeMap<String, Value>,
+}
+
+impl ReplayProvider {
+    /// Creates a provider without recorded responses
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the system prompt the recorded requests were sent with
+    ///
+    /// It is part of the key of every request, so set it before recording responses.
+    pub fn system(mut self, system: impl Into<String>) -> Self {
+        self.system = Some(system.into());
+        self
+    }
+
+    /// Sets a request parameter the recorded requests were sent with, e.g. "temperature"
+    ///
+    /// Parameters are part of the key of every request, so set them before recording
+    /// responses.
+    pub fn parameter(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.parameters.insert(name.into(), value.into());
+        self
+    }
+
+    /// Records the response to a single user message
+    pub fn response(self, prompt: impl Into<String>, response: impl Into<String>) -> Self {
+        let prompt = ChatMessage::user().content(prompt).build();
+        self.conversation(&[prompt], response)
+    }
+
+    /// Records the response to a whole conversation
+    pub fn conversation(mut self, messages: &[ChatMessage], response: impl Into<String>) -> Self {
+        let key = self.key(messages);
+        self.responses.insert(key, response.into());
+        self
+    }
+
+    /// Sets the response to prompts without a recorded response
+    pub fn default_response(mut self, response: impl Into<String>) -> Self {
+        self.default = Some(response.into());
+        self
+    }
+
+    /// Records the responses a provider gave in a dataset evaluation
+    ///
+    /// Failed cases are skipped, so replaying them fails as well. The responses are keyed
+    /// without system prompt nor parameters; use [`ReplayProvider::record_report`] on a
+    /// configured provider otherwise.
+    pub fn from_report(
+        dataset: &EvalDataset,
+        report: &EvalReport,
+        provider_id: &str,
+    ) -> Result<Self, LLMError> {
+        Self::new().record_report(dataset, report, provider_id)
+    }
+
+    /// Same as [`ReplayProvider::from_report`], keyed with the system prompt and
+    /// parameters of this provider
+    pub fn record_report(
+        self,
+        dataset: &EvalDataset,
+        report: &EvalReport,
+        provider_id: &str,
+    ) -> Result<Self, LLMError> {
+        let mut replay = self;
+        for result in report
+            .results
+            .iter()
+            .filter(|r| r.provider_id == provider_id && r.error.is_none())
+        {
+            let Some(case) = dataset.cases.iter().find(|c| c.id == result.case_id) else {
+                continue;
+            };
+            replay = replay.conversation(&case.chat_messages()?, result.text.clone());
+        }
+        Ok(replay)
+    }
+
+    /// Loads recorded responses saved with [`ReplayProvider::save`]
+    ///
+    /// The system prompt and parameters are part of the saved keys; set them again on the
+    /// loaded provider so its requests match.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LLMError> {
+        Ok(Self {
+            responses: serde_json::from_str(&read_file(path.as_ref())?)?,
+            ..Self::default()
+        })
+    }
+
+    /// Saves the recorded responses as a JSON object mapping request keys to responses
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), LLMError> {
+        write_file(
+            path.as_ref(),
+            &serde_json::to_string_pretty(&self.responses)?,
+        )
+    }
+
+    /// Key of a request: the conversation with the system prompt and parameters, as JSON
+    fn key(&self, messages: &[ChatMessage]) -> String {
+        let messages = messages
+            .iter()
+            .map(|m| ReplayMessage {
+                role: match m.role {
+                    ChatRole::User => "user",
+                    ChatRole::Assistant => "assistant",
+                },
+                content: &m.content,
+                payload: match &m.message_type {
+                    MessageType::Text => None,
+                    MessageType::ToolUse(calls) | MessageType::ToolResult(calls) => {
+                        serde_json::to_value(calls).ok()
+                    }
+                    MessageType::Image(_) | MessageType::Pdf(_) => Some("attachment".into()),
+                    MessageType::ImageURL(url) => Some(url.as_str().into()),
+                },
+            })
+            .collect();
+        serde_json::to_string(&ReplayKey {
+            system: self.system.as_deref(),
+            parameters: &self.parameters,
+            messages,
+        })
+        .unwrap_or_default()
+    }
+
+    fn lookup(&self, messages: &[ChatMessage]) -> Result<String, LLMError> {
+        self.responses
+            .get(&self.key(messages))
+            .or(self.default.as_ref())
+            .cloned()
+            .ok_or_else(|| {
+                let last = messages.last().map(|m| m.content.as_str()).unwrap_or("");
+                LLMError::InvalidRequest(format!(
+                    "No recorded response for conversation ending with: {}",
+                    last
+                ))
+            })
+    }
+}
+
+impl LLMProvider for ReplayProvider {}
+
+#[async_trait]
+impl ChatProvider for ReplayProvider {
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        _tools: Option<&[Tool]>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        Ok(Box::new(ReplayResponse(self.lookup(messages)?)))
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for ReplayProvider {
+    async fn complete(&self, req: &CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        Ok(CompletionResponse {
+            text: self.lookup(&[ChatMessage::user().content(&req.prompt).build()])?,
+        })
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for ReplayProvider {
+    async fn embed(&self, _input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        Err(LLMError::ProviderError(
+            "Embeddings are not supported by ReplayProvider".into(),
+        ))
+    }
+}
+
+#[async_trait]
+impl SpeechToTextProvider for ReplayProvider {
+    async fn transcribe(&self, _audio: Vec<u8>) -> Result<String, LLMError> {
+        Err(LLMError::ProviderError(
+            "Speech to text is not supported by ReplayProvider".into(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluator::{ExactMatch, LLMEvaluator};
+
+    fn conversation(last: &str) -> Vec<ChatMessage> {
+        vec![
+            ChatMessage::user().content("Hi").build(),
+            ChatMessage::assistant().content("Hello").build(),
+            ChatMessage::user().content(last).build(),
+        ]
+    }
+
+    #[tokio::test]
+    async fn matches_the_whole_conversation() {
+        let replay = ReplayProvider::new().conversation(&conversation("Bye"), "Goodbye");
+
+        let answer = replay.chat(&conversation("Bye")).await.unwrap();
+        assert_eq!(answer.text().as_deref(), Some("Goodbye"));
+
+        let other = [ChatMessage::user().content("Bye").build()];
+        assert!(replay.chat(&other).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn system_prompt_and_parameters_are_part_of_the_key() {
+        let replay = ReplayProvider::new()
+            .system("Be terse")
+            .parameter("temperature", 0.2)
+            .response("Hi", "Yo");
+        let messages = [ChatMessage::user().content("Hi").build()];
+
+        assert!(replay.chat(&messages).await.is_ok());
+        assert!(replay
+            .clone()
+            .system("Be verbose")
+            .chat(&messages)
+            .await
+            .is_err());
+        assert!(replay
+            .clone()
+            .parameter("temperature", 0.7)
+            .chat(&messages)
+            .await
+            .is_err());
+
+        let fallback = replay.system("Be verbose").default_response("Hello");
+        let answer = fallback.chat(&messages).await.unwrap();
+        assert_eq!(answer.text().as_deref(), Some("Hello"));
+    }
+
+    #[tokio::test]
+    async fn completions_match_single_message_responses() {
+        let replay = ReplayProvider::new().response("Once upon", "a time");
+        let completion = replay
+            .complete(&CompletionRequest::new("Once upon"))
+            .await
+            .unwrap();
+        assert_eq!(completion.text, "a time");
+    }
+
+    #[tokio::test]
+    async fn replays_a_report_and_survives_a_save() {
+        let dataset = EvalDataset::from_reader(
+            r#"{"id": "a", "messages": [{"role": "user", "content": "Hi"}, {"role": "assistant", "content": "Hello"}, {"role": "user", "content": "Bye"}]}"#
+                .as_bytes(),
+        )
+        .unwrap();
+        let recorded = ReplayProvider::new().conversation(&conversation("Bye"), "Goodbye");
+        let report = LLMEvaluator::named(vec![("live".to_string(), Box::new(recorded))])
+            .scorer(ExactMatch)
+            .dataset(&dataset)
+            .run()
+            .await
+            .unwrap();
+
+        let path = std::env::temp_dir().join(format!("rllm_replay_{}.json", std::process::id()));
+        ReplayProvider::from_report(&dataset, &report, "live")
+            .unwrap()
+            .save(&path)
+            .unwrap();
+        let loaded = ReplayProvider::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let answer = loaded.chat(&conversation("Bye")).await.unwrap();
+        assert_eq!(answer.text().as_deref(), Some("Goodbye"));
+    }
+}
@@ -0,0 +1,461 @@
+//! Aggregated reports of dataset evaluations, exportable as JSON or JUnit XML.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{chat::ChatMessage, error::LLMError};
+
+use super::{dataset::EvalCase, EvalResult, Judgement, ScoreDetail};
+
+/// Token pricing of a provider, in currency units per 1000 tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Pricing {
+    /// Price of 1000 prompt tokens
+    pub input_per_1k: f64,
+    /// Price of 1000 generated tokens
+    pub output_per_1k: f64,
+}
+
+impl Pricing {
+    /// Creates a pricing from per-1000-token prices
+    pub fn per_1k(input_per_1k: f64, output_per_1k: f64) -> Self {
+        Self {
+            input_per_1k,
+            output_per_1k,
+        }
+    }
+
+    /// Cost of a request with the given token counts
+    pub fn cost(&self, input_tokens: u64, output_tokens: u64) -> f64 {
+        (input_tokens as f64 * self.input_per_1k + output_tokens as f64 * self.output_per_1k)
+            / 1000.0
+    }
+}
+
+/// Rough token count (about 4 characters per token), used when providers don't report usage
+pub(crate) fn estimate_tokens(text: &str) -> u64 {
+    (text.chars().count() as u64).div_ceil(4)
+}
+
+/// Outcome of one case on one provider
+#[derive(Debug, Clone, Serialize)]
+pub struct CaseResult {
+    /// Dataset case identifier
+    pub case_id: String,
+    /// Provider identifier
+    pub provider_id: String,
+    /// Tags of the case
+    pub tags: Vec<String>,
+    /// Response text, empty when the request failed
+    pub text: String,
+    /// Total score, `None` when the request or scoring failed
+    pub score: Option<f32>,
+    /// Score of each scoring function
+    pub breakdown: Vec<ScoreDetail>,
+    /// Judge score and rationale, when a judge is configured
+    pub judgement: Option<Judgement>,
+    /// Request latency in milliseconds
+    pub latency_ms: u64,
+    /// Estimated prompt tokens
+    pub input_tokens: u64,
+    /// Estimated response tokens
+    pub output_tokens: u64,
+    /// Error message when the request or scoring failed
+    pub error: Option<String>,
+    /// Whether the case passed (no error and score above the pass threshold, if any)
+    pub passed: bool,
+}
+
+impl CaseResult {
+    pub(super) fn new(case: &EvalCase, provider_id: &str, latency_ms: u128) -> Self {
+        Self {
+            case_id: case.id.clone(),
+            provider_id: provider_id.to_string(),
+            tags: case.tags.clone(),
+            text: String::new(),
+            score: None,
+            breakdown: Vec::new(),
+            judgement: None,
+            latency_ms: latency_ms as u64,
+            input_tokens: 0,
+            output_tokens: 0,
+            error: None,
+            passed: false,
+        }
+    }
+
+    pub(super) fn record(&mut self, messages: &[ChatMessage], eval: EvalResult) {
+        self.input_tokens = messages.iter().map(|m| estimate_tokens(&m.content)).sum();
+        self.output_tokens = estimate_tokens(&eval.text);
+        self.text = eval.text;
+        self.score = Some(eval.score);
+        self.breakdown = eval.breakdown;
+        self.judgement = eval.judgement;
+    }
+}
+
+/// Latency percentiles in milliseconds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct LatencyStats {
+    pub mean: f64,
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    pub max: u64,
+}
+
+impl LatencyStats {
+    fn from_samples(mut samples: Vec<u64>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_unstable();
+        // Nearest-rank percentile
+        let pct = |p: f64| samples[((p * samples.len() as f64).ceil() as usize).max(1) - 1];
+        Self {
+            mean: samples.iter().sum::<u64>() as f64 / samples.len() as f64,
+            p50: pct(0.50),
+            p90: pct(0.90),
+            p99: pct(0.99),
+            max: samples[samples.len() - 1],
+        }
+    }
+}
+
+/// Aggregated results of one provider
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderSummary {
+    /// Provider identifier
+    pub provider_id: String,
+    /// Number of cases run
+    pub cases: usize,
+    /// Cases where the request or scoring failed
+    pub errors: usize,
+    /// Cases that did not pass (errors included)
+    pub failures: usize,
+    /// Mean total score over the successful cases
+    pub mean_score: Option<f32>,
+    /// Mean score of each scoring function over the successful cases
+    pub scorers: BTreeMap<String, f32>,
+    /// Mean judge score over the judged cases
+    pub judge_mean: Option<f32>,
+    /// Latency over all cases
+    pub latency: LatencyStats,
+    /// Estimated prompt tokens
+    pub input_tokens: u64,
+    /// Estimated response tokens
+    pub output_tokens: u64,
+    /// Estimated cost, when a pricing was set for the provider
+    pub estimated_cost: Option<f64>,
+}
+
+/// Report of a dataset evaluation
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalReport {
+    /// Per-provider aggregates, in evaluator order
+    pub providers: Vec<ProviderSummary>,
+    /// Every case result, in dataset order
+    pub results: Vec<CaseResult>,
+}
+
+fn mean(values: &[f32]) -> Option<f32> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum::<f32>() / values.len() as f32)
+    }
+}
+
+impl EvalReport {
+    pub(super) fn from_results(
+        mut results: Vec<CaseResult>,
+        provider_ids: &[&str],
+        pass_threshold: Option<f32>,
+        pricing: &HashMap<String, Pricing>,
+    ) -> Self {
+        for r in &mut results {
+            r.passed = match (r.score, pass_threshold) {
+                (None, _) => false,
+                (Some(score), Some(threshold)) => score >= threshold,
+                (Some(_), None) => true,
+            };
+        }
+
+        let providers = provider_ids
+            .iter()
+            .map(|pid| {
+                let runs: Vec<&CaseResult> =
+                    results.iter().filter(|r| r.provider_id == *pid).collect();
+                let scores: Vec<f32> = runs.iter().filter_map(|r| r.score).collect();
+
+                let mut per_scorer: BTreeMap<String, Vec<f32>> = BTreeMap::new();
+                for detail in runs.iter().flat_map(|r| &r.breakdown) {
+                    per_scorer
+                        .entry(detail.name.clone())
+                        .or_default()
+                        .push(detail.score);
+                }
+                let judge_scores: Vec<f32> = runs
+                    .iter()
+                    .filter_map(|r| r.judgement.as_ref().map(|j| j.score))
+                    .collect();
+
+                let input_tokens = runs.iter().map(|r| r.input_tokens).sum();
+                let output_tokens = runs.iter().map(|r| r.output_tokens).sum();
+
+                ProviderSummary {
+                    provider_id: pid.to_string(),
+                    cases: runs.len(),
+                    errors: runs.iter().filter(|r| r.error.is_some()).count(),
+                    failures: runs.iter().filter(|r| !r.passed).count(),
+                    mean_score: mean(&scores),
+                    scorers: per_scorer
+                        .into_iter()
+                        .filter_map(|(name, s)| mean(&s).map(|m| (name, m)))
+                        .collect(),
+                    judge_mean: mean(&judge_scores),
+                    latency: LatencyStats::from_samples(
+                        runs.iter().map(|r| r.latency_ms).collect(),
+                    ),
+                    input_tokens,
+                    output_tokens,
+                    estimated_cost: pricing
+                        .get(*pid)
+                        .map(|p| p.cost(input_tokens, output_tokens)),
+                }
+            })
+            .collect();
+
+        Self { providers, results }
+    }
+
+    /// Returns the summary of a provider
+    pub fn provider(&self, provider_id: &str) -> Option<&ProviderSummary> {
+        self.providers.iter().find(|p| p.provider_id == provider_id)
+    }
+
+    /// Serializes the report as pretty-printed JSON
+    pub fn to_json(&self) -> Result<String, LLMError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Writes the report as JSON to `path`
+    pub fn write_json(&self, path: impl AsRef<Path>) -> Result<(), LLMError> {
+        write_file(path.as_ref(), &self.to_json()?)
+    }
+
+    /// Renders the report as JUnit XML, one test suite per provider
+    pub fn to_junit_xml(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let total_errors: usize = self.providers.iter().map(|p| p.errors).sum();
+        let total_failures: usize = self.providers.iter().map(|p| p.failures - p.errors).sum();
+        xml.push_str(&format!(
+            "<testsuites name=\"rllm-eval\" tests=\"{}\" failures=\"{}\" errors=\"{}\">\n",
+            self.results.len(),
+            total_failures,
+            total_errors
+        ));
+
+        for provider in &self.providers {
+            let runs: Vec<&CaseResult> = self
+                .results
+                .iter()
+                .filter(|r| r.provider_id == provider.provider_id)
+                .collect();
+            let time: u64 = runs.iter().map(|r| r.latency_ms).sum();
+            xml.push_str(&format!(
+                "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">\n",
+                xml_escape(&provider.provider_id),
+                provider.cases,
+                provider.failures - provider.errors,
+                provider.errors,
+                time as f64 / 1000.0
+            ));
+
+            for r in runs {
+                xml.push_str(&format!(
+                    "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\">\n",
+                    xml_escape(&r.provider_id),
+                    xml_escape(&r.case_id),
+                    r.latency_ms as f64 / 1000.0
+                ));
+                match (&r.error, r.score) {
+                    (Some(error), _) => xml.push_str(&format!(
+                        "      <error message=\"{}\"/>\n",
+                        xml_escape(error)
+                    )),
+                    (None, Some(score)) if !r.passed => xml.push_str(&format!(
+                        "      <failure message=\"score {:.3} below pass threshold\"/>\n",
+                        score
+                    )),
+                    _ => {}
+                }
+                if !r.text.is_empty() {
+                    xml.push_str(&format!(
+                        "      <system-out>{}</system-out>\n",
+                        xml_escape(&r.text)
+                    ));
+                }
+                xml.push_str("    </testcase>\n");
+            }
+            xml.push_str("  </testsuite>\n");
+        }
+
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+
+    /// Writes the report as JUnit XML to `path`
+    pub fn write_junit(&self, path: impl AsRef<Path>) -> Result<(), LLMError> {
+        write_file(path.as_ref(), &self.to_junit_xml())
+    }
+}
+
+pub(super) fn write_file(path: &Path, content: &str) -> Result<(), LLMError> {
+    std::fs::write(path, content)
+        .map_err(|e| LLMError::InvalidRequest(format!("Cannot write {}: {}", path.display(), e)))
+}
+
+pub(super) fn read_file(path: &Path) -> Result<String, LLMError> {
+    std::fs::read_to_string(path)
+        .map_err(|e| LLMError::InvalidRequest(format!("Cannot read {}: {}", path.display(), e)))
+}
+
+fn xml_escape(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // Control characters are not allowed in XML 1.0
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(provider_id: &str, case_id: &str, latency_ms: u128) -> CaseResult {
+        let case = EvalCase {
+            id: case_id.to_string(),
+            messages: Vec::new(),
+            reference: None,
+            tags: vec!["math".to_string()],
+        };
+        CaseResult::new(&case, provider_id, latency_ms)
+    }
+
+    fn scored(provider_id: &str, case_id: &str, latency_ms: u128, score: f32) -> CaseResult {
+        let mut result = result(provider_id, case_id, latency_ms);
+        let prompt = ChatMessage::user().content("What is 2 + 2?").build();
+        result.record(
+            &[prompt],
+            EvalResult {
+                text: format!("<{}> & done", score),
+                score,
+                breakdown: vec![ScoreDetail {
+                    name: "exact".to_string(),
+                    score,
+                    weight: 1.0,
+                }],
+                judgement: None,
+            },
+        );
+        result
+    }
+
+    fn report() -> EvalReport {
+        let mut failed = result("b", "c1", 50);
+        failed.error = Some("timeout \"slow\"".to_string());
+        EvalReport::from_results(
+            vec![
+                scored("a", "c1", 100, 0.9),
+                scored("a", "c2", 300, 0.3),
+                failed,
+            ],
+            &["a", "b"],
+            Some(0.5),
+            &HashMap::from([("a".to_string(), Pricing::per_1k(1.0, 2.0))]),
+        )
+    }
+
+    #[test]
+    fn latency_uses_nearest_rank_percentiles() {
+        let stats = LatencyStats::from_samples((1..=10).rev().collect());
+        assert_eq!(
+            stats,
+            LatencyStats {
+                mean: 5.5,
+                p50: 5,
+                p90: 9,
+                p99: 10,
+                max: 10,
+            }
+        );
+        assert_eq!(LatencyStats::from_samples(vec![7]).p50, 7);
+        assert_eq!(
+            LatencyStats::from_samples(Vec::new()),
+            LatencyStats::default()
+        );
+    }
+
+    #[test]
+    fn summaries_aggregate_each_provider() {
+        let report = report();
+        let a = report.provider("a").unwrap();
+        assert_eq!((a.cases, a.errors, a.failures), (2, 0, 1));
+        assert!((a.mean_score.unwrap() - 0.6).abs() < 1e-6);
+        assert!((a.scorers["exact"] - 0.6).abs() < 1e-6);
+        assert_eq!(a.latency.max, 300);
+        // "What is 2 + 2?" is 4 tokens, "<0.9> & done" and "<0.3> & done" 3 each
+        assert_eq!((a.input_tokens, a.output_tokens), (8, 6));
+        assert_eq!(a.estimated_cost, Some((8.0 + 2.0 * 6.0) / 1000.0));
+
+        let b = report.provider("b").unwrap();
+        assert_eq!((b.cases, b.errors, b.failures), (1, 1, 1));
+        assert_eq!(b.mean_score, None);
+        assert_eq!(b.estimated_cost, None);
+        assert!(report.provider("c").is_none());
+    }
+
+    #[test]
+    fn junit_reports_failures_and_errors() {
+        assert_eq!(
+            report().to_junit_xml(),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="rllm-eval" tests="3" failures="1" errors="1">
+  <testsuite name="a" tests="2" failures="1" errors="0" time="0.400">
+    <testcase classname="a" name="c1" time="0.100">
+      <system-out>&lt;0.9&gt; &amp; done</system-out>
+    </testcase>
+    <testcase classname="a" name="c2" time="0.300">
+      <failure message="score 0.300 below pass threshold"/>
+      <system-out>&lt;0.3&gt; &amp; done</system-out>
+    </testcase>
+  </testsuite>
+  <testsuite name="b" tests="1" failures="0" errors="1" time="0.050">
+    <testcase classname="b" name="c1" time="0.050">
+      <error message="timeout &quot;slow&quot;"/>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+
+    #[test]
+    fn xml_escape_drops_control_characters() {
+        assert_eq!(xml_escape("a'b\u{1}\tc"), "a&apos;b\tc");
+    }
+}
@@ -0,0 +1,187 @@
+//! Repeated sampling of providers, summarized with per-scorer statistics.
+
+use std::collections::BTreeMap;
+
+use futures::stream::{self, StreamExt};
+
+use crate::{chat::ChatMessage, error::LLMError};
+
+use super::{
+    stats::{compare_samples, ScoreStats, SignificanceTest, JUDGE_SCORE, TOTAL_SCORE},
+    EvalResult, LLMEvaluator,
+};
+
+/// Scores of one provider sampled several times on the same messages
+#[derive(Debug, Clone)]
+pub struct SampledEvalResult {
+    /// Provider identifier
+    pub provider_id: String,
+    /// Successful samples
+    pub samples: Vec<EvalResult>,
+    /// Error messages of the failed samples
+    pub errors: Vec<String>,
+    /// Statistics per scorer name, plus "total" and "judge" when available
+    pub stats: BTreeMap<String, ScoreStats>,
+}
+
+impl SampledEvalResult {
+    /// Returns the sampled scores of a scorer ("total", "judge" or a scoring function name)
+    pub fn scores(&self, scorer: &str) -> Vec<f32> {
+        self.samples
+            .iter()
+            .filter_map(|s| match scorer {
+                TOTAL_SCORE => Some(s.score),
+                JUDGE_SCORE => s.judgement.as_ref().map(|j| j.score),
+                name => s.breakdown.iter().find(|d| d.name == name).map(|d| d.score),
+            })
+            .collect()
+    }
+}
+
+impl LLMEvaluator {
+    /// Samples every provider [`LLMEvaluator::samples`] times on the same messages
+    ///
+    /// Up to [`LLMEvaluator::sample_concurrency`] samples of a provider run at once. Failed
+    /// samples are reported in [`SampledEvalResult::errors`] instead of aborting the
+    /// evaluation. Scorers named "total" or "judge" are rejected, as these keys hold the
+    /// total and judge statistics.
+    pub async fn evaluate_chat_sampled(
+        &self,
+        messages: &[ChatMessage],
+    ) -> Result<Vec<SampledEvalResult>, LLMError> {
+        if let Some(scorer) = self
+            .scorings_fns
+            .iter()
+            .find(|sc| matches!(sc.name(), TOTAL_SCORE | JUDGE_SCORE))
+        {
+            return Err(LLMError::InvalidRequest(format!(
+                "Scorer name '{}' is reserved in sampled evaluations",
+                scorer.name()
+            )));
+        }
+
+        let mut results = Vec::new();
+        for (provider_id, llm) in &self.llms {
+            let outcomes: Vec<_> = stream::iter(0..self.samples)
+                .map(|_| async move {
+                    let response = llm.chat(messages).await?;
+                    self.score(messages, response.text().unwrap_or_default(), None)
+                        .await
+                })
+                .buffer_unordered(self.sample_concurrency)
+                .collect()
+                .await;
+
+            let mut samples = Vec::new();
+            let mut errors = Vec::new();
+            for outcome in outcomes {
+                match outcome {
+                    Ok(sample) => samples.push(sample),
+                    Err(e) => errors.push(e.to_string()),
+                }
+            }
+
+            let mut result = SampledEvalResult {
+                provider_id: provider_id.clone(),
+                samples,
+                errors,
+                stats: BTreeMap::new(),
+            };
+            let mut keys: Vec<String> = vec![TOTAL_SCORE.to_string(), JUDGE_SCORE.to_string()];
+            keys.extend(self.scorings_fns.iter().map(|sc| sc.name().to_string()));
+            for key in keys {
+                if let Some(stats) = ScoreStats::from_samples(&result.scores(&key), &self.stats) {
+                    result.stats.insert(key, stats);
+                }
+            }
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    /// Tests whether two sampled providers differ on a scorer ("total" for the total score)
+    ///
+    /// Returns `None` when either provider has no sample for that scorer.
+    pub fn compare(
+        &self,
+        a: &SampledEvalResult,
+        b: &SampledEvalResult,
+        scorer: &str,
+    ) -> Option<SignificanceTest> {
+        compare_samples(&a.scores(scorer), &b.scores(scorer), &self.stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::atomic::Ordering, time::Duration};
+
+    use super::*;
+    use crate::testing::MockLLM;
+
+    fn evaluator(llm: MockLLM) -> LLMEvaluator {
+        LLMEvaluator::named(vec![("mock".to_string(), Box::new(llm))])
+            .named_scoring("length", |response| response.len() as f32)
+            .samples(6)
+    }
+
+    fn messages() -> Vec<ChatMessage> {
+        vec![ChatMessage::user().content("Say hi").build()]
+    }
+
+    #[tokio::test]
+    async fn samples_are_summarized_per_scorer() {
+        let evaluator = evaluator(MockLLM::new(["hi"])).sample_concurrency(2);
+
+        let results = evaluator.evaluate_chat_sampled(&messages()).await.unwrap();
+
+        assert_eq!(results[0].samples.len(), 6);
+        assert_eq!(results[0].scores("length"), vec![2.0; 6]);
+        assert_eq!(results[0].stats["length"].mean, 2.0);
+        assert!(results[0].stats.contains_key(TOTAL_SCORE));
+        assert!(!results[0].stats.contains_key(JUDGE_SCORE));
+    }
+
+    #[tokio::test]
+    async fn concurrency_limit_is_observed_by_the_provider() {
+        let llm = MockLLM::new(["hi"]).delay(Duration::from_millis(20));
+        let max_in_flight = llm.max_in_flight();
+        let evaluator = evaluator(llm).sample_concurrency(2);
+
+        evaluator.evaluate_chat_sampled(&messages()).await.unwrap();
+
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn reserved_scorer_names_are_rejected() {
+        for name in [TOTAL_SCORE, JUDGE_SCORE] {
+            let evaluator = evaluator(MockLLM::new(["hi"])).named_scoring(name, |_| 1.0);
+            let error = evaluator
+                .evaluate_chat_sampled(&messages())
+                .await
+                .unwrap_err();
+            assert!(matches!(error, LLMError::InvalidRequest(_)));
+        }
+    }
+
+    #[tokio::test]
+    async fn compare_uses_scores_of_both_providers() {
+        let evaluator = LLMEvaluator::named(vec![
+            ("long".to_string(), Box::new(MockLLM::new(["hello"])) as _),
+            ("short".to_string(), Box::new(MockLLM::new(["hi"])) as _),
+        ])
+        .named_scoring("length", |response| response.len() as f32)
+        .samples(6);
+
+        let results = evaluator.evaluate_chat_sampled(&messages()).await.unwrap();
+
+        assert_eq!(results[0].provider_id, "long");
+        assert!(evaluator
+            .compare(&results[0], &results[1], "length")
+            .is_some());
+        assert!(evaluator
+            .compare(&results[0], &results[1], "missing")
+            .is_none());
+    }
+}
@@ -0,0 +1,784 @@
+//! Built-in scorers for [`LLMEvaluator`](super::LLMEvaluator).
+//!
+//! Every scorer has a name, reported in [`EvalResult::breakdown`](super::EvalResult), and a
+//! weight applied when summing the total score. Scores are in `[0, 1]` unless stated
+//! otherwise; reference-based scorers return 0 when no reference answer is available.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::{chat::ChatMessage, embedding::cosine_similarity, error::LLMError, LLMProvider};
+
+pub use crate::text::LengthUnit;
+
+/// What a scorer sees of an evaluated response
+#[derive(Debug, Clone, Copy)]
+pub struct ScoreInput<'a> {
+    /// Messages sent to the provider
+    pub messages: &'a [ChatMessage],
+    /// Response text to score
+    pub response: &'a str,
+    /// Reference answer, when the evaluated case has one
+    pub reference: Option<&'a str>,
+}
+
+/// A named, weighted scoring function
+#[async_trait]
+pub trait Scorer: Send + Sync {
+    /// Name reported in score breakdowns
+    fn name(&self) -> &str;
+
+    /// Factor applied to the score when summing the total score
+    fn weight(&self) -> f32 {
+        1.0
+    }
+
+    /// Scores a response
+    async fn score(&self, input: &ScoreInput<'_>) -> Result<f32, LLMError>;
+}
+
+/// Adapters renaming or reweighting any scorer
+pub trait ScorerExt: Scorer + Sized {
+    /// Reports the scorer under another name
+    fn with_name(self, name: impl Into<String>) -> Configured<Self> {
+        Configured {
+            inner: self,
+            name: Some(name.into()),
+            weight: None,
+        }
+    }
+
+    /// Applies a weight to the scorer
+    fn with_weight(self, weight: f32) -> Configured<Self> {
+        Configured {
+            inner: self,
+            name: None,
+            weight: Some(weight),
+        }
+    }
+}
+
+impl<S: Scorer + Sized> ScorerExt for S {}
+
+/// A scorer with an overridden name or weight
+pub struct Configured<S> {
+    inner: S,
+    name: Option<String>,
+    weight: Option<f32>,
+}
+
+impl<S: Scorer> Configured<S> {
+    /// Reports the scorer under another name
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Applies a weight to the scorer
+    pub fn with_weight(mut self, weight: f32) -> Self {
+        self.weight = Some(weight);
+        self
+    }
+}
+
+#[async_trait]
+impl<S: Scorer> Scorer for Configured<S> {
+    fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or_else(|| self.inner.name())
+    }
+
+    fn weight(&self) -> f32 {
+        self.weight.unwrap_or_else(|| self.inner.weight())
+    }
+
+    async fn score(&self, input: &ScoreInput<'_>) -> Result<f32, LLMError> {
+        self.inner.score(input).await
+    }
+}
+
+/// Scorer wrapping a closure over the response text
+pub struct FnScorer {
+    name: String,
+    f: Box<super::ScoringFn>,
+}
+
+impl FnScorer {
+    pub fn new<F>(name: impl Into<String>, f: F) -> Self
+    where
+        F: Fn(&str) -> f32 + Send + Sync + 'static,
+    {
+        Self {
+            name: name.into(),
+            f: Box::new(f),
+        }
+    }
+}
+
+#[async_trait]
+impl Scorer for FnScorer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn score(&self, input: &ScoreInput<'_>) -> Result<f32, LLMError> {
+        Ok((self.f)(input.response))
+    }
+}
+
+fn bool_score(ok: bool) -> f32 {
+    if ok {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// Lowercases, drops punctuation and collapses whitespace
+pub fn normalize_text(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn tokens(text: &str) -> Vec<String> {
+    normalize_text(text)
+        .split(' ')
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// 1 when the trimmed response equals the reference
+pub struct ExactMatch;
+
+#[async_trait]
+impl Scorer for ExactMatch {
+    fn name(&self) -> &str {
+        "exact_match"
+    }
+
+    async fn score(&self, input: &ScoreInput<'_>) -> Result<f32, LLMError> {
+        Ok(bool_score(
+            input.reference.map(str::trim) == Some(input.response.trim()),
+        ))
+    }
+}
+
+/// 1 when the response equals the reference ignoring case, punctuation and spacing
+pub struct NormalizedMatch;
+
+#[async_trait]
+impl Scorer for NormalizedMatch {
+    fn name(&self) -> &str {
+        "normalized_match"
+    }
+
+    async fn score(&self, input: &ScoreInput<'_>) -> Result<f32, LLMError> {
+        Ok(bool_score(input.reference.is_some_and(|reference| {
+            normalize_text(reference) == normalize_text(input.response)
+        })))
+    }
+}
+
+/// 1 when the response contains a match of a regular expression
+pub struct RegexPresence {
+    name: String,
+    regex: Regex,
+}
+
+impl RegexPresence {
+    pub fn new(pattern: &str) -> Result<Self, LLMError> {
+        let regex = Regex::new(pattern)
+            .map_err(|e| LLMError::InvalidRequest(format!("Invalid regex: {}", e)))?;
+        Ok(Self {
+            name: format!("regex:{}", pattern),
+            regex,
+        })
+    }
+}
+
+#[async_trait]
+impl Scorer for RegexPresence {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn score(&self, input: &ScoreInput<'_>) -> Result<f32, LLMError> {
+        Ok(bool_score(self.regex.is_match(input.response)))
+    }
+}
+
+/// 1 when the whole response parses as JSON
+pub struct JsonParses;
+
+#[async_trait]
+impl Scorer for JsonParses {
+    fn name(&self) -> &str {
+        "json_parses"
+    }
+
+    async fn score(&self, input: &ScoreInput<'_>) -> Result<f32, LLMError> {
+        Ok(bool_score(
+            serde_json::from_str::<Value>(input.response.trim()).is_ok(),
+        ))
+    }
+}
+
+/// 1 when the response parses as JSON and conforms to a JSON schema
+pub struct JsonSchemaConformance {
+    validator: jsonschema::Validator,
+}
+
+impl JsonSchemaConformance {
+    pub fn new(schema: &Value) -> Result<Self, LLMError> {
+        let validator = jsonschema::validator_for(schema)
+            .map_err(|e| LLMError::InvalidRequest(format!("Invalid JSON schema: {}", e)))?;
+        Ok(Self { validator })
+    }
+}
+
+#[async_trait]
+impl Scorer for JsonSchemaConformance {
+    fn name(&self) -> &str {
+        "json_schema"
+    }
+
+    async fn score(&self, input: &ScoreInput<'_>) -> Result<f32, LLMError> {
+        Ok(bool_score(
+            serde_json::from_str::<Value>(input.response.trim())
+                .is_ok_and(|value| self.validator.is_valid(&value)),
+        ))
+    }
+}
+
+/// ROUGE-L F1 between the response and the reference, over lowercase word tokens
+pub struct RougeL;
+
+/// Computes the ROUGE-L F1 score of `candidate` against `reference`
+pub fn rouge_l(candidate: &str, reference: &str) -> f32 {
+    let (c, r) = (tokens(candidate), tokens(reference));
+    if c.is_empty() || r.is_empty() {
+        return 0.0;
+    }
+
+    // Longest common subsequence, keeping a single DP row
+    let mut row = vec![0usize; r.len() + 1];
+    for ct in &c {
+        let mut diag = 0;
+        for (j, rt) in r.iter().enumerate() {
+            let up = row[j + 1];
+            row[j + 1] = if ct == rt { diag + 1 } else { up.max(row[j]) };
+            diag = up;
+        }
+    }
+    let lcs = row[r.len()] as f32;
+    if lcs == 0.0 {
+        return 0.0;
+    }
+    let precision = lcs / c.len() as f32;
+    let recall = lcs / r.len() as f32;
+    2.0 * precision * recall / (precision + recall)
+}
+
+#[async_trait]
+impl Scorer for RougeL {
+    fn name(&self) -> &str {
+        "rouge_l"
+    }
+
+    async fn score(&self, input: &ScoreInput<'_>) -> Result<f32, LLMError> {
+        Ok(input
+            .reference
+            .map_or(0.0, |reference| rouge_l(input.response, reference)))
+    }
+}
+
+/// Sentence BLEU (up to 4-grams, add-one smoothing) against the reference
+pub struct Bleu;
+
+/// Computes the smoothed sentence BLEU score of `candidate` against `reference`
+pub fn bleu(candidate: &str, reference: &str) -> f32 {
+    let (c, r) = (tokens(candidate), tokens(reference));
+    if c.is_empty() || r.is_empty() {
+        return 0.0;
+    }
+
+    let max_n = 4.min(c.len());
+    let mut log_precision = 0.0;
+    for n in 1..=max_n {
+        let mut reference_counts = std::collections::HashMap::new();
+        for gram in r.windows(n) {
+            *reference_counts.entry(gram).or_insert(0usize) += 1;
+        }
+        let mut matches = 0usize;
+        for gram in c.windows(n) {
+            if let Some(count) = reference_counts.get_mut(gram) {
+                if *count > 0 {
+                    *count -= 1;
+                    matches += 1;
+                }
+            }
+        }
+        let total = c.len() + 1 - n;
+        // Smooth higher orders so a missing 4-gram doesn't zero the score
+        let (num, den) = if n == 1 {
+            (matches as f32, total as f32)
+        } else {
+            (matches as f32 + 1.0, total as f32 + 1.0)
+        };
+        if num == 0.0 {
+            return 0.0;
+        }
+        log_precision += (num / den).ln() / max_n as f32;
+    }
+
+    let brevity = if c.len() >= r.len() {
+        1.0
+    } else {
+        (1.0 - r.len() as f32 / c.len() as f32).exp()
+    };
+    brevity * log_precision.exp()
+}
+
+#[async_trait]
+impl Scorer for Bleu {
+    fn name(&self) -> &str {
+        "bleu"
+    }
+
+    async fn score(&self, input: &ScoreInput<'_>) -> Result<f32, LLMError> {
+        Ok(input
+            .reference
+            .map_or(0.0, |reference| bleu(input.response, reference)))
+    }
+}
+
+/// Cosine similarity between the response and reference embeddings
+pub struct EmbeddingSimilarity {
+    embedder: Arc<dyn LLMProvider>,
+}
+
+impl EmbeddingSimilarity {
+    /// # Arguments
+    /// * `embedder` - Provider used to embed the response and the reference
+    pub fn new(embedder: Box<dyn LLMProvider>) -> Self {
+        Self {
+            embedder: Arc::from(embedder),
+        }
+    }
+}
+
+#[async_trait]
+impl Scorer for EmbeddingSimilarity {
+    fn name(&self) -> &str {
+        "embedding_similarity"
+    }
+
+    async fn score(&self, input: &ScoreInput<'_>) -> Result<f32, LLMError> {
+        let Some(reference) = input.reference else {
+            return Ok(0.0);
+        };
+        let embeddings = self
+            .embedder
+            .embed(vec![input.response.to_string(), reference.to_string()])
+            .await?;
+        match embeddings.as_slice() {
+            [response, reference] => Ok(cosine_similarity(response, reference)),
+            _ => Err(LLMError::ProviderError(
+                "Embedding provider returned an unexpected number of vectors".into(),
+            )),
+        }
+    }
+}
+
+/// 1 when the response length is within bounds
+pub struct LengthBounds {
+    min: Option<usize>,
+    max: Option<usize>,
+    unit: LengthUnit,
+}
+
+impl LengthBounds {
+    pub fn new(min: Option<usize>, max: Option<usize>, unit: LengthUnit) -> Self {
+        Self { min, max, unit }
+    }
+
+    /// Bounds measured in characters
+    pub fn chars(min: usize, max: usize) -> Self {
+        Self::new(Some(min), Some(max), LengthUnit::Chars)
+    }
+
+    /// Bounds measured in whitespace-separated words
+    pub fn words(min: usize, max: usize) -> Self {
+        Self::new(Some(min), Some(max), LengthUnit::Words)
+    }
+
+    /// Bounds measured in estimated tokens
+    pub fn tokens(min: usize, max: usize) -> Self {
+        Self::new(Some(min), Some(max), LengthUnit::Tokens)
+    }
+}
+
+#[async_trait]
+impl Scorer for LengthBounds {
+    fn name(&self) -> &str {
+        "length"
+    }
+
+    async fn score(&self, input: &ScoreInput<'_>) -> Result<f32, LLMError> {
+        let len = self.unit.measure(input.response);
+        Ok(bool_score(
+            self.min.is_none_or(|min| len >= min) && self.max.is_none_or(|max| len <= max),
+        ))
+    }
+}
+
+/// Fraction of the fenced code blocks written in the expected language
+///
+/// The language comes from the fence info string (` ```rust `) and is otherwise
+/// guessed from the block content. Responses without code blocks score 0.
+pub struct CodeBlockLanguage {
+    name: String,
+    language: String,
+}
+
+impl CodeBlockLanguage {
+    pub fn new(language: impl Into<String>) -> Self {
+        let language = canonical_language(&language.into());
+        Self {
+            name: format!("code_language:{}", language),
+            language,
+        }
+    }
+}
+
+#[async_trait]
+impl Scorer for CodeBlockLanguage {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn score(&self, input: &ScoreInput<'_>) -> Result<f32, LLMError> {
+        let blocks = code_blocks(input.response);
+        if blocks.is_empty() {
+            return Ok(0.0);
+        }
+        let matching = blocks
+            .iter()
+            .filter(|(tag, code)| {
+                let detected = if tag.is_empty() {
+                    detect_language(code).map(str::to_string)
+                } else {
+                    Some(canonical_language(tag))
+                };
+                detected.as_deref() == Some(self.language.as_str())
+            })
+            .count();
+        Ok(matching as f32 / blocks.len() as f32)
+    }
+}
+
+/// Extracts (info string, content) of each fenced code block
+pub fn code_blocks(text: &str) -> Vec<(String, String)> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(String, Vec<&str>)> = None;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        match (&mut current, trimmed.strip_prefix("```")) {
+            (None, Some(info)) => current = Some((info.trim().to_string(), Vec::new())),
+            (Some(_), Some(_)) => {
+                if let Some((tag, lines)) = current.take() {
+                    blocks.push((tag, lines.join("\n")));
+                }
+            }
+            (Some((_, lines)), None) => lines.push(line),
+            (None, None) => {}
+        }
+    }
+    blocks
+}
+
+fn canonical_language(tag: &str) -> String {
+    let tag = tag
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '{')
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    match tag.as_str() {
+        "rs" => "rust",
+        "py" | "python3" => "python",
+        "js" | "node" => "javascript",
+        "ts" => "typescript",
+        "sh" | "shell" | "zsh" => "bash",
+        "golang" => "go",
+        "c++" | "cc" | "hpp" => "cpp",
+        "yml" => "yaml",
+        other => other,
+    }
+    .to_string()
+}
+
+/// Guesses the language of a code snippet from characteristic tokens
+pub fn detect_language(code: &str) -> Option<&'static str> {
+    const SIGNATURES: &[(&str, &[&str])] = &[
+        (
+            "rust",
+            &[
+                "fn ",
+                "let mut ",
+                "impl ",
+                "pub fn",
+                "use std::",
+                "::<",
+                "-> Result<",
+            ],
+        ),
+        (
+            "python",
+            &[
+                "def ", "import ", "self.", "elif ", "print(", "__init__", "None:",
+            ],
+        ),
+        (
+            "javascript",
+            &[
+                "const ",
+                "function ",
+                "=> {",
+                "console.log",
+                "require(",
+                "let ",
+            ],
+        ),
+        (
+            "typescript",
+            &[
+                "interface ",
+                ": string",
+                ": number",
+                "export type",
+                "as const",
+            ],
+        ),
+        (
+            "go",
+            &["func ", "package ", ":= ", "fmt.", "go func", "chan "],
+        ),
+        (
+            "java",
+            &[
+                "public class",
+                "public static void",
+                "System.out",
+                "private final",
+                "import java.",
+            ],
+        ),
+        (
+            "cpp",
+            &["#include", "std::", "cout <<", "template<", "nullptr"],
+        ),
+        (
+            "bash",
+            &["#!/bin/", "echo ", "fi\n", "$(", "export ", "then\n"],
+        ),
+        (
+            "sql",
+            &["SELECT ", "FROM ", "WHERE ", "INSERT INTO", "CREATE TABLE"],
+        ),
+        ("json", &["{\"", "\": ", "[{"]),
+    ];
+
+    // Reversed so that ties resolve to the first language of the table
+    SIGNATURES
+        .iter()
+        .rev()
+        .map(|(lang, markers)| (*lang, markers.iter().filter(|m| code.contains(**m)).count()))
+        .filter(|(_, hits)| *hits > 0)
+        .max_by_key(|(_, hits)| *hits)
+        .map(|(lang, _)| lang)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{evaluator::LLMEvaluator, testing::MockLLM};
+
+    fn close(actual: f32, expected: f32) -> bool {
+        (actual - expected).abs() < 1e-4
+    }
+
+    async fn score(scorer: &dyn Scorer, response: &str, reference: Option<&str>) -> f32 {
+        let input = ScoreInput {
+            messages: &[],
+            response,
+            reference,
+        };
+        scorer.score(&input).await.unwrap()
+    }
+
+    #[test]
+    fn normalize_text_drops_case_punctuation_and_spacing() {
+        assert_eq!(
+            normalize_text("  Hello,  World!\n It's"),
+            "hello world it s"
+        );
+        assert_eq!(normalize_text("Ünïcode — déjà vu"), "ünïcode déjà vu");
+        assert_eq!(normalize_text("?!"), "");
+    }
+
+    #[test]
+    fn rouge_l_uses_the_longest_common_subsequence() {
+        let reference = "the cat is on the mat";
+        // "the cat on the mat" is common: precision and recall of 5/6
+        assert!(close(
+            rouge_l("The cat sat on the mat.", reference),
+            5.0 / 6.0
+        ));
+        // Precision 1, recall 2/6
+        assert!(close(rouge_l("the mat", reference), 0.5));
+        assert_eq!(rouge_l(reference, reference), 1.0);
+        assert_eq!(rouge_l("dogs bark", reference), 0.0);
+        assert_eq!(rouge_l("", reference), 0.0);
+    }
+
+    #[test]
+    fn bleu_smooths_missing_ngrams_and_penalizes_brevity() {
+        let reference = "the cat is on the mat";
+        assert!(close(bleu(reference, reference), 1.0));
+        // Precisions 5/6, (3+1)/(5+1), (1+1)/(4+1) and (0+1)/(3+1)
+        let expected = (5.0 / 6.0 * 4.0 / 6.0 * 2.0 / 5.0 * 1.0 / 4.0f32).powf(0.25);
+        assert!(close(bleu("the cat sat on the mat", reference), expected));
+        // Perfect precisions, brevity penalty exp(1 - 6/2)
+        assert!(close(bleu("the cat", reference), (-2.0f32).exp()));
+        assert_eq!(bleu("dogs bark", reference), 0.0);
+        assert_eq!(bleu(reference, ""), 0.0);
+    }
+
+    #[test]
+    fn detect_language_counts_markers() {
+        assert_eq!(
+            detect_language("fn main() {\n    let mut x = 1;\n}"),
+            Some("rust")
+        );
+        assert_eq!(
+            detect_language("def area(self):\n    print(self.r)"),
+            Some("python")
+        );
+        assert_eq!(
+            detect_language("SELECT name FROM users WHERE id = 1"),
+            Some("sql")
+        );
+        // One marker each for rust and javascript: the first language wins
+        assert_eq!(detect_language("fn x; const y"), Some("rust"));
+        assert_eq!(detect_language("just prose"), None);
+    }
+
+    #[test]
+    fn code_blocks_keep_info_strings_and_drop_unclosed_fences() {
+        let text = "Intro\n```rust\nfn a() {}\n```\nThen:\n  ```\nplain\n  text\n```\n```py\nopen";
+        assert_eq!(
+            code_blocks(text),
+            vec![
+                ("rust".to_string(), "fn a() {}".to_string()),
+                (String::new(), "plain\n  text".to_string()),
+            ]
+        );
+        assert!(code_blocks("no code").is_empty());
+    }
+
+    #[tokio::test]
+    async fn code_block_language_scores_the_matching_fraction() {
+        let response = "```rs\nfn a() {}\n```\n```\ndef b(self):\n    print(1)\n```";
+        assert_eq!(
+            score(&CodeBlockLanguage::new("Rust"), response, None).await,
+            0.5
+        );
+        assert_eq!(
+            score(&CodeBlockLanguage::new("py"), response, None).await,
+            0.5
+        );
+        assert_eq!(
+            score(&CodeBlockLanguage::new("go"), response, None).await,
+            0.0
+        );
+        assert_eq!(CodeBlockLanguage::new("golang").name(), "code_language:go");
+    }
+
+    #[tokio::test]
+    async fn reference_scorers_need_a_reference() {
+        assert_eq!(score(&ExactMatch, " Paris ", Some("Paris")).await, 1.0);
+        assert_eq!(score(&ExactMatch, "paris", Some("Paris")).await, 0.0);
+        assert_eq!(score(&NormalizedMatch, "paris!", Some("Paris")).await, 1.0);
+        assert_eq!(score(&NormalizedMatch, "Paris", None).await, 0.0);
+        assert_eq!(score(&RougeL, "Paris", None).await, 0.0);
+        assert_eq!(score(&Bleu, "Paris", None).await, 0.0);
+    }
+
+    #[tokio::test]
+    async fn format_scorers_check_the_response() {
+        assert_eq!(score(&JsonParses, " {\"a\": 1} ", None).await, 1.0);
+        assert_eq!(score(&JsonParses, "{a: 1}", None).await, 0.0);
+
+        let schema = JsonSchemaConformance::new(&serde_json::json!({ "type": "array" })).unwrap();
+        assert_eq!(score(&schema, "[1]", None).await, 1.0);
+        assert_eq!(score(&schema, "{}", None).await, 0.0);
+        assert!(JsonSchemaConformance::new(&serde_json::json!({ "type": 3 })).is_err());
+
+        let regex = RegexPresence::new(r"\d{4}").unwrap();
+        assert_eq!(regex.name(), r"regex:\d{4}");
+        assert_eq!(score(&regex, "in 1969", None).await, 1.0);
+        assert!(RegexPresence::new("(").is_err());
+
+        let words = LengthBounds::words(2, 3);
+        assert_eq!(score(&words, "one two three", None).await, 1.0);
+        assert_eq!(score(&words, "one", None).await, 0.0);
+        let at_most = LengthBounds::new(None, Some(2), LengthUnit::Tokens);
+        assert_eq!(score(&at_most, "12345678", None).await, 1.0);
+        assert_eq!(score(&at_most, "123456789", None).await, 0.0);
+    }
+
+    #[tokio::test]
+    async fn embedding_similarity_compares_with_the_reference() {
+        let scorer = EmbeddingSimilarity::new(Box::new(MockLLM::default()));
+        assert!(close(score(&scorer, "abc", Some("cab")).await, 1.0));
+        assert_eq!(score(&scorer, "abc", Some("xyz")).await, 0.0);
+        assert_eq!(score(&scorer, "abc", None).await, 0.0);
+    }
+
+    #[tokio::test]
+    async fn configured_scorers_are_renamed_and_weighted() {
+        let scorer = ExactMatch.with_weight(2.0).with_name("exact");
+        assert_eq!((scorer.name(), scorer.weight()), ("exact", 2.0));
+        let renamed = NormalizedMatch.with_name("normalized");
+        assert_eq!((renamed.name(), renamed.weight()), ("normalized", 1.0));
+
+        let evaluator = LLMEvaluator::new(Vec::new())
+            .scorer(scorer)
+            .scorer(JsonParses.with_weight(0.5))
+            .scorer(FnScorer::new("half", |_| 0.5));
+        let result = evaluator
+            .score(&[], "Paris".to_string(), Some("Paris"))
+            .await
+            .unwrap();
+
+        let weights: Vec<(&str, f32)> = result
+            .breakdown
+            .iter()
+            .map(|d| (d.name.as_str(), d.weight))
+            .collect();
+        assert_eq!(
+            weights,
+            vec![("exact", 2.0), ("json_parses", 0.5), ("half", 1.0)]
+        );
+        // 2 * 1 + 0.5 * 0 + 1 * 0.5
+        assert!(close(result.score, 2.5));
+    }
+}
@@ -0,0 +1,281 @@
+//! Statistics over repeated evaluation samples.
+//!
+//! Sampling a provider several times turns a single noisy score into a distribution.
+//! This module summarizes such distributions (mean, variance, bootstrap confidence
+//! interval) and tests whether two providers differ significantly, using a two-sided
+//! permutation test on the difference of means.
+
+use serde::Serialize;
+
+/// Key of the total score in per-scorer statistics
+pub const TOTAL_SCORE: &str = "total";
+
+/// Key of the judge score in per-scorer statistics
+pub const JUDGE_SCORE: &str = "judge";
+
+/// Settings of the statistical computations
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatsConfig {
+    /// Confidence level of intervals, e.g. 0.95
+    pub confidence: f64,
+    /// Number of bootstrap resamples and permutations
+    pub resamples: usize,
+    /// Seed of the resampling generator, for reproducible reports
+    pub seed: u64,
+}
+
+impl Default for StatsConfig {
+    fn default() -> Self {
+        Self {
+            confidence: 0.95,
+            resamples: 2000,
+            seed: 0x5EED,
+        }
+    }
+}
+
+/// Summary of a score distribution
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ScoreStats {
+    /// Number of samples
+    pub n: usize,
+    /// Sample mean
+    pub mean: f64,
+    /// Unbiased sample variance (0 for a single sample)
+    pub variance: f64,
+    /// Sample standard deviation
+    pub std_dev: f64,
+    /// Lower bound of the bootstrap confidence interval of the mean
+    pub ci_low: f64,
+    /// Upper bound of the bootstrap confidence interval of the mean
+    pub ci_high: f64,
+}
+
+impl ScoreStats {
+    /// Summarizes `samples`, returning `None` when there are none
+    pub fn from_samples(samples: &[f32], config: &StatsConfig) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let values: Vec<f64> = samples.iter().map(|&s| s as f64).collect();
+        let mean = mean(&values);
+        let variance = variance(&values, mean);
+
+        let mut rng = SplitMix64::new(config.seed);
+        let mut means: Vec<f64> = (0..config.resamples)
+            .map(|_| {
+                let sum: f64 = (0..values.len())
+                    .map(|_| values[rng.below(values.len())])
+                    .sum();
+                sum / values.len() as f64
+            })
+            .collect();
+        let (ci_low, ci_high) = percentile_interval(&mut means, config.confidence, mean);
+
+        Some(Self {
+            n: values.len(),
+            mean,
+            variance,
+            std_dev: variance.sqrt(),
+            ci_low,
+            ci_high,
+        })
+    }
+}
+
+/// Result of comparing two score distributions
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SignificanceTest {
+    /// Mean of the first distribution minus mean of the second
+    pub mean_diff: f64,
+    /// Lower bound of the bootstrap confidence interval of the difference
+    pub ci_low: f64,
+    /// Upper bound of the bootstrap confidence interval of the difference
+    pub ci_high: f64,
+    /// Two-sided permutation test p-value
+    pub p_value: f64,
+    /// Whether `p_value` is below `1 - confidence`
+    pub significant: bool,
+}
+
+/// Tests whether the means of `a` and `b` differ, returning `None` if either is empty
+pub fn compare_samples(a: &[f32], b: &[f32], config: &StatsConfig) -> Option<SignificanceTest> {
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+    let a: Vec<f64> = a.iter().map(|&s| s as f64).collect();
+    let b: Vec<f64> = b.iter().map(|&s| s as f64).collect();
+    let observed = mean(&a) - mean(&b);
+    let mut rng = SplitMix64::new(config.seed);
+
+    // Bootstrap interval of the difference, resampling each group independently
+    let mut diffs: Vec<f64> = (0..config.resamples)
+        .map(|_| {
+            let ma = (0..a.len()).map(|_| a[rng.below(a.len())]).sum::<f64>() / a.len() as f64;
+            let mb = (0..b.len()).map(|_| b[rng.below(b.len())]).sum::<f64>() / b.len() as f64;
+            ma - mb
+        })
+        .collect();
+    let (ci_low, ci_high) = percentile_interval(&mut diffs, config.confidence, observed);
+
+    // Permutation test: shuffle group labels and count differences at least as extreme
+    let mut pooled: Vec<f64> = a.iter().chain(b.iter()).copied().collect();
+    let mut extreme = 0usize;
+    for _ in 0..config.resamples {
+        rng.shuffle(&mut pooled);
+        let (pa, pb) = pooled.split_at(a.len());
+        if (mean(pa) - mean(pb)).abs() >= observed.abs() - 1e-12 {
+            extreme += 1;
+        }
+    }
+    // Add-one smoothing keeps the p-value valid for a finite number of permutations
+    let p_value = (extreme + 1) as f64 / (config.resamples + 1) as f64;
+
+    Some(SignificanceTest {
+        mean_diff: observed,
+        ci_low,
+        ci_high,
+        p_value,
+        significant: p_value < 1.0 - config.confidence,
+    })
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+fn variance(values: &[f64], mean: f64) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64
+}
+
+/// Percentile interval of bootstrap estimates, falling back to `point` without resamples
+fn percentile_interval(estimates: &mut [f64], confidence: f64, point: f64) -> (f64, f64) {
+    if estimates.is_empty() {
+        return (point, point);
+    }
+    estimates.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let alpha = (1.0 - confidence.clamp(0.0, 1.0)) / 2.0;
+    let last = estimates.len() - 1;
+    let low = ((alpha * last as f64).floor() as usize).min(last);
+    let high = (((1.0 - alpha) * last as f64).ceil() as usize).min(last);
+    (estimates[low], estimates[high])
+}
+
+/// Small deterministic generator, good enough for resampling
+pub(crate) struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform index in `0..n`
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Fisher-Yates shuffle
+    pub(crate) fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(resamples: usize) -> StatsConfig {
+        StatsConfig {
+            confidence: 0.9,
+            resamples,
+            seed: 42,
+        }
+    }
+
+    fn close(actual: f64, expected: f64) -> bool {
+        (actual - expected).abs() < 1e-6
+    }
+
+    #[test]
+    fn generator_matches_reference_splitmix64() {
+        assert_eq!(SplitMix64::new(0).next_u64(), 0xE220_A839_7B1D_CDAF);
+
+        let mut items: Vec<u32> = (0..10).collect();
+        SplitMix64::new(7).shuffle(&mut items);
+        items.sort_unstable();
+        assert_eq!(items, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn bootstrap_interval_is_reproducible() {
+        let samples = [0.2, 0.4, 0.6, 0.8];
+        let stats = ScoreStats::from_samples(&samples, &config(1000)).unwrap();
+
+        assert_eq!(stats.n, 4);
+        assert!(close(stats.mean, 0.5));
+        assert!(close(stats.variance, 0.2 / 3.0));
+        assert!(close(stats.std_dev, (0.2f64 / 3.0).sqrt()));
+        // Means of 4 resampled values move in steps of 0.05
+        assert!(close(stats.ci_low, 0.3), "{:?}", stats);
+        assert!(close(stats.ci_high, 0.65), "{:?}", stats);
+        assert_eq!(
+            ScoreStats::from_samples(&samples, &config(1000)),
+            Some(stats)
+        );
+    }
+
+    #[test]
+    fn degenerate_samples_have_a_point_interval() {
+        assert_eq!(ScoreStats::from_samples(&[], &config(100)), None);
+
+        let single = ScoreStats::from_samples(&[0.7], &config(100)).unwrap();
+        assert_eq!(single.variance, 0.0);
+        assert!(close(single.ci_low, 0.7) && close(single.ci_high, 0.7));
+
+        let unsampled = ScoreStats::from_samples(&[0.2, 0.8], &config(0)).unwrap();
+        assert!(close(unsampled.ci_low, 0.5) && close(unsampled.ci_high, 0.5));
+    }
+
+    #[test]
+    fn separated_samples_differ_significantly() {
+        let a = [0.9, 0.95, 0.85, 0.9, 0.92, 0.88, 0.91, 0.93];
+        let b = [0.1, 0.15, 0.2, 0.12, 0.1, 0.18, 0.14, 0.11];
+        let test = compare_samples(&a, &b, &config(1000)).unwrap();
+
+        assert!(close(test.mean_diff, 0.7675));
+        assert!(test.ci_low > 0.7 && test.ci_high < 0.8, "{:?}", test);
+        // None of the shuffles separates the groups as well, leaving the smoothing term
+        assert!(close(test.p_value, 1.0 / 1001.0), "{:?}", test);
+        assert!(test.significant);
+
+        let reversed = compare_samples(&b, &a, &config(1000)).unwrap();
+        assert!(close(reversed.mean_diff, -0.7675));
+        assert!(reversed.significant);
+    }
+
+    #[test]
+    fn identical_samples_do_not_differ() {
+        let a = [0.5, 0.6, 0.7, 0.5, 0.6, 0.7];
+        let test = compare_samples(&a, &a, &config(500)).unwrap();
+
+        assert!(close(test.mean_diff, 0.0));
+        assert!(test.ci_low <= 0.0 && test.ci_high >= 0.0);
+        assert!(close(test.p_value, 1.0));
+        assert!(!test.significant);
+        assert_eq!(compare_samples(&a, &[], &config(500)), None);
+    }
+}
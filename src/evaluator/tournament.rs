@@ -0,0 +1,524 @@
+//! Pairwise comparisons and rating of providers.
+//!
+//! A [`PairwiseJudge`] asks a judge LLM which of two responses to the same prompt is
+//! better, optionally asking twice with swapped positions to counter position bias. A
+//! tournament runs every pair of providers over a dataset and aggregates the verdicts
+//! into Elo or Bradley-Terry ratings, overall or per task type (dataset tag).
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    chat::{ChatMessage, ChatRole, MessageType},
+    error::LLMError,
+    json::extract_json,
+    LLMProvider,
+};
+
+use super::{conversation::render_transcript, EvalDataset, LLMEvaluator};
+
+/// Outcome of a pairwise comparison
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preference {
+    /// The first response is better
+    A,
+    /// The second response is better
+    B,
+    /// Neither is clearly better, or the judge contradicted itself when swapped
+    Tie,
+}
+
+impl Preference {
+    fn swapped(self) -> Self {
+        match self {
+            Preference::A => Preference::B,
+            Preference::B => Preference::A,
+            Preference::Tie => Preference::Tie,
+        }
+    }
+
+    /// Score of the first response: 1 for a win, 0.5 for a tie, 0 for a loss
+    pub fn score_a(self) -> f64 {
+        match self {
+            Preference::A => 1.0,
+            Preference::B => 0.0,
+            Preference::Tie => 0.5,
+        }
+    }
+}
+
+/// Verdict of a pairwise judge
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PairwiseVerdict {
+    /// Which response won
+    pub preference: Preference,
+    /// Judge explanations, one per asked order
+    pub rationales: Vec<String>,
+    /// Whether the judge agreed with itself across swapped positions
+    pub consistent: bool,
+}
+
+#[derive(Deserialize)]
+struct RawVerdict {
+    winner: String,
+    #[serde(default)]
+    rationale: String,
+}
+
+/// Judge LLM comparing two responses to the same conversation
+#[derive(Clone)]
+pub struct PairwiseJudge {
+    judge: Arc<dyn LLMProvider>,
+    criteria: String,
+    swap_positions: bool,
+}
+
+impl PairwiseJudge {
+    /// Creates a pairwise judge asking both orders of each pair
+    pub fn new(judge: Box<dyn LLMProvider>) -> Self {
+        Self {
+            judge: Arc::from(judge),
+            criteria: "Prefer the response that is more correct, helpful and follows the \
+                       instructions. Ignore length unless it hurts clarity."
+                .to_string(),
+            swap_positions: true,
+        }
+    }
+
+    /// Sets the criteria the judge compares against
+    pub fn criteria(mut self, criteria: impl Into<String>) -> Self {
+        self.criteria = criteria.into();
+        self
+    }
+
+    /// Enables or disables asking a second time with swapped positions (enabled by default)
+    pub fn swap_positions(mut self, swap: bool) -> Self {
+        self.swap_positions = swap;
+        self
+    }
+
+    /// Compares two responses to `messages`
+    pub async fn compare(
+        &self,
+        messages: &[ChatMessage],
+        response_a: &str,
+        response_b: &str,
+    ) -> Result<PairwiseVerdict, LLMError> {
+        let (first, rationale) = self.ask(messages, response_a, response_b).await?;
+        if !self.swap_positions {
+            return Ok(PairwiseVerdict {
+                preference: first,
+                rationales: vec![rationale],
+                consistent: true,
+            });
+        }
+
+        let (second, swapped_rationale) = self.ask(messages, response_b, response_a).await?;
+        let second = second.swapped();
+        let consistent = first == second;
+        Ok(PairwiseVerdict {
+            preference: if consistent { first } else { Preference::Tie },
+            rationales: vec![rationale, swapped_rationale],
+            consistent,
+        })
+    }
+
+    async fn ask(
+        &self,
+        messages: &[ChatMessage],
+        first: &str,
+        second: &str,
+    ) -> Result<(Preference, String), LLMError> {
+        let conversation = render_transcript(messages);
+
+        let prompt = format!(
+            "You are an impartial judge comparing two AI assistant responses.\n\n\
+             Criteria:\n{criteria}\n\n\
+             Conversation:\n{conversation}\n\n\
+             [Response A]\n{first}\n[End of Response A]\n\n\
+             [Response B]\n{second}\n[End of Response B]\n\n\
+             Answer only with a JSON object of the form \
+             {{\"winner\": \"A\" | \"B\" | \"tie\", \"rationale\": \"<short explanation>\"}}.",
+            criteria = self.criteria,
+        );
+
+        let answer = self
+            .judge
+            .chat(&[ChatMessage {
+                role: ChatRole::User,
+                message_type: MessageType::Text,
+                content: prompt,
+            }])
+            .await?
+            .text()
+            .unwrap_or_default();
+
+        parse_verdict(&answer).ok_or_else(|| LLMError::ResponseFormatError {
+            message: "Judge answer contains no winner".to_string(),
+            raw_response: answer,
+        })
+    }
+}
+
+fn parse_verdict(answer: &str) -> Option<(Preference, String)> {
+    let raw = extract_json(answer)
+        .ok()
+        .and_then(|extracted| serde_json::from_value::<RawVerdict>(extracted.value).ok());
+    let (winner, rationale) = match raw {
+        Some(raw) => (raw.winner, raw.rationale),
+        None => (answer.trim().to_string(), answer.trim().to_string()),
+    };
+
+    let winner = winner
+        .trim()
+        .trim_matches(|c| c == '"' || c == '[' || c == ']');
+    let preference = match winner.to_lowercase().as_str() {
+        "a" | "response a" => Preference::A,
+        "b" | "response b" => Preference::B,
+        "tie" | "draw" | "equal" => Preference::Tie,
+        _ => return None,
+    };
+    Some((preference, rationale))
+}
+
+/// One judged comparison between two providers
+#[derive(Debug, Clone, Serialize)]
+pub struct PairwiseMatch {
+    /// Dataset case identifier
+    pub case_id: String,
+    /// Tags of the case, used as task types
+    pub tags: Vec<String>,
+    /// First provider
+    pub provider_a: String,
+    /// Second provider
+    pub provider_b: String,
+    /// Judge verdict, `preference` being relative to `provider_a`
+    pub verdict: PairwiseVerdict,
+}
+
+/// Rating of a provider
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Rating {
+    /// Provider identifier
+    pub provider_id: String,
+    /// Rating on the Elo scale (1500 is average)
+    pub rating: f64,
+    /// Number of matches played
+    pub matches: usize,
+    pub wins: usize,
+    pub losses: usize,
+    pub ties: usize,
+}
+
+/// Results of a pairwise tournament
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TournamentReport {
+    /// Judged matches, in play order
+    pub matches: Vec<PairwiseMatch>,
+    /// Error messages of the cases that could not be played
+    pub errors: Vec<String>,
+}
+
+impl TournamentReport {
+    /// Matches of a task type, or all matches when `task` is `None`
+    pub fn matches_for(&self, task: Option<&str>) -> Vec<&PairwiseMatch> {
+        self.matches
+            .iter()
+            .filter(|m| task.is_none_or(|t| m.tags.iter().any(|tag| tag == t)))
+            .collect()
+    }
+
+    /// Task types present in the tournament
+    pub fn tasks(&self) -> Vec<String> {
+        let mut tasks: Vec<String> = self.matches.iter().flat_map(|m| m.tags.clone()).collect();
+        tasks.sort();
+        tasks.dedup();
+        tasks
+    }
+
+    /// Sequential Elo ratings (K-factor `k`, everyone starting at 1500), best first
+    pub fn elo(&self, task: Option<&str>, k: f64) -> Vec<Rating> {
+        let matches = self.matches_for(task);
+        let mut ratings = Self::empty_ratings(&matches);
+        for m in &matches {
+            let ra = ratings[&m.provider_a].rating;
+            let rb = ratings[&m.provider_b].rating;
+            let expected_a = 1.0 / (1.0 + 10f64.powf((rb - ra) / 400.0));
+            let score_a = m.verdict.preference.score_a();
+            if let Some(a) = ratings.get_mut(&m.provider_a) {
+                a.rating += k * (score_a - expected_a);
+            }
+            if let Some(b) = ratings.get_mut(&m.provider_b) {
+                b.rating += k * ((1.0 - score_a) - (1.0 - expected_a));
+            }
+        }
+        Self::finish(ratings, &matches)
+    }
+
+    /// Bradley-Terry ratings fitted by minorization-maximization, on the Elo scale, best first
+    ///
+    /// Ties count as half a win for each side. A small prior keeps the fit finite for
+    /// providers that never win or never lose.
+    pub fn bradley_terry(&self, task: Option<&str>) -> Vec<Rating> {
+        const PRIOR: f64 = 0.5;
+        let matches = self.matches_for(task);
+        let mut ratings = Self::empty_ratings(&matches);
+        let ids: Vec<String> = ratings.keys().cloned().collect();
+        let index: BTreeMap<&str, usize> = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (id.as_str(), i))
+            .collect();
+        let n = ids.len();
+        if n == 0 {
+            return Vec::new();
+        }
+
+        // wins[i][j]: (fractional) wins of i over j, including the prior
+        let prior = PRIOR / (n - 1).max(1) as f64;
+        let mut wins: Vec<Vec<f64>> = (0..n)
+            .map(|i| (0..n).map(|j| if i == j { 0.0 } else { prior }).collect())
+            .collect();
+        for m in &matches {
+            let (a, b) = (index[m.provider_a.as_str()], index[m.provider_b.as_str()]);
+            let score_a = m.verdict.preference.score_a();
+            wins[a][b] += score_a;
+            wins[b][a] += 1.0 - score_a;
+        }
+
+        let mut strength = vec![1.0; n];
+        for _ in 0..200 {
+            let mut next = vec![0.0; n];
+            for i in 0..n {
+                let total_wins: f64 = wins[i].iter().sum();
+                let denom: f64 = (0..n)
+                    .filter(|&j| j != i)
+                    .map(|j| (wins[i][j] + wins[j][i]) / (strength[i] + strength[j]))
+                    .sum();
+                next[i] = if denom > 0.0 {
+                    total_wins / denom
+                } else {
+                    strength[i]
+                };
+            }
+            // Normalize to a geometric mean of 1
+            let log_mean = next.iter().map(|p: &f64| p.ln()).sum::<f64>() / n as f64;
+            let converged = next
+                .iter()
+                .zip(&strength)
+                .all(|(a, b)| (a / log_mean.exp() - b).abs() < 1e-9);
+            strength = next.iter().map(|p| p / log_mean.exp()).collect();
+            if converged {
+                break;
+            }
+        }
+
+        for (i, id) in ids.iter().enumerate() {
+            if let Some(r) = ratings.get_mut(id) {
+                r.rating = 1500.0 + 400.0 * strength[i].log10();
+            }
+        }
+        Self::finish(ratings, &matches)
+    }
+
+    fn empty_ratings(matches: &[&PairwiseMatch]) -> BTreeMap<String, Rating> {
+        let mut ratings = BTreeMap::new();
+        for m in matches {
+            for id in [&m.provider_a, &m.provider_b] {
+                ratings.entry(id.clone()).or_insert_with(|| Rating {
+                    provider_id: id.clone(),
+                    rating: 1500.0,
+                    matches: 0,
+                    wins: 0,
+                    losses: 0,
+                    ties: 0,
+                });
+            }
+        }
+        ratings
+    }
+
+    fn finish(mut ratings: BTreeMap<String, Rating>, matches: &[&PairwiseMatch]) -> Vec<Rating> {
+        for m in matches {
+            let outcomes = match m.verdict.preference {
+                Preference::A => [(&m.provider_a, 1), (&m.provider_b, -1)],
+                Preference::B => [(&m.provider_a, -1), (&m.provider_b, 1)],
+                Preference::Tie => [(&m.provider_a, 0), (&m.provider_b, 0)],
+            };
+            for (id, outcome) in outcomes {
+                if let Some(r) = ratings.get_mut(id) {
+                    r.matches += 1;
+                    match outcome {
+                        1 => r.wins += 1,
+                        -1 => r.losses += 1,
+                        _ => r.ties += 1,
+                    }
+                }
+            }
+        }
+        let mut ratings: Vec<Rating> = ratings.into_values().collect();
+        ratings.sort_by(|a, b| {
+            b.rating
+                .partial_cmp(&a.rating)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        ratings
+    }
+}
+
+impl LLMEvaluator {
+    /// Plays every pair of providers on every dataset case, judged by `judge`
+    ///
+    /// Each provider answers each case once; cases where a provider fails are reported in
+    /// [`TournamentReport::errors`] and skipped for that provider.
+    pub async fn tournament(
+        &self,
+        dataset: &EvalDataset,
+        judge: &PairwiseJudge,
+    ) -> Result<TournamentReport, LLMError> {
+        let mut report = TournamentReport::default();
+
+        for case in &dataset.cases {
+            let messages = case.chat_messages()?;
+            let responses = join_all(self.llms.iter().map(|(id, llm)| {
+                let messages = &messages;
+                async move { (id, llm.chat(messages).await) }
+            }))
+            .await;
+
+            let mut answered = Vec::new();
+            for (id, response) in responses {
+                match response {
+                    Ok(response) => answered.push((id, response.text().unwrap_or_default())),
+                    Err(e) => report.errors.push(format!("{} on {}: {}", id, case.id, e)),
+                }
+            }
+
+            for i in 0..answered.len() {
+                for j in (i + 1)..answered.len() {
+                    let (id_a, text_a) = &answered[i];
+                    let (id_b, text_b) = &answered[j];
+                    match judge.compare(&messages, text_a, text_b).await {
+                        Ok(verdict) => report.matches.push(PairwiseMatch {
+                            case_id: case.id.clone(),
+                            tags: case.tags.clone(),
+                            provider_a: id_a.to_string(),
+                            provider_b: id_b.to_string(),
+                            verdict,
+                        }),
+                        Err(e) => report.errors.push(format!(
+                            "judge on {} ({} vs {}): {}",
+                            case.id, id_a, id_b, e
+                        )),
+                    }
+                }
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockLLM;
+
+    fn game(a: &str, b: &str, preference: Preference, tag: &str) -> PairwiseMatch {
+        PairwiseMatch {
+            case_id: format!("{}-{}", a, b),
+            tags: vec![tag.to_string()],
+            provider_a: a.to_string(),
+            provider_b: b.to_string(),
+            verdict: PairwiseVerdict {
+                preference,
+                rationales: Vec::new(),
+                consistent: true,
+            },
+        }
+    }
+
+    #[test]
+    fn parses_fenced_json_verdicts() {
+        let answer =
+            "Verdict:\n```json\n{\"winner\": \"B\", \"rationale\": \"uses {braces}\"}\n```";
+        assert_eq!(
+            parse_verdict(answer),
+            Some((Preference::B, "uses {braces}".to_string()))
+        );
+    }
+
+    #[test]
+    fn parses_plain_verdicts() {
+        assert_eq!(parse_verdict("tie").map(|v| v.0), Some(Preference::Tie));
+        assert_eq!(
+            parse_verdict("[Response A]").map(|v| v.0),
+            Some(Preference::A)
+        );
+        assert_eq!(parse_verdict("I cannot decide"), None);
+    }
+
+    #[tokio::test]
+    async fn inconsistent_swapped_verdicts_are_ties() {
+        let judge = PairwiseJudge::new(Box::new(MockLLM::new([
+            r#"{"winner": "A"}"#,
+            r#"{"winner": "A"}"#,
+        ])));
+        let messages = [ChatMessage::user().content("Hi").build()];
+
+        let verdict = judge.compare(&messages, "one", "two").await.unwrap();
+
+        assert_eq!(verdict.preference, Preference::Tie);
+        assert!(!verdict.consistent);
+    }
+
+    #[tokio::test]
+    async fn judge_prompt_contains_the_transcript() {
+        let llm = MockLLM::new([r#"{"winner": "B"}"#]);
+        let requests = std::sync::Arc::new(llm);
+        let judge = PairwiseJudge {
+            judge: requests.clone(),
+            criteria: "Be brief".to_string(),
+            swap_positions: false,
+        };
+        let messages = [
+            ChatMessage::user().content("Hi").build(),
+            ChatMessage::assistant().content("Hello").build(),
+            ChatMessage::user().content("Bye").build(),
+        ];
+
+        let verdict = judge.compare(&messages, "one", "two").await.unwrap();
+
+        assert_eq!(verdict.preference, Preference::B);
+        let prompt = requests.requests.lock().unwrap()[0][0].content.clone();
+        assert!(prompt.contains("User: Hi\nAssistant: Hello\nUser: Bye"));
+        assert!(prompt.contains("Be brief"));
+    }
+
+    #[test]
+    fn ratings_rank_the_winner_first() {
+        let report = TournamentReport {
+            matches: vec![
+                game("x", "y", Preference::A, "math"),
+                game("x", "z", Preference::A, "math"),
+                game("y", "z", Preference::Tie, "chat"),
+            ],
+            errors: Vec::new(),
+        };
+
+        let elo = report.elo(None, 32.0);
+        assert_eq!(elo[0].provider_id, "x");
+        assert_eq!((elo[0].wins, elo[0].losses), (2, 0));
+
+        let bt = report.bradley_terry(None);
+        assert_eq!(bt[0].provider_id, "x");
+        assert!((bt[1].rating - bt[2].rating).abs() < 1e-6);
+
+        assert_eq!(report.tasks(), vec!["chat", "math"]);
+        assert_eq!(report.elo(Some("chat"), 32.0).len(), 2);
+        assert!(report.bradley_terry(Some("code")).is_empty());
+    }
+}
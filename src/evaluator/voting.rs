@@ -0,0 +1,269 @@
+//! Self-consistency voting over repeated samples.
+//!
+//! [`SelfConsistency`] samples one or more chat providers several times on the same
+//! messages, extracts a final answer from each response with an [`AnswerExtractor`] and
+//! returns the majority answer with its vote distribution. This stabilizes classification
+//! or math-style answers that flip between single samples.
+
+use std::{collections::BTreeMap, fmt, sync::Arc};
+
+use futures::future::join_all;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{chat::ChatMessage, error::LLMError, json::extract_json, LLMProvider};
+
+/// Closure extracting an answer from a response
+type ExtractFn = dyn Fn(&str) -> Option<String> + Send + Sync;
+
+/// Extracts the final answer of a response, the unit votes are counted in
+#[derive(Clone)]
+pub enum AnswerExtractor {
+    /// Last match of a regex, or its first capture group when it has one
+    Regex(Regex),
+    /// Field of the JSON object found in the response, as a dotted path like "result.label"
+    JsonField(String),
+    /// Custom extraction closure
+    Custom(Arc<ExtractFn>),
+}
+
+impl AnswerExtractor {
+    /// Creates a regex extractor, e.g. `r"answer:\s*(\d+)"`
+    pub fn regex(pattern: &str) -> Result<Self, LLMError> {
+        Regex::new(pattern)
+            .map(AnswerExtractor::Regex)
+            .map_err(|e| LLMError::InvalidRequest(format!("Invalid answer pattern: {}", e)))
+    }
+
+    /// Creates an extractor reading a JSON field, e.g. "label" or "result.label"
+    pub fn json_field(path: impl Into<String>) -> Self {
+        AnswerExtractor::JsonField(path.into())
+    }
+
+    /// Creates an extractor from a closure
+    pub fn custom<F>(f: F) -> Self
+    where
+        F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+    {
+        AnswerExtractor::Custom(Arc::new(f))
+    }
+
+    /// Extracts the answer of a response, if any
+    pub fn extract(&self, response: &str) -> Option<String> {
+        match self {
+            AnswerExtractor::Regex(re) => {
+                let caps = re.captures_iter(response).last()?;
+                caps.get(1)
+                    .or_else(|| caps.get(0))
+                    .map(|m| m.as_str().to_string())
+            }
+            AnswerExtractor::JsonField(path) => {
+                let value = extract_json(response).ok()?.value;
+                let field = path
+                    .split('.')
+                    .try_fold(&value, |value, key| value.get(key))?;
+                match field {
+                    Value::String(s) => Some(s.clone()),
+                    Value::Null => None,
+                    other => Some(other.to_string()),
+                }
+            }
+            AnswerExtractor::Custom(f) => f(response),
+        }
+    }
+}
+
+impl fmt::Debug for AnswerExtractor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnswerExtractor::Regex(re) => f.debug_tuple("Regex").field(&re.as_str()).finish(),
+            AnswerExtractor::JsonField(path) => f.debug_tuple("JsonField").field(path).finish(),
+            AnswerExtractor::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+/// One sampled response and the answer extracted from it
+#[derive(Debug, Clone, Serialize)]
+pub struct VoteSample {
+    /// Provider that produced the response
+    pub provider_id: String,
+    /// Response text
+    pub text: String,
+    /// Extracted answer, after normalization; `None` when extraction failed
+    pub answer: Option<String>,
+}
+
+/// Outcome of a self-consistency vote
+#[derive(Debug, Clone, Serialize)]
+pub struct VoteResult {
+    /// Most voted answer; ties go to the answer seen first. `None` without any answer
+    pub answer: Option<String>,
+    /// Number of votes per answer
+    pub votes: BTreeMap<String, usize>,
+    /// Share of answered samples that voted for the majority answer, in [0, 1]
+    pub agreement: f32,
+    /// Every successful sample, in provider and sample order
+    pub samples: Vec<VoteSample>,
+    /// Errors of failed samples
+    pub errors: Vec<String>,
+}
+
+impl VoteResult {
+    /// Number of samples an answer could be extracted from
+    pub fn answered(&self) -> usize {
+        self.votes.values().sum()
+    }
+}
+
+/// Majority voting over repeated samples of one or more providers
+pub struct SelfConsistency {
+    llms: Vec<(String, Box<dyn LLMProvider>)>,
+    extractor: AnswerExtractor,
+    samples: usize,
+    normalize: bool,
+}
+
+impl SelfConsistency {
+    /// Creates a vote over a single provider
+    pub fn new(llm: Box<dyn LLMProvider>, extractor: AnswerExtractor) -> Self {
+        Self::named(vec![("llm_0".to_string(), llm)], extractor)
+    }
+
+    /// Creates a vote over identified providers, each sampled [`SelfConsistency::samples`] times
+    pub fn named(llms: Vec<(String, Box<dyn LLMProvider>)>, extractor: AnswerExtractor) -> Self {
+        Self {
+            llms,
+            extractor,
+            samples: 5,
+            normalize: true,
+        }
+    }
+
+    /// Sets how many times each provider is sampled (defaults to 5)
+    pub fn samples(mut self, samples: usize) -> Self {
+        self.samples = samples.max(1);
+        self
+    }
+
+    /// Whether answers are trimmed and lowercased before counting (defaults to true)
+    pub fn normalize(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    /// Samples every provider and returns the majority answer
+    ///
+    /// Fails only when every sample failed; samples without an extractable answer are
+    /// kept in [`VoteResult::samples`] but don't vote.
+    pub async fn vote(&self, messages: &[ChatMessage]) -> Result<VoteResult, LLMError> {
+        let requests = self.llms.iter().flat_map(|(id, llm)| {
+            std::iter::repeat_n((id, llm), self.samples).map(|(id, llm)| async move {
+                let text = llm.chat(messages).await?.text().unwrap_or_default();
+                Ok::<_, LLMError>((id, text))
+            })
+        });
+
+        let mut samples = Vec::new();
+        let mut errors = Vec::new();
+        for outcome in join_all(requests).await {
+            match outcome {
+                Ok((provider_id, text)) => {
+                    let answer = self.extractor.extract(&text).map(|a| self.normalized(a));
+                    samples.push(VoteSample {
+                        provider_id: provider_id.clone(),
+                        text,
+                        answer,
+                    });
+                }
+                Err(e) => errors.push(e.to_string()),
+            }
+        }
+
+        if samples.is_empty() {
+            return Err(LLMError::ProviderError(format!(
+                "All samples failed: {}",
+                errors.join("; ")
+            )));
+        }
+
+        let mut votes = BTreeMap::new();
+        let mut order = Vec::new();
+        for answer in samples.iter().filter_map(|s| s.answer.as_ref()) {
+            *votes.entry(answer.clone()).or_insert(0) += 1;
+            if !order.contains(answer) {
+                order.push(answer.clone());
+            }
+        }
+
+        // max_by_key keeps the last maximum, so scan in reverse to favor earlier answers
+        let answer = order.into_iter().rev().max_by_key(|a| votes[a]);
+        let answered: usize = votes.values().sum();
+        let agreement = match &answer {
+            Some(a) => votes[a] as f32 / answered as f32,
+            None => 0.0,
+        };
+
+        Ok(VoteResult {
+            answer,
+            votes,
+            agreement,
+            samples,
+            errors,
+        })
+    }
+
+    fn normalized(&self, answer: String) -> String {
+        if self.normalize {
+            answer.trim().to_lowercase()
+        } else {
+            answer
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockLLM;
+
+    #[test]
+    fn json_field_reads_fenced_and_nested_json() {
+        let extractor = AnswerExtractor::json_field("result.label");
+        let response = "Here:\n```json\n{\"result\": {\"label\": \"spam\"}}\n```\n{not json}";
+        assert_eq!(extractor.extract(response), Some("spam".to_string()));
+        assert_eq!(extractor.extract("{\"result\": null}"), None);
+        assert_eq!(
+            AnswerExtractor::json_field("n").extract("{\"n\": 4}"),
+            Some("4".to_string())
+        );
+    }
+
+    #[test]
+    fn regex_uses_the_last_match() {
+        let extractor = AnswerExtractor::regex(r"answer:\s*(\d+)").unwrap();
+        assert_eq!(
+            extractor.extract("answer: 3, no wait, answer: 4"),
+            Some("4".to_string())
+        );
+        assert!(AnswerExtractor::regex("(").is_err());
+    }
+
+    #[tokio::test]
+    async fn majority_answer_wins() {
+        let llm = MockLLM::new(["Answer: 4", "answer: 5", "ANSWER: 4 "]);
+        let vote = SelfConsistency::new(
+            Box::new(llm),
+            AnswerExtractor::regex(r"(?i)answer:\s*(\d+)").unwrap(),
+        )
+        .samples(3)
+        .vote(&[ChatMessage::user().content("2+2?").build()])
+        .await
+        .unwrap();
+
+        assert_eq!(vote.answer.as_deref(), Some("4"));
+        assert_eq!(vote.answered(), 3);
+        assert!((vote.agreement - 2.0 / 3.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,144 @@
+//! Lenient extraction of JSON from model output.
+//!
+//! Models often wrap JSON in markdown fences, surround it with prose, leave trailing
+//! commas or single quotes, or stop mid-value when hitting a token limit.
+//! [`extract_json`] finds the first JSON value of a response, repairs these defects and
+//! reports each [`JsonRepair`] it made. [`PartialJsonParser`] applies the same repairs to
+//! streamed responses, reporting the value as it grows.
+//!
+//! ```
+//! use rllm::json::{extract_json, JsonRepair};
+//!
+//! let extracted = extract_json("Sure! ```json\n{'name': 'Garfield', 'tags': ['cat',]\n```").unwrap();
+//! assert_eq!(extracted.value["name"], "Garfield");
+//! assert!(extracted.repairs.contains(&JsonRepair::SingleQuotes));
+//! ```
+
+mod partial;
+mod repair;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::LLMError;
+
+pub use partial::{partial_json_stream, PartialJsonEvent, PartialJsonParser};
+pub use repair::JsonRepair;
+pub(crate) use repair::Scanner;
+
+/// Maximum number of candidate start positions tried in a response
+const MAX_CANDIDATES: usize = 16;
+
+/// JSON value extracted from a response
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExtractedJson {
+    /// Parsed value
+    pub value: Value,
+    /// Repaired JSON text the value was parsed from
+    pub json: String,
+    /// Defects fixed to obtain valid JSON, empty when the response was valid as is
+    pub repairs: Vec<JsonRepair>,
+}
+
+impl ExtractedJson {
+    /// Whether the response needed any repair
+    pub fn was_repaired(&self) -> bool {
+        !self.repairs.is_empty()
+    }
+}
+
+/// Extracts and repairs the first JSON value of a response
+///
+/// Objects and arrays are looked for first, so that prose before the JSON is skipped;
+/// a response holding a single scalar, such as `42`, is accepted as well.
+pub fn extract_json(text: &str) -> Result<ExtractedJson, LLMError> {
+    let (content, mut repairs) = strip_fence(text);
+
+    let starts = content
+        .char_indices()
+        .filter(|(_, c)| matches!(c, '{' | '['))
+        .map(|(i, _)| i)
+        .take(MAX_CANDIDATES);
+    for start in starts {
+        let mut scanner = Scanner::default();
+        let mut end = content.len();
+        for (i, c) in content[start..].char_indices() {
+            if !scanner.push(c) {
+                end = start + i;
+                break;
+            }
+        }
+        let Some((json, scan_repairs)) = scanner.finish() else {
+            continue;
+        };
+        let Ok(value) = serde_json::from_str(&json) else {
+            continue;
+        };
+
+        if !content[..start].trim().is_empty() || !content[end..].trim().is_empty() {
+            push_repair(&mut repairs, JsonRepair::SurroundingText);
+        }
+        for repair in scan_repairs {
+            push_repair(&mut repairs, repair);
+        }
+        return Ok(ExtractedJson {
+            value,
+            json,
+            repairs,
+        });
+    }
+
+    let trimmed = content.trim();
+    match serde_json::from_str(trimmed) {
+        Ok(value) => Ok(ExtractedJson {
+            value,
+            json: trimmed.to_string(),
+            repairs,
+        }),
+        Err(_) => Err(LLMError::ResponseFormatError {
+            message: "No JSON value found in response".to_string(),
+            raw_response: text.to_string(),
+        }),
+    }
+}
+
+/// Extracts the first JSON value of a response and deserializes it
+pub fn extract_json_as<T: serde::de::DeserializeOwned>(text: &str) -> Result<T, LLMError> {
+    let extracted = extract_json(text)?;
+    serde_json::from_value(extracted.value).map_err(|e| LLMError::ResponseFormatError {
+        message: e.to_string(),
+        raw_response: text.to_string(),
+    })
+}
+
+/// Returns the content of the first markdown code fence, if any
+fn strip_fence(text: &str) -> (&str, Vec<JsonRepair>) {
+    let Some(open) = text.find("```") else {
+        return (text, Vec::new());
+    };
+    // Skip the language tag, e.g. ```json
+    let tag_len = text[open + 3..]
+        .find(|c: char| !c.is_alphanumeric())
+        .unwrap_or(text.len() - open - 3);
+    let body_start = open + 3 + tag_len;
+    let body_end = text[body_start..]
+        .find("```")
+        .map_or(text.len(), |i| body_start + i);
+    let body = &text[body_start..body_end];
+    if !body.contains(['{', '[']) {
+        return (text, Vec::new());
+    }
+
+    let mut repairs = vec![JsonRepair::Fence];
+    let after = (body_end + 3).min(text.len());
+    if !text[..open].trim().is_empty() || !text[after..].trim().is_empty() {
+        repairs.push(JsonRepair::SurroundingText);
+    }
+    (body, repairs)
+}
+
+fn push_repair(repairs: &mut Vec<JsonRepair>, repair: JsonRepair) {
+    if !repairs.contains(&repair) {
+        repairs.push(repair);
+    }
+}
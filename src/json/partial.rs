@@ -0,0 +1,326 @@
+//! Incremental parsing of JSON streamed in text deltas.
+
+use futures::{stream, Stream, StreamExt};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::LLMError;
+
+use super::Scanner;
+
+/// Progress reported while parsing streamed JSON
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum PartialJsonEvent {
+    /// The value read so far, with open strings, members and brackets closed
+    Snapshot(Value),
+    /// A member of the top-level object was read completely
+    FieldComplete {
+        /// Member name
+        name: String,
+        /// Member value
+        value: Value,
+    },
+    /// An element of the top-level array was read completely
+    ElementComplete {
+        /// Element position
+        index: usize,
+        /// Element value
+        value: Value,
+    },
+    /// The top-level value was read completely
+    Complete(Value),
+}
+
+/// Parser consuming text deltas of a JSON response and reporting the value as it grows
+///
+/// Text before the first `{` or `[`, such as a markdown fence, is skipped, and so is text
+/// after the value. Snapshots close whatever is still open, so a number or literal being
+/// read may still change in the next snapshot; members and elements are only reported
+/// complete once the text following them proves it.
+///
+/// Characters are scanned once, but each snapshot re-parses the whole value read so far, so
+/// a response streamed in `k` deltas costs `O(k * n)` for `n` bytes of JSON: quadratic in
+/// the worst case of single-character deltas. This is negligible for typical responses;
+/// feed larger deltas, or parse the buffered text once with
+/// [`extract_json`](super::extract_json), for very large values.
+///
+/// ```
+/// use rllm::json::{PartialJsonEvent, PartialJsonParser};
+///
+/// let mut parser = PartialJsonParser::new();
+/// let events = parser.push("```json\n{\"name\": \"Garf");
+/// assert_eq!(events, vec![PartialJsonEvent::Snapshot(serde_json::json!({"name": "Garf"}))]);
+///
+/// let events = parser.push("ield\", \"age\": 4");
+/// assert!(events.contains(&PartialJsonEvent::FieldComplete {
+///     name: "name".to_string(),
+///     value: "Garfield".into(),
+/// }));
+/// assert_eq!(parser.finish().unwrap(), serde_json::json!({"name": "Garfield", "age": 4}));
+/// ```
+#[derive(Debug, Default)]
+pub struct PartialJsonParser {
+    scanner: Scanner,
+    /// Whether the first `{` or `[` was read
+    started: bool,
+    /// Number of members or elements already reported complete
+    reported: usize,
+    /// Last snapshot reported
+    snapshot: Option<Value>,
+}
+
+impl PartialJsonParser {
+    /// Creates a parser waiting for the start of a JSON object or array
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the top-level value was read completely
+    pub fn is_complete(&self) -> bool {
+        self.scanner.is_done()
+    }
+
+    /// Last snapshot of the value, `None` before the value starts
+    pub fn snapshot(&self) -> Option<&Value> {
+        self.snapshot.as_ref()
+    }
+
+    /// Consumes a text delta and returns the events it caused
+    ///
+    /// Completed members or elements come first, in order, followed by a snapshot when the
+    /// value changed, and by the complete value once it was read entirely.
+    pub fn push(&mut self, delta: &str) -> Vec<PartialJsonEvent> {
+        let mut events = Vec::new();
+        if self.scanner.is_done() {
+            return events;
+        }
+
+        for c in delta.chars() {
+            if !self.started {
+                if !matches!(c, '{' | '[') {
+                    continue;
+                }
+                self.started = true;
+            }
+            if !self.scanner.push(c) {
+                break;
+            }
+            if self.scanner.completed() > self.reported {
+                self.reported = self.scanner.completed();
+                if let Some(event) = self.completed_event() {
+                    events.push(event);
+                }
+            }
+        }
+
+        if let Some(value) = self.current() {
+            if self.snapshot.as_ref() != Some(&value) {
+                self.snapshot = Some(value.clone());
+                events.push(PartialJsonEvent::Snapshot(value.clone()));
+            }
+            if self.scanner.is_done() {
+                events.push(PartialJsonEvent::Complete(value));
+            }
+        }
+        events
+    }
+
+    /// Closes the value read so far and returns it
+    ///
+    /// Fails when no object or array was started.
+    pub fn finish(self) -> Result<Value, LLMError> {
+        let json = self
+            .scanner
+            .finish()
+            .map(|(json, _)| json)
+            .unwrap_or_default();
+        serde_json::from_str(&json).map_err(|_| LLMError::ResponseFormatError {
+            message: "No JSON value found in response".to_string(),
+            raw_response: json,
+        })
+    }
+
+    /// Value read so far, with everything still open closed
+    fn current(&self) -> Option<Value> {
+        let (json, _) = self.scanner.clone().finish()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    /// Event for the member or element that was just read completely
+    fn completed_event(&self) -> Option<PartialJsonEvent> {
+        let value = self.current()?;
+        match value {
+            Value::Object(mut members) => {
+                let name = self.scanner.last_key()?.to_string();
+                let value = members.remove(&name)?;
+                Some(PartialJsonEvent::FieldComplete { name, value })
+            }
+            Value::Array(mut elements) => {
+                let index = self.reported - 1;
+                let value = elements.get_mut(index).map(Value::take)?;
+                Some(PartialJsonEvent::ElementComplete { index, value })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Parses a stream of text deltas into a stream of [`PartialJsonEvent`]s
+///
+/// Errors of the delta stream are passed through.
+pub fn partial_json_stream<S>(deltas: S) -> impl Stream<Item = Result<PartialJsonEvent, LLMError>>
+where
+    S: Stream<Item = Result<String, LLMError>>,
+{
+    let mut parser = PartialJsonParser::new();
+    deltas.flat_map(move |delta| {
+        let events = match delta {
+            Ok(delta) => parser.push(&delta).into_iter().map(Ok).collect(),
+            Err(e) => vec![Err(e)],
+        };
+        stream::iter(events)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+    use serde_json::json;
+
+    use super::*;
+
+    /// Pushes each delta and returns all events
+    fn events(deltas: &[&str]) -> Vec<PartialJsonEvent> {
+        let mut parser = PartialJsonParser::new();
+        deltas.iter().flat_map(|delta| parser.push(delta)).collect()
+    }
+
+    fn snapshots(events: &[PartialJsonEvent]) -> Vec<Value> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                PartialJsonEvent::Snapshot(value) => Some(value.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn strings_split_across_deltas() {
+        let events = events(&["{\"text\": \"Hel", "lo wo", "rld\"}"]);
+        assert_eq!(
+            snapshots(&events),
+            vec![
+                json!({"text": "Hel"}),
+                json!({"text": "Hello wo"}),
+                json!({"text": "Hello world"}),
+            ]
+        );
+        assert_eq!(
+            events.last(),
+            Some(&PartialJsonEvent::Complete(json!({"text": "Hello world"})))
+        );
+    }
+
+    #[test]
+    fn escapes_split_across_deltas() {
+        let mut parser = PartialJsonParser::new();
+        parser.push("[\"a\\");
+        assert_eq!(parser.snapshot(), Some(&json!(["a"])));
+        parser.push("\"b\\u00");
+        assert_eq!(parser.snapshot(), Some(&json!(["a\"b"])));
+        parser.push("e9\"]");
+        assert!(parser.is_complete());
+        assert_eq!(parser.finish().unwrap(), json!(["a\"b\u{e9}"]));
+    }
+
+    #[test]
+    fn numbers_split_across_deltas() {
+        let mut parser = PartialJsonParser::new();
+        let events = parser.push("[12");
+        assert_eq!(events, vec![PartialJsonEvent::Snapshot(json!([12]))]);
+
+        // The number may still grow: it is not complete before the separator
+        let events = parser.push("3.5");
+        assert_eq!(events, vec![PartialJsonEvent::Snapshot(json!([123.5]))]);
+
+        let events = parser.push(", -");
+        assert_eq!(
+            events,
+            vec![PartialJsonEvent::ElementComplete {
+                index: 0,
+                value: json!(123.5)
+            }]
+        );
+        parser.push("4e1]");
+        assert_eq!(parser.finish().unwrap(), json!([123.5, -40.0]));
+    }
+
+    #[test]
+    fn nested_containers_complete_top_level_members() {
+        let events = events(&["{\"a\": {\"b\": [1,", " {\"c\": true}]}, \"d\": [", "]}"]);
+        let completed: Vec<_> = events
+            .iter()
+            .filter(|event| matches!(event, PartialJsonEvent::FieldComplete { .. }))
+            .collect();
+        assert_eq!(
+            completed,
+            vec![
+                &PartialJsonEvent::FieldComplete {
+                    name: "a".to_string(),
+                    value: json!({"b": [1, {"c": true}]}),
+                },
+                &PartialJsonEvent::FieldComplete {
+                    name: "d".to_string(),
+                    value: json!([]),
+                },
+            ]
+        );
+        assert_eq!(snapshots(&events)[0], json!({"a": {"b": [1]}}));
+    }
+
+    #[test]
+    fn single_character_deltas_match_one_push() {
+        let text = "Here: {'name': \"Garfield\", tags: [\"cat\", \"lazy\",], age: 4} done";
+        let mut whole = PartialJsonParser::new();
+        let events = whole.push(text);
+
+        let mut split = PartialJsonParser::new();
+        for c in text.chars() {
+            split.push(&c.to_string());
+        }
+        let expected = json!({"name": "Garfield", "tags": ["cat", "lazy"], "age": 4});
+        assert!(events.contains(&PartialJsonEvent::Complete(expected.clone())));
+        assert_eq!(split.snapshot(), Some(&expected));
+        assert_eq!(split.finish().unwrap(), expected);
+    }
+
+    #[test]
+    fn ignores_text_around_the_value() {
+        let mut parser = PartialJsonParser::new();
+        assert!(parser.push("```json\n").is_empty());
+        parser.push("[1]");
+        assert!(parser.is_complete());
+        assert!(parser.push("\n```").is_empty());
+        assert_eq!(parser.finish().unwrap(), json!([1]));
+
+        assert!(PartialJsonParser::new().finish().is_err());
+    }
+
+    #[test]
+    fn streams_pass_errors_through() {
+        let deltas = stream::iter(vec![
+            Ok("[1, ".to_string()),
+            Err(LLMError::ProviderError("disconnected".to_string())),
+            Ok("2]".to_string()),
+        ]);
+        let events: Vec<_> = block_on(partial_json_stream(deltas).collect());
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, Err(LLMError::ProviderError(_)))));
+        assert!(matches!(
+            events.last(),
+            Some(Ok(PartialJsonEvent::Complete(value))) if *value == json!([1, 2])
+        ));
+    }
+}
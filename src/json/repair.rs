@@ -0,0 +1,592 @@
+//! Lenient JSON scanner rewriting common model output defects into valid JSON.
+
+use std::fmt;
+
+use serde::Serialize;
+
+/// Defect fixed while extracting JSON from a response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum JsonRepair {
+    /// The JSON was wrapped in a markdown code fence
+    Fence,
+    /// Text before or after the JSON value was dropped
+    SurroundingText,
+    /// Commas before a closing bracket were removed
+    TrailingComma,
+    /// Single-quoted strings were converted to double-quoted ones
+    SingleQuotes,
+    /// Unquoted object keys were quoted
+    UnquotedKeys,
+    /// Raw newlines or tabs inside strings were escaped
+    ControlCharacters,
+    /// The value was truncated: open strings, members and brackets were closed
+    Truncated,
+}
+
+impl fmt::Display for JsonRepair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            JsonRepair::Fence => "removed markdown code fence",
+            JsonRepair::SurroundingText => "dropped text around the JSON value",
+            JsonRepair::TrailingComma => "removed trailing commas",
+            JsonRepair::SingleQuotes => "converted single-quoted strings",
+            JsonRepair::UnquotedKeys => "quoted object keys",
+            JsonRepair::ControlCharacters => "escaped control characters in strings",
+            JsonRepair::Truncated => "closed truncated value",
+        };
+        f.write_str(text)
+    }
+}
+
+/// Position inside an object or array
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expect {
+    Key,
+    Colon,
+    Value,
+    /// A complete member or element was read
+    Next,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Container {
+    object: bool,
+    expect: Expect,
+    /// Whether the last separator read was a comma
+    after_comma: bool,
+}
+
+/// Token being read when the input ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Open {
+    None,
+    String,
+    Literal,
+}
+
+/// Scanner state over one JSON value
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Scanner {
+    out: String,
+    stack: Vec<Container>,
+    repairs: Vec<JsonRepair>,
+    /// Quote of the string being read, if any
+    quote: Option<char>,
+    escaped: bool,
+    /// Literal or number being read
+    literal: String,
+    /// Unquoted key being read
+    bare_key: Option<String>,
+    /// Whether a complete top-level value was read
+    done: bool,
+    started: bool,
+    /// Number of members or elements of the top-level container read completely
+    completed: usize,
+    /// Start in `out` of the top-level key being read
+    key_start: Option<usize>,
+    /// Last top-level key read
+    last_key: Option<String>,
+}
+
+impl Scanner {
+    fn note(&mut self, repair: JsonRepair) {
+        if !self.repairs.contains(&repair) {
+            self.repairs.push(repair);
+        }
+    }
+
+    /// Marks the start of a value in the current container
+    fn begin_value(&mut self) {
+        self.started = true;
+        if let Some(top) = self.stack.last_mut() {
+            top.expect = Expect::Next;
+            top.after_comma = false;
+        }
+    }
+
+    /// Marks the end of a value; completes the scan at top level
+    fn end_value(&mut self) {
+        match self.stack.len() {
+            0 => self.done = true,
+            1 => self.completed += 1,
+            _ => {}
+        }
+    }
+
+    /// Whether a complete top-level value was read
+    pub(crate) fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Number of members or elements of the top-level container read completely
+    pub(crate) fn completed(&self) -> usize {
+        self.completed
+    }
+
+    /// Last key read in the top-level object
+    pub(crate) fn last_key(&self) -> Option<&str> {
+        self.last_key.as_deref()
+    }
+
+    /// Records a key read in the top-level object
+    fn set_key(&mut self, key: String) {
+        if self.stack.len() == 1 {
+            self.last_key = Some(key);
+        }
+    }
+
+    /// Feeds one character; returns false once the value is complete and `c` is not part of it
+    pub(crate) fn push(&mut self, c: char) -> bool {
+        if self.done {
+            return false;
+        }
+        if let Some(quote) = self.quote {
+            self.push_string_char(quote, c);
+            return true;
+        }
+        if let Some(key) = &mut self.bare_key {
+            if c.is_alphanumeric() || c == '_' || c == '$' || c == '-' {
+                key.push(c);
+                return true;
+            }
+            let key = self.bare_key.take().unwrap_or_default();
+            self.out
+                .push_str(&serde_json::to_string(&key).unwrap_or_default());
+            self.note(JsonRepair::UnquotedKeys);
+            self.set_key(key);
+        }
+        if !self.literal.is_empty() {
+            if c.is_alphanumeric() || matches!(c, '+' | '-' | '.') {
+                self.literal.push(c);
+                return true;
+            }
+            self.finish_literal();
+            if self.done {
+                return false;
+            }
+        }
+
+        let expect = self.stack.last().map(|t| t.expect);
+        match c {
+            c if c.is_whitespace() => {
+                if self.started {
+                    self.out.push(c);
+                }
+            }
+            '{' | '[' => {
+                self.begin_value();
+                self.stack.push(Container {
+                    object: c == '{',
+                    expect: if c == '{' { Expect::Key } else { Expect::Value },
+                    after_comma: false,
+                });
+                self.out.push(c);
+            }
+            '}' | ']' => {
+                let Some(top) = self.stack.pop() else {
+                    return false;
+                };
+                if top.after_comma {
+                    self.remove_trailing_comma();
+                }
+                self.out.push(if top.object { '}' } else { ']' });
+                self.end_value();
+            }
+            ',' => {
+                if let Some(top) = self.stack.last_mut() {
+                    top.expect = if top.object {
+                        Expect::Key
+                    } else {
+                        Expect::Value
+                    };
+                    top.after_comma = true;
+                }
+                self.out.push(',');
+            }
+            ':' => {
+                if let Some(top) = self.stack.last_mut() {
+                    top.expect = Expect::Value;
+                }
+                self.out.push(':');
+            }
+            '"' | '\'' => {
+                if c == '\'' {
+                    self.note(JsonRepair::SingleQuotes);
+                }
+                match expect {
+                    Some(Expect::Key) => {
+                        if let Some(top) = self.stack.last_mut() {
+                            top.expect = Expect::Colon;
+                            top.after_comma = false;
+                        }
+                        self.key_start = Some(self.out.len());
+                    }
+                    _ => self.begin_value(),
+                }
+                self.quote = Some(c);
+                self.out.push('"');
+            }
+            c if expect == Some(Expect::Key) && (c.is_alphabetic() || c == '_' || c == '$') => {
+                if let Some(top) = self.stack.last_mut() {
+                    top.expect = Expect::Colon;
+                    top.after_comma = false;
+                }
+                self.bare_key = Some(c.to_string());
+            }
+            c if c.is_alphanumeric() || c == '-' || c == '+' || c == '.' => {
+                self.begin_value();
+                self.literal.push(c);
+            }
+            _ => return self.started,
+        }
+        true
+    }
+
+    fn push_string_char(&mut self, quote: char, c: char) {
+        if self.escaped {
+            self.escaped = false;
+            if c == '\'' && quote == '\'' {
+                // \' is not a valid JSON escape
+                self.out.pop();
+                self.out.push('\'');
+            } else {
+                self.out.push(c);
+            }
+            return;
+        }
+        match c {
+            '\\' => {
+                self.escaped = true;
+                self.out.push('\\');
+            }
+            c if c == quote => {
+                self.quote = None;
+                self.out.push('"');
+                if self.stack.last().is_none_or(|t| t.expect != Expect::Colon) {
+                    self.end_value();
+                } else if let Some(start) = self.key_start.take() {
+                    if let Ok(key) = serde_json::from_str(&self.out[start..]) {
+                        self.set_key(key);
+                    }
+                }
+            }
+            '"' => self.out.push_str("\\\""),
+            '\n' => {
+                self.note(JsonRepair::ControlCharacters);
+                self.out.push_str("\\n");
+            }
+            '\r' => {
+                self.note(JsonRepair::ControlCharacters);
+                self.out.push_str("\\r");
+            }
+            '\t' => {
+                self.note(JsonRepair::ControlCharacters);
+                self.out.push_str("\\t");
+            }
+            c => self.out.push(c),
+        }
+    }
+
+    fn finish_literal(&mut self) {
+        let literal = std::mem::take(&mut self.literal);
+        self.out.push_str(&literal);
+        self.end_value();
+    }
+
+    fn remove_trailing_comma(&mut self) {
+        let trimmed = self.out.trim_end().len();
+        if self.out[..trimmed].ends_with(',') {
+            self.out.truncate(trimmed - 1);
+            self.note(JsonRepair::TrailingComma);
+        }
+    }
+
+    /// Removes the separator before a dropped partial key
+    fn drop_key(&mut self) {
+        let trimmed = self.out.trim_end().len();
+        if self.out[..trimmed].ends_with(',') {
+            self.out.truncate(trimmed - 1);
+        }
+        if let Some(top) = self.stack.last_mut() {
+            top.expect = Expect::Next;
+            top.after_comma = false;
+        }
+    }
+
+    /// Closes whatever is still open and returns the JSON text with the repairs made
+    ///
+    /// Returns `None` when no value was started.
+    pub(crate) fn finish(mut self) -> Option<(String, Vec<JsonRepair>)> {
+        if !self.started && self.bare_key.is_none() {
+            return None;
+        }
+        if self.done {
+            return Some((self.out, self.repairs));
+        }
+
+        let mut truncated = false;
+        let open = if self.quote.is_some() {
+            Open::String
+        } else if !self.literal.is_empty() {
+            Open::Literal
+        } else {
+            Open::None
+        };
+        match open {
+            Open::String => {
+                truncated = true;
+                if let Some(start) = self.key_start.take() {
+                    // A partial key is dropped rather than guessed
+                    self.out.truncate(start);
+                    self.drop_key();
+                } else {
+                    if self.escaped {
+                        self.out.pop();
+                    }
+                    trim_partial_escape(&mut self.out);
+                    self.out.push('"');
+                }
+            }
+            Open::Literal => {
+                let literal = std::mem::take(&mut self.literal);
+                match complete_literal(&literal) {
+                    Some(complete) => {
+                        if complete != literal {
+                            truncated = true;
+                        }
+                        self.out.push_str(&complete);
+                    }
+                    None => {
+                        // Nothing usable was read: the value never started
+                        truncated = true;
+                        if let Some(top) = self.stack.last_mut() {
+                            top.expect = Expect::Value;
+                            top.after_comma = true;
+                        } else {
+                            return None;
+                        }
+                    }
+                }
+            }
+            Open::None => {}
+        }
+        if self.bare_key.take().is_some() {
+            self.drop_key();
+        }
+
+        while let Some(top) = self.stack.pop() {
+            truncated = true;
+            match top.expect {
+                Expect::Colon => self.out.push_str(": null"),
+                Expect::Value if top.object => self.out.push_str(" null"),
+                Expect::Key | Expect::Value if top.after_comma => self.remove_trailing_comma(),
+                _ => {}
+            }
+            self.out.push(if top.object { '}' } else { ']' });
+        }
+        if truncated {
+            self.note(JsonRepair::Truncated);
+        }
+        Some((self.out, self.repairs))
+    }
+}
+
+/// Removes a unicode escape the input ended in that is incomplete, or a high surrogate
+/// missing its low half
+fn trim_partial_escape(out: &mut String) {
+    while let Some(start) = out.rfind("\\u") {
+        let backslashes = out[..=start]
+            .bytes()
+            .rev()
+            .take_while(|&b| b == b'\\')
+            .count();
+        let digits = &out[start + 2..];
+        let high_surrogate = digits.len() == 4
+            && u16::from_str_radix(digits, 16).is_ok_and(|unit| (0xD800..0xDC00).contains(&unit));
+        // An even number of backslashes escapes the last one
+        if backslashes % 2 == 0 || (digits.len() >= 4 && !high_surrogate) {
+            return;
+        }
+        out.truncate(start);
+    }
+}
+
+/// Completes a truncated literal or number, `None` when nothing usable remains
+fn complete_literal(literal: &str) -> Option<String> {
+    for word in ["true", "false", "null"] {
+        if word.starts_with(literal) {
+            return Some(word.to_string());
+        }
+    }
+    let number = literal.trim_end_matches(['.', 'e', 'E', '+', '-']);
+    if number.is_empty() {
+        None
+    } else {
+        Some(number.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::extract_json;
+    use serde_json::{json, Value};
+
+    /// Scans `text` from its first character and returns the repaired JSON
+    fn scan(text: &str) -> (String, Vec<JsonRepair>) {
+        let mut scanner = Scanner::default();
+        for c in text.chars() {
+            if !scanner.push(c) {
+                break;
+            }
+        }
+        scanner.finish().unwrap()
+    }
+
+    #[test]
+    fn valid_json_needs_no_repair() {
+        let (json, repairs) = scan(r#"{"a": [1, 2.5, -3e2], "b": "x\"y", "c": null}"#);
+        assert_eq!(json, r#"{"a": [1, 2.5, -3e2], "b": "x\"y", "c": null}"#);
+        assert!(repairs.is_empty());
+    }
+
+    #[test]
+    fn removes_trailing_commas() {
+        let (json, repairs) = scan("{\"a\": [1, 2,], \"b\": 3, }");
+        assert_eq!(
+            serde_json::from_str::<Value>(&json).unwrap(),
+            json!({"a": [1, 2], "b": 3})
+        );
+        assert_eq!(repairs, vec![JsonRepair::TrailingComma]);
+    }
+
+    #[test]
+    fn converts_single_quotes() {
+        let (json, repairs) = scan(r#"{'name': 'it\'s "ok"'}"#);
+        assert_eq!(
+            serde_json::from_str::<Value>(&json).unwrap(),
+            json!({"name": "it's \"ok\""})
+        );
+        assert_eq!(repairs, vec![JsonRepair::SingleQuotes]);
+    }
+
+    #[test]
+    fn quotes_bare_keys() {
+        let (json, repairs) = scan("{name: \"Garfield\", $age_2: 5}");
+        assert_eq!(
+            serde_json::from_str::<Value>(&json).unwrap(),
+            json!({"name": "Garfield", "$age_2": 5})
+        );
+        assert_eq!(repairs, vec![JsonRepair::UnquotedKeys]);
+    }
+
+    #[test]
+    fn escapes_control_characters() {
+        let (json, repairs) = scan("{\"text\": \"line one\n\tline two\r\"}");
+        assert_eq!(
+            serde_json::from_str::<Value>(&json).unwrap(),
+            json!({"text": "line one\n\tline two\r"})
+        );
+        assert_eq!(repairs, vec![JsonRepair::ControlCharacters]);
+    }
+
+    #[test]
+    fn closes_truncated_values() {
+        let cases = [
+            (r#"{"a": "unfinished"#, json!({"a": "unfinished"})),
+            (r#"{"a": [1, 2"#, json!({"a": [1, 2]})),
+            (r#"{"a": 1, "b"#, json!({"a": 1})),
+            (r#"{"a": 1, "b":"#, json!({"a": 1, "b": null})),
+            (r#"{"a": 1, "b""#, json!({"a": 1, "b": null})),
+            (r#"{"a": tr"#, json!({"a": true})),
+            (r#"{"a": 1.5e"#, json!({"a": 1.5})),
+            (r#"[1, -"#, json!([1])),
+            (r#"{"a": "x\"#, json!({"a": "x"})),
+            (r#"{"a": "x\u00"#, json!({"a": "x"})),
+            (r#"{"a": "x\\u00"#, json!({"a": "x\\u00"})),
+            (r#"{"a": "x\ud83d\ude"#, json!({"a": "x"})),
+            (r#"{"a": "x\ud83d\ude00"#, json!({"a": "x\u{1f600}"})),
+            (r#"{"a": {"b": [{"c": 1"#, json!({"a": {"b": [{"c": 1}]}})),
+        ];
+        for (text, expected) in cases {
+            let (json, repairs) = scan(text);
+            assert_eq!(
+                serde_json::from_str::<Value>(&json).unwrap(),
+                expected,
+                "{text}"
+            );
+            assert!(repairs.contains(&JsonRepair::Truncated), "{text}");
+        }
+    }
+
+    #[test]
+    fn complete_value_is_not_truncated() {
+        let (_, repairs) = scan("[1, 2]");
+        assert!(!repairs.contains(&JsonRepair::Truncated));
+    }
+
+    #[test]
+    fn stops_after_the_value() {
+        let mut scanner = Scanner::default();
+        let rest = "{\"a\": 1} and more";
+        let consumed = rest.chars().take_while(|&c| scanner.push(c)).count();
+        assert_eq!(consumed, 8);
+        assert!(scanner.is_done());
+        assert_eq!(scanner.finish().unwrap().0, "{\"a\": 1}");
+    }
+
+    #[test]
+    fn tracks_top_level_members() {
+        let mut scanner = Scanner::default();
+        for c in r#"{"a": {"x": 1}, "b": [2, 3], "c": "#.chars() {
+            scanner.push(c);
+        }
+        assert_eq!(scanner.completed(), 2);
+        assert_eq!(scanner.last_key(), Some("c"));
+    }
+
+    #[test]
+    fn empty_input_yields_nothing() {
+        assert!(Scanner::default().finish().is_none());
+    }
+
+    #[test]
+    fn extracts_from_fences_and_prose() {
+        let extracted = extract_json("```json\n{\"a\": 1}\n```").unwrap();
+        assert_eq!(extracted.value, json!({"a": 1}));
+        assert_eq!(extracted.repairs, vec![JsonRepair::Fence]);
+
+        let extracted = extract_json("Here it is: [1, 2]. Hope it helps!").unwrap();
+        assert_eq!(extracted.value, json!([1, 2]));
+        assert_eq!(extracted.repairs, vec![JsonRepair::SurroundingText]);
+
+        let extracted = extract_json("Sure:\n```json\n{\"a\": 1}\n```\nDone.").unwrap();
+        assert_eq!(
+            extracted.repairs,
+            vec![JsonRepair::Fence, JsonRepair::SurroundingText]
+        );
+    }
+
+    #[test]
+    fn skips_brackets_in_prose() {
+        let extracted = extract_json("Options [see below]: {\"choice\": 2}").unwrap();
+        assert_eq!(extracted.value, json!({"choice": 2}));
+        assert!(extracted.repairs.contains(&JsonRepair::SurroundingText));
+    }
+
+    #[test]
+    fn extracts_truncated_response_after_prose() {
+        let extracted = extract_json("The answer is {\"items\": [\"a\", \"b").unwrap();
+        assert_eq!(extracted.value, json!({"items": ["a", "b"]}));
+        assert!(extracted.repairs.contains(&JsonRepair::SurroundingText));
+        assert!(extracted.repairs.contains(&JsonRepair::Truncated));
+    }
+
+    #[test]
+    fn accepts_bare_scalars() {
+        let extracted = extract_json(" 42 ").unwrap();
+        assert_eq!(extracted.value, json!(42));
+        assert!(!extracted.was_repaired());
+        assert!(extract_json("no json here").is_err());
+    }
+}
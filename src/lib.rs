@@ -12,5 +12,38 @@
 //!
 //! # Architecture
 //! The crate is organized into modules that handle different aspects of LLM interactions:
-//! 
-pub use llm::*;
\ No newline at end of file
+//!
+pub use llm::*;
+
+/// Builder for configuring LLM providers, with validation and repair
+pub mod builder;
+
+/// Chain multiple LLM steps and Rust functions together for complex workflows
+pub mod chain;
+
+/// Vector embeddings generation for text
+pub mod embedding;
+
+/// Evaluator for LLM providers
+pub mod evaluator;
+
+/// Lenient JSON extraction and repair of model output
+pub mod json;
+
+/// Model Context Protocol client and server for sharing tools
+pub mod mcp;
+
+/// Typed chat responses derived from JSON schemas
+pub mod structured;
+
+/// Text measurement shared by scorers and validators
+mod text;
+
+/// Tool registries and automatic tool execution loops
+pub mod tools;
+
+/// Response validation with repair loops
+pub mod validation;
+
+#[cfg(test)]
+mod testing;
@@ -0,0 +1,420 @@
+//! Client listing and calling the tools of an MCP server.
+
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    chat::{StructuredOutputFormat, Tool},
+    error::LLMError,
+    structured::format_tool,
+    tools::{ToolDefinition, ToolError, ToolRegistry},
+};
+
+use super::{HttpTransport, McpTransport, StdioTransport, PROTOCOL_VERSION};
+
+/// Maximum number of pages of tools listed from a server
+pub const MAX_TOOL_PAGES: usize = 100;
+
+/// Name and version of an MCP server, as reported during initialization
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct McpServerInfo {
+    /// Server name
+    pub name: String,
+    /// Server version
+    #[serde(default)]
+    pub version: String,
+}
+
+/// Tool offered by an MCP server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct McpTool {
+    /// Tool name
+    pub name: String,
+    /// Description shown to the model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// JSON schema of the arguments
+    #[serde(rename = "inputSchema")]
+    pub input_schema: Value,
+}
+
+impl McpTool {
+    /// Function tool definition sent to the model
+    ///
+    /// Tools without a description are described by their name. An input schema without
+    /// `type`, such as `{}`, stands for an object with any members; schemas of other types
+    /// are rejected, as tool arguments are objects.
+    pub fn to_tool(&self) -> Result<Tool, LLMError> {
+        let schema = match &self.input_schema {
+            Value::Object(members) if members.get("type").is_none_or(|t| t == "object") => {
+                let mut members = members.clone();
+                members.insert("type".to_string(), json!("object"));
+                Value::Object(members)
+            }
+            Value::Bool(true) => json!({ "type": "object" }),
+            schema => {
+                return Err(LLMError::ProviderError(format!(
+                    "Input schema of MCP tool '{}' is not an object schema: {}",
+                    self.name, schema
+                )))
+            }
+        };
+        let format = StructuredOutputFormat {
+            name: self.name.clone(),
+            description: None,
+            schema: Some(schema),
+            strict: None,
+        };
+        let (mut tool, _) = format_tool(&format);
+        tool.function.description = self.description.clone().unwrap_or(self.name.clone());
+        Ok(tool)
+    }
+}
+
+/// Connection to an MCP server, exposing its tools as function tools
+///
+/// Connecting performs the MCP initialization handshake. The tools of the server can then be
+/// listed and called directly, or added to a [`ToolRegistry`] with [`McpClient::register`],
+/// which routes the calls made by the model during a [`ToolRunner`](crate::tools::ToolRunner)
+/// run to the server.
+pub struct McpClient {
+    transport: Box<dyn McpTransport>,
+    next_id: AtomicU64,
+    server: McpServerInfo,
+    instructions: Option<String>,
+}
+
+impl McpClient {
+    /// Connects through a transport and initializes the session
+    pub async fn connect(transport: impl McpTransport + 'static) -> Result<Self, LLMError> {
+        let mut client = Self {
+            transport: Box::new(transport),
+            next_id: AtomicU64::new(1),
+            server: McpServerInfo::default(),
+            instructions: None,
+        };
+
+        let result = client
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": { "name": "rllm", "version": env!("CARGO_PKG_VERSION") },
+                }),
+            )
+            .await?;
+        client.server = serde_json::from_value(result["serverInfo"].clone()).unwrap_or_default();
+        client.instructions = result["instructions"].as_str().map(str::to_string);
+
+        client
+            .transport
+            .notify(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+            .await?;
+        Ok(client)
+    }
+
+    /// Starts a server process and connects to it over its standard streams
+    pub async fn stdio<I, S>(program: &str, args: I) -> Result<Self, LLMError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<std::ffi::OsStr>,
+    {
+        Self::connect(StdioTransport::new(program, args)?).await
+    }
+
+    /// Connects to a server over streamable HTTP
+    pub async fn http(url: impl Into<String>) -> Result<Self, LLMError> {
+        Self::connect(HttpTransport::new(url)).await
+    }
+
+    /// Name and version of the server
+    pub fn server_info(&self) -> &McpServerInfo {
+        &self.server
+    }
+
+    /// Usage instructions given by the server, if any
+    pub fn instructions(&self) -> Option<&str> {
+        self.instructions.as_deref()
+    }
+
+    /// Sends a JSON-RPC request and returns its result
+    pub async fn request(&self, method: &str, params: Value) -> Result<Value, LLMError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let response = self
+            .transport
+            .request(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))
+            .await?;
+
+        if let Some(error) = response.get("error") {
+            return Err(LLMError::ProviderError(format!(
+                "MCP error {}: {}",
+                error["code"],
+                error["message"].as_str().unwrap_or_default()
+            )));
+        }
+        response
+            .get("result")
+            .cloned()
+            .ok_or_else(|| LLMError::ResponseFormatError {
+                message: format!("MCP response to {} has no result", method),
+                raw_response: response.to_string(),
+            })
+    }
+
+    /// Lists the tools of the server, following pagination
+    ///
+    /// Listing stops at a cursor already followed, or after [`MAX_TOOL_PAGES`] pages, so a
+    /// misbehaving server cannot make it loop forever.
+    pub async fn list_tools(&self) -> Result<Vec<McpTool>, LLMError> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        let mut followed = HashSet::new();
+        for _ in 0..MAX_TOOL_PAGES {
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let result = self.request("tools/list", params).await?;
+            tools.extend(serde_json::from_value::<Vec<McpTool>>(
+                result["tools"].clone(),
+            )?);
+            match result["nextCursor"].as_str() {
+                Some(next) if followed.insert(next.to_string()) => cursor = Some(next.to_string()),
+                _ => break,
+            }
+        }
+        Ok(tools)
+    }
+
+    /// Calls a tool of the server
+    ///
+    /// The result is the structured content of the call when the server gives one, else its
+    /// text content, parsed as JSON when possible. Calls the server reports as failed
+    /// become a [`ToolError`] carrying their text.
+    pub async fn call_tool(&self, name: &str, arguments: Value) -> Result<Value, ToolError> {
+        let result = self
+            .request(
+                "tools/call",
+                json!({ "name": name, "arguments": arguments }),
+            )
+            .await?;
+        let content = content_value(&result["content"]);
+
+        if result["isError"].as_bool() == Some(true) {
+            let message = match &content {
+                Value::String(text) if !text.is_empty() => text.clone(),
+                _ => format!("Tool {} failed", name),
+            };
+            let error = ToolError::new(message);
+            return Err(match result.get("structuredContent") {
+                Some(details) => error.details(details.clone()),
+                None => error,
+            });
+        }
+        Ok(result.get("structuredContent").cloned().unwrap_or(content))
+    }
+
+    /// Definitions of the server's tools, with handlers calling the server
+    ///
+    /// Fails when a tool has an input schema that is not an object schema.
+    pub async fn tool_definitions(self: &Arc<Self>) -> Result<Vec<ToolDefinition>, LLMError> {
+        self.list_tools()
+            .await?
+            .into_iter()
+            .map(|tool| {
+                let (client, name) = (self.clone(), tool.name.clone());
+                Ok(ToolDefinition::new(tool.to_tool()?, move |arguments| {
+                    let (client, name) = (client.clone(), name.clone());
+                    async move { client.call_tool(&name, arguments).await }
+                }))
+            })
+            .collect()
+    }
+
+    /// Adds the server's tools to a registry
+    pub async fn register(
+        self: &Arc<Self>,
+        registry: ToolRegistry,
+    ) -> Result<ToolRegistry, LLMError> {
+        Ok(self
+            .tool_definitions()
+            .await?
+            .into_iter()
+            .fold(registry, ToolRegistry::register))
+    }
+}
+
+impl std::fmt::Debug for McpClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("McpClient")
+            .field("server", &self.server)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Value of the content blocks of a call result
+///
+/// Text blocks are joined, and parsed as JSON when they hold a JSON value; other blocks,
+/// such as images, are kept as they are.
+fn content_value(content: &Value) -> Value {
+    let Some(blocks) = content.as_array() else {
+        return Value::Null;
+    };
+    let texts: Option<Vec<&str>> = blocks
+        .iter()
+        .map(|block| match block["type"].as_str() {
+            Some("text") => block["text"].as_str(),
+            _ => None,
+        })
+        .collect();
+    match texts {
+        Some(texts) => {
+            let text = texts.join("\n");
+            serde_json::from_str(&text).unwrap_or(Value::String(text))
+        }
+        None => content.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use super::*;
+
+    /// Transport answering tools/list with pages linked by `cursors`
+    struct Pages {
+        cursors: Vec<Option<&'static str>>,
+        requests: std::sync::Mutex<Vec<Value>>,
+    }
+
+    #[async_trait]
+    impl McpTransport for Pages {
+        async fn request(&self, request: Value) -> Result<Value, LLMError> {
+            self.requests.lock().unwrap().push(request.clone());
+            let result = match request["method"].as_str() {
+                Some("tools/list") => {
+                    let page = match request["params"]["cursor"].as_str() {
+                        Some(cursor) => cursor[1..].parse().unwrap(),
+                        None => 0,
+                    };
+                    let mut result = json!({ "tools": [{
+                        "name": format!("tool_{}", page),
+                        "inputSchema": { "type": "object" },
+                    }]});
+                    if let Some(next) = self.cursors[page] {
+                        result["nextCursor"] = json!(next);
+                    }
+                    result
+                }
+                _ => json!({ "serverInfo": { "name": "pages" }, "instructions": "Paged" }),
+            };
+            Ok(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+        }
+
+        async fn notify(&self, _notification: Value) -> Result<(), LLMError> {
+            Ok(())
+        }
+    }
+
+    async fn client(cursors: Vec<Option<&'static str>>) -> McpClient {
+        McpClient::connect(Pages {
+            cursors,
+            requests: Default::default(),
+        })
+        .await
+        .unwrap()
+    }
+
+    fn names(tools: &[McpTool]) -> Vec<&str> {
+        tools.iter().map(|t| t.name.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn list_tools_follows_pages() {
+        let client = client(vec![Some("p1"), Some("p2"), None]).await;
+        assert_eq!(client.server_info().name, "pages");
+        assert_eq!(client.instructions(), Some("Paged"));
+
+        let tools = client.list_tools().await.unwrap();
+        assert_eq!(names(&tools), vec!["tool_0", "tool_1", "tool_2"]);
+    }
+
+    #[tokio::test]
+    async fn list_tools_stops_at_a_repeated_cursor() {
+        let client = client(vec![Some("p1"), Some("p2"), Some("p1")]).await;
+        let tools = client.list_tools().await.unwrap();
+        assert_eq!(names(&tools), vec!["tool_0", "tool_1", "tool_2"]);
+    }
+
+    #[test]
+    fn content_value_parses_text_blocks() {
+        let text = |text: &str| json!({ "type": "text", "text": text });
+        assert_eq!(
+            content_value(&json!([text("{\"a\":"), text("1}")])),
+            json!({ "a": 1 })
+        );
+        assert_eq!(content_value(&json!([text("plain")])), json!("plain"));
+
+        let image = json!([{ "type": "image", "data": "AAAA", "mimeType": "image/png" }]);
+        assert_eq!(content_value(&image), image);
+        assert_eq!(content_value(&Value::Null), Value::Null);
+    }
+
+    #[test]
+    fn mcp_tools_become_function_tools() {
+        let tool: McpTool = serde_json::from_value(json!({
+            "name": "echo",
+            "description": "Repeat a text",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "text": { "type": "string" } },
+                "required": ["text"],
+            },
+        }))
+        .unwrap();
+
+        let tool = tool.to_tool().unwrap();
+        assert_eq!(tool.function.name, "echo");
+        assert_eq!(tool.function.description, "Repeat a text");
+        assert_eq!(tool.function.parameters.required, vec!["text"]);
+        assert_eq!(
+            tool.function.parameters.properties["text"].property_type,
+            "string"
+        );
+    }
+
+    #[test]
+    fn mcp_tools_without_description_or_type_are_objects() {
+        let tool: McpTool = serde_json::from_value(json!({
+            "name": "ping",
+            "inputSchema": {},
+        }))
+        .unwrap();
+
+        let tool = tool.to_tool().unwrap();
+        assert_eq!(tool.function.description, "ping");
+        assert_eq!(tool.function.parameters.schema_type, "object");
+        assert!(tool.function.parameters.properties.is_empty());
+    }
+
+    #[test]
+    fn mcp_tools_with_non_object_schemas_are_rejected() {
+        let tool: McpTool = serde_json::from_value(json!({
+            "name": "sum",
+            "inputSchema": { "type": "array", "items": { "type": "number" } },
+        }))
+        .unwrap();
+
+        assert!(matches!(tool.to_tool(), Err(LLMError::ProviderError(_))));
+    }
+}
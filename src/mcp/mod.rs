@@ -0,0 +1,45 @@
+//! Model Context Protocol client and server.
+//!
+//! [`McpClient`] connects to an MCP server, either a child process talking over its
+//! standard streams ([`StdioTransport`]) or a remote server over streamable HTTP
+//! ([`HttpTransport`]). The tools listed by the server become function tools usable with
+//! any backend supporting function calling, and their calls are routed to the server.
+//!
+//! In the other direction, [`McpServer`] offers the tools of a registry to MCP clients such
+//! as IDE agents, and [`RegistryTools`] turns the providers and chains of an
+//! [`LLMRegistry`](crate::chain::LLMRegistry) into such tools.
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use rllm::builder::{LLMBackend, LLMBuilder};
+//! use rllm::chat::ChatMessage;
+//! use rllm::mcp::McpClient;
+//! use rllm::tools::{ToolRegistry, ToolRunner};
+//!
+//! # async fn run() -> Result<(), rllm::error::LLMError> {
+//! let llm = LLMBuilder::new().backend(LLMBackend::OpenAI).build()?;
+//! let server = ["-y", "@modelcontextprotocol/server-filesystem", "."];
+//! let files = Arc::new(McpClient::stdio("npx", server).await?);
+//! let registry = files.register(ToolRegistry::new()).await?;
+//!
+//! let run = ToolRunner::new(llm.as_ref(), registry)
+//!     .run(&[ChatMessage::user().content("Which files are in this folder?").build()])
+//!     .await?;
+//! println!("{}", run.answer.unwrap_or_default());
+//! # Ok(())
+//! # }
+//! ```
+
+mod client;
+mod registry;
+mod server;
+mod transport;
+
+pub use client::{McpClient, McpServerInfo, McpTool, MAX_TOOL_PAGES};
+pub use registry::RegistryTools;
+pub use server::McpServer;
+pub use transport::{HttpTransport, McpTransport, StdioTransport};
+
+/// MCP protocol version requested by the client
+pub const PROTOCOL_VERSION: &str = "2025-06-18";
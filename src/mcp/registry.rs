@@ -0,0 +1,277 @@
+//! Tools backed by the providers and chains of an LLM registry.
+
+use std::sync::Arc;
+
+use schemars::JsonSchema;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    chain::{LLMRegistry, MultiPromptChain},
+    chat::ChatMessage,
+    error::LLMError,
+    tools::{ToolDefinition, ToolError, ToolRegistry},
+};
+
+/// Arguments of a provider tool
+#[derive(Deserialize, JsonSchema)]
+struct PromptArgs {
+    /// Message sent to the model
+    prompt: String,
+}
+
+/// Builds tools calling the providers and chains of an [`LLMRegistry`]
+///
+/// A provider tool sends its `prompt` argument to the provider and returns the answer. A
+/// chain tool builds a [`MultiPromptChain`] for each call, with the call arguments in the
+/// chain memory, so that templates can use them as `{{name}}`, and returns the output of
+/// one of its steps. The resulting registry can be served over MCP with
+/// [`McpServer`](super::McpServer), or given to a [`ToolRunner`](crate::tools::ToolRunner).
+///
+/// ```no_run
+/// use rllm::builder::{LLMBackend, LLMBuilder};
+/// use rllm::chain::{LLMRegistryBuilder, MultiChainStepBuilder, MultiChainStepMode};
+/// use rllm::chain::MultiPromptChain;
+/// use rllm::mcp::{McpServer, RegistryTools};
+/// use rllm::structured::JsonSchema;
+/// use serde::{Deserialize, Serialize};
+///
+/// /// Arguments of the summarize tool
+/// #[derive(Serialize, Deserialize, JsonSchema)]
+/// struct Summarize {
+///     /// Text to summarize
+///     text: String,
+/// }
+///
+/// # async fn run() -> Result<(), rllm::error::LLMError> {
+/// let llms = LLMRegistryBuilder::new()
+///     .register("openai", LLMBuilder::new().backend(LLMBackend::OpenAI).build()?)
+///     .build();
+///
+/// let tools = RegistryTools::new(llms)
+///     .provider("openai", "Ask GPT a question")?
+///     .chain::<Summarize, _>("summarize", "Summarize a text", "summary", |llms| {
+///         MultiPromptChain::new(llms).step(
+///             MultiChainStepBuilder::new(MultiChainStepMode::Chat)
+///                 .provider_id("openai")
+///                 .id("summary")
+///                 .template("Summarize in three sentences:\n{{text}}")
+///                 .build()
+///                 .unwrap(),
+///         )
+///     })?;
+///
+/// McpServer::new("team-pipelines", "1.0.0")
+///     .tools(tools.build())
+///     .serve_stdio()
+///     .await
+/// # }
+/// ```
+pub struct RegistryTools {
+    llms: Arc<LLMRegistry>,
+    tools: ToolRegistry,
+}
+
+impl RegistryTools {
+    /// Starts from a registry, without tools
+    pub fn new(llms: LLMRegistry) -> Self {
+        Self {
+            llms: Arc::new(llms),
+            tools: ToolRegistry::new(),
+        }
+    }
+
+    /// Adds a tool named after a provider, chatting with it
+    pub fn provider(mut self, id: &str, description: &str) -> Result<Self, LLMError> {
+        if self.llms.get(id).is_none() {
+            return Err(LLMError::InvalidRequest(format!(
+                "No provider with id '{}' found in registry",
+                id
+            )));
+        }
+        let (llms, provider) = (self.llms.clone(), id.to_string());
+        self.tools = self
+            .tools
+            .register(ToolDefinition::typed::<PromptArgs, _, _>(
+                id,
+                description,
+                move |args| {
+                    let (llms, provider) = (llms.clone(), provider.clone());
+                    async move {
+                        let llm = llms.get(&provider).ok_or("Provider not found")?;
+                        let messages = [ChatMessage::user().content(args.prompt).build()];
+                        let answer = llm.chat(&messages).await?.text().unwrap_or_default();
+                        Ok(Value::String(answer))
+                    }
+                },
+            ));
+        Ok(self)
+    }
+
+    /// Adds a tool for each provider of the registry
+    pub fn providers(self) -> Self {
+        let mut ids: Vec<String> = self.llms.backends.keys().cloned().collect();
+        ids.sort();
+        ids.iter().fold(self, |tools, id| {
+            let description = format!("Send a prompt to the {} model and get its answer", id);
+            tools
+                .provider(id, &description)
+                .expect("registry providers exist")
+        })
+    }
+
+    /// Adds a tool running a chain, whose arguments are the fields of `A`
+    ///
+    /// `build` creates the chain for each call. String fields are stored in the chain
+    /// memory as they are and other fields as JSON; the tool returns the output of the
+    /// step `output`. `build` is called once here to check that the chain has that step.
+    pub fn chain<A, F>(
+        mut self,
+        name: &str,
+        description: &str,
+        output: &str,
+        build: F,
+    ) -> Result<Self, LLMError>
+    where
+        A: Serialize + DeserializeOwned + JsonSchema + Send + 'static,
+        F: Fn(&LLMRegistry) -> MultiPromptChain<'_> + Send + Sync + 'static,
+    {
+        if !build(&self.llms).has_step(output) {
+            return Err(LLMError::InvalidRequest(format!(
+                "Chain of tool '{}' has no step '{}'",
+                name, output
+            )));
+        }
+        let (llms, build, output) = (self.llms.clone(), Arc::new(build), output.to_string());
+        self.tools = self.tools.register(ToolDefinition::typed::<A, _, _>(
+            name,
+            description,
+            move |args| {
+                let (llms, build, output) = (llms.clone(), build.clone(), output.clone());
+                async move {
+                    let inputs = match serde_json::to_value(args)? {
+                        Value::Object(inputs) => inputs,
+                        _ => return Err(ToolError::new("Chain arguments must be an object")),
+                    };
+                    let chain = inputs
+                        .into_iter()
+                        .fold(build(&llms), |chain, (key, value)| match value {
+                            Value::Null => chain,
+                            Value::String(text) => chain.input(key, text),
+                            value => chain.input(key, value.to_string()),
+                        });
+                    let mut outputs = chain.run().await?;
+                    outputs.remove(&output).map(Value::String).ok_or_else(|| {
+                        ToolError::new(format!("The chain has no step '{}'", output))
+                    })
+                }
+            },
+        ));
+        Ok(self)
+    }
+
+    /// Registry of the tools added so far
+    pub fn build(self) -> ToolRegistry {
+        self.tools
+    }
+}
+
+impl std::fmt::Debug for RegistryTools {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RegistryTools")
+            .field("tools", &self.tools)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        chain::{LLMRegistryBuilder, MultiChainStepBuilder, MultiChainStepMode},
+        testing::MockLLM,
+        FunctionCall, ToolCall,
+    };
+
+    #[derive(Serialize, Deserialize, JsonSchema)]
+    struct Shout {
+        text: String,
+        times: Option<u32>,
+    }
+
+    fn llms() -> LLMRegistry {
+        LLMRegistryBuilder::new()
+            .register("echo", Box::new(MockLLM::new(["echoed"])))
+            .register("alpha", Box::new(MockLLM::new(["first"])))
+            .build()
+    }
+
+    fn shout_chain(llms: &LLMRegistry) -> MultiPromptChain<'_> {
+        MultiPromptChain::new(llms).step(
+            MultiChainStepBuilder::new(MultiChainStepMode::function(|context| {
+                let times: usize = context.get("times").map_or(1, |t| t.parse().unwrap());
+                let text = context.get("text").unwrap_or_default();
+                Ok(text.to_uppercase().repeat(times))
+            }))
+            .id("shout")
+            .build()
+            .unwrap(),
+        )
+    }
+
+    fn call(name: &str, arguments: Value) -> ToolCall {
+        ToolCall {
+            id: "call_1".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: name.to_string(),
+                arguments: arguments.to_string(),
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn chain_tools_run_the_chain_with_their_arguments() {
+        let tools = RegistryTools::new(llms())
+            .chain::<Shout, _>("shout", "Shouts", "shout", shout_chain)
+            .unwrap()
+            .build();
+
+        let once = tools.call(&call("shout", json!({ "text": "hi" }))).await;
+        assert_eq!(once.unwrap(), "HI");
+        let twice = tools
+            .call(&call("shout", json!({ "text": "hi", "times": 2 })))
+            .await;
+        assert_eq!(twice.unwrap(), "HIHI");
+    }
+
+    #[test]
+    fn chain_tools_need_the_output_step() {
+        let error = RegistryTools::new(llms())
+            .chain::<Shout, _>("shout", "Shouts", "missing", shout_chain)
+            .err()
+            .unwrap();
+        assert!(matches!(error, LLMError::InvalidRequest(message)
+            if message == "Chain of tool 'shout' has no step 'missing'"));
+    }
+
+    #[tokio::test]
+    async fn provider_tools_chat_with_the_provider() {
+        assert!(RegistryTools::new(llms()).provider("missing", "").is_err());
+
+        let tools = RegistryTools::new(llms()).providers().build();
+        let names: Vec<&str> = tools
+            .tools()
+            .iter()
+            .map(|t| t.function.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["alpha", "echo"]);
+
+        let answer = tools
+            .call(&call("echo", json!({ "prompt": "hello" })))
+            .await;
+        assert_eq!(answer.unwrap(), "echoed");
+    }
+}
@@ -0,0 +1,335 @@
+//! Server exposing a tool registry over MCP.
+
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{
+    chat::Tool,
+    error::LLMError,
+    tools::{ToolDefinition, ToolExecutor, ToolRegistry},
+    FunctionCall, ToolCall,
+};
+
+use super::{McpServerInfo, McpTool, PROTOCOL_VERSION};
+
+/// Protocol versions the server can answer with
+const SUPPORTED_VERSIONS: [&str; 3] = ["2024-11-05", "2025-03-26", PROTOCOL_VERSION];
+
+/// MCP server offering the tools of a [`ToolRegistry`]
+///
+/// Calls go through a [`ToolExecutor`], so its timeout and approval policy apply to MCP
+/// clients as they do to models. [`McpServer::serve_stdio`] serves a client that started
+/// the program as a child process; other transports, such as an HTTP endpoint, can pass
+/// each JSON-RPC message to [`McpServer::handle`].
+#[derive(Debug)]
+pub struct McpServer {
+    info: McpServerInfo,
+    instructions: Option<String>,
+    tools: ToolRegistry,
+    executor: ToolExecutor,
+}
+
+impl McpServer {
+    /// Creates a server without tools, reporting its name and version to clients
+    pub fn new(name: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            info: McpServerInfo {
+                name: name.into(),
+                version: version.into(),
+            },
+            instructions: None,
+            tools: ToolRegistry::new(),
+            executor: ToolExecutor::default(),
+        }
+    }
+
+    /// Sets usage instructions given to clients during initialization
+    pub fn instructions(mut self, instructions: impl Into<String>) -> Self {
+        self.instructions = Some(instructions.into());
+        self
+    }
+
+    /// Sets the tools offered by the server
+    pub fn tools(mut self, tools: ToolRegistry) -> Self {
+        self.tools = tools;
+        self
+    }
+
+    /// Adds a tool, replacing any tool with the same name
+    pub fn register(mut self, definition: ToolDefinition) -> Self {
+        self.tools = self.tools.register(definition);
+        self
+    }
+
+    /// Sets how tool calls are executed, e.g. with a timeout or an approval policy
+    pub fn executor(mut self, executor: ToolExecutor) -> Self {
+        self.executor = executor;
+        self
+    }
+
+    /// Handles a JSON-RPC message, returning the response to send, if any
+    ///
+    /// Notifications get no response. Failed tool calls are reported in the call result,
+    /// so the client's model can correct itself; unknown tools and methods are protocol
+    /// errors.
+    pub async fn handle(&self, message: Value) -> Option<Value> {
+        let id = message.get("id")?.clone();
+        let params = &message["params"];
+        let result = match message["method"].as_str().unwrap_or_default() {
+            "initialize" => Ok(self.initialize(params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({
+                "tools": self.tools.tools().iter().map(McpTool::from).collect::<Vec<_>>(),
+            })),
+            "tools/call" => self.call_tool(params).await,
+            method => Err((-32601, format!("Method not found: {}", method))),
+        };
+
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message },
+            }),
+        })
+    }
+
+    /// Serves a client over the standard input and output of the process
+    pub async fn serve_stdio(&self) -> Result<(), LLMError> {
+        let stdin = tokio::io::BufReader::new(tokio::io::stdin());
+        self.serve(stdin, tokio::io::stdout()).await
+    }
+
+    /// Serves a client sending JSON-RPC messages as lines, until the reader is closed
+    ///
+    /// Messages are handled one at a time, in the order they are received.
+    pub async fn serve<R, W>(&self, reader: R, mut writer: W) -> Result<(), LLMError>
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let mut lines = reader.lines();
+        while let Some(line) = lines.next_line().await.map_err(io_error)? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = match serde_json::from_str::<Value>(&line) {
+                Ok(message) => self.handle(message).await,
+                Err(e) => Some(json!({
+                    "jsonrpc": "2.0",
+                    "id": null,
+                    "error": { "code": -32700, "message": format!("Parse error: {}", e) },
+                })),
+            };
+            if let Some(response) = response {
+                let mut line = serde_json::to_vec(&response)?;
+                line.push(b'\n');
+                writer.write_all(&line).await.map_err(io_error)?;
+                writer.flush().await.map_err(io_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn initialize(&self, params: &Value) -> Value {
+        let version = params["protocolVersion"]
+            .as_str()
+            .filter(|version| SUPPORTED_VERSIONS.contains(version))
+            .unwrap_or(PROTOCOL_VERSION);
+        let mut result = json!({
+            "protocolVersion": version,
+            "capabilities": { "tools": { "listChanged": false } },
+            "serverInfo": self.info,
+        });
+        if let Some(instructions) = &self.instructions {
+            result["instructions"] = json!(instructions);
+        }
+        result
+    }
+
+    async fn call_tool(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let name = params["name"].as_str().unwrap_or_default();
+        if !self.tools.contains(name) {
+            return Err((-32602, format!("Unknown tool: {}", name)));
+        }
+        let call = ToolCall {
+            id: "mcp".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: name.to_string(),
+                arguments: match params.get("arguments") {
+                    Some(arguments) if !arguments.is_null() => arguments.to_string(),
+                    _ => String::new(),
+                },
+            },
+        };
+        let record = self.executor.execute(&self.tools, &[call]).await.remove(0);
+
+        Ok(match record.result {
+            Ok(value) => {
+                let text = match &value {
+                    Value::String(text) => text.clone(),
+                    value => value.to_string(),
+                };
+                let mut result = json!({ "content": [{ "type": "text", "text": text }] });
+                if value.is_object() {
+                    result["structuredContent"] = value;
+                }
+                result
+            }
+            Err(error) => {
+                let mut result = json!({
+                    "content": [{ "type": "text", "text": error.message }],
+                    "isError": true,
+                });
+                if let Some(details) = error.details {
+                    result["structuredContent"] = details;
+                }
+                result
+            }
+        })
+    }
+}
+
+impl From<&Tool> for McpTool {
+    fn from(tool: &Tool) -> Self {
+        Self {
+            name: tool.function.name.clone(),
+            description: Some(tool.function.description.clone()).filter(|d| !d.is_empty()),
+            input_schema: serde_json::to_value(&tool.function.parameters)
+                .unwrap_or_else(|_| json!({ "type": "object" })),
+        }
+    }
+}
+
+fn io_error(error: std::io::Error) -> LLMError {
+    LLMError::ProviderError(format!("MCP connection error: {}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::tools::ToolError;
+
+    #[derive(Deserialize, schemars::JsonSchema)]
+    struct DivideArgs {
+        a: f64,
+        b: f64,
+    }
+
+    fn server() -> McpServer {
+        McpServer::new("test", "1.0")
+            .instructions("Use divide")
+            .register(ToolDefinition::typed(
+                "divide",
+                "Divides a by b",
+                |args: DivideArgs| async move {
+                    if args.b == 0.0 {
+                        return Err(ToolError::new("Division by zero").details(json!({ "b": 0 })));
+                    }
+                    Ok(json!({ "quotient": args.a / args.b }))
+                },
+            ))
+    }
+
+    async fn request(server: &McpServer, method: &str, params: Value) -> Value {
+        server
+            .handle(json!({ "jsonrpc": "2.0", "id": 7, "method": method, "params": params }))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn initialize_negotiates_the_protocol_version() {
+        let server = server();
+        let known = request(
+            &server,
+            "initialize",
+            json!({ "protocolVersion": "2025-03-26" }),
+        )
+        .await;
+        assert_eq!(known["result"]["protocolVersion"], "2025-03-26");
+        assert_eq!(known["result"]["serverInfo"]["name"], "test");
+        assert_eq!(known["result"]["instructions"], "Use divide");
+
+        let unknown = request(
+            &server,
+            "initialize",
+            json!({ "protocolVersion": "1999-01-01" }),
+        )
+        .await;
+        assert_eq!(unknown["result"]["protocolVersion"], PROTOCOL_VERSION);
+    }
+
+    #[tokio::test]
+    async fn notifications_get_no_response() {
+        let notification = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+        assert!(server().handle(notification).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn tool_calls_report_results_and_errors() {
+        let server = server();
+        let listed = request(&server, "tools/list", json!({})).await;
+        assert_eq!(listed["result"]["tools"][0]["name"], "divide");
+
+        let divided = request(
+            &server,
+            "tools/call",
+            json!({ "name": "divide", "arguments": { "a": 1, "b": 4 } }),
+        )
+        .await;
+        assert_eq!(
+            divided["result"]["structuredContent"],
+            json!({ "quotient": 0.25 })
+        );
+        assert_eq!(
+            divided["result"]["content"][0]["text"],
+            "{\"quotient\":0.25}"
+        );
+
+        let failed = request(
+            &server,
+            "tools/call",
+            json!({ "name": "divide", "arguments": { "a": 1, "b": 0 } }),
+        )
+        .await;
+        assert_eq!(failed["result"]["isError"], true);
+        assert_eq!(failed["result"]["content"][0]["text"], "Division by zero");
+        assert_eq!(failed["result"]["structuredContent"], json!({ "b": 0 }));
+    }
+
+    #[tokio::test]
+    async fn unknown_tools_and_methods_are_protocol_errors() {
+        let server = server();
+        let tool = request(&server, "tools/call", json!({ "name": "nope" })).await;
+        assert_eq!(tool["error"]["code"], -32602);
+        let method = request(&server, "resources/list", json!({})).await;
+        assert_eq!(method["error"]["code"], -32601);
+        assert_eq!(method["id"], 7);
+    }
+
+    #[tokio::test]
+    async fn serve_answers_each_line() {
+        let input = concat!(
+            "{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"}\n",
+            "\n",
+            "not json\n",
+            "{\"jsonrpc\":\"2.0\",\"method\":\"notifications/initialized\"}\n",
+        );
+        let mut output = Vec::new();
+        server().serve(input.as_bytes(), &mut output).await.unwrap();
+
+        let lines: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["result"], json!({}));
+        assert_eq!(lines[1]["error"]["code"], -32700);
+    }
+}
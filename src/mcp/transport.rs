@@ -0,0 +1,345 @@
+//! Transports carrying JSON-RPC messages between a client and an MCP server.
+
+use std::{
+    collections::HashMap,
+    process::Stdio,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, CONTENT_TYPE};
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    process::{Child, Command},
+    sync::oneshot,
+};
+
+use crate::error::LLMError;
+
+use super::PROTOCOL_VERSION;
+
+/// Header carrying the session id of a streamable HTTP connection
+const SESSION_HEADER: &str = "mcp-session-id";
+/// Header carrying the negotiated protocol version on streamable HTTP
+const VERSION_HEADER: &str = "mcp-protocol-version";
+
+/// Channel carrying JSON-RPC messages to an MCP server
+///
+/// Transports match responses to requests by id; the client assigns the ids.
+#[async_trait]
+pub trait McpTransport: Send + Sync {
+    /// Sends a request and returns the response carrying the same id
+    async fn request(&self, request: Value) -> Result<Value, LLMError>;
+
+    /// Sends a notification, which has no response
+    async fn notify(&self, notification: Value) -> Result<(), LLMError>;
+}
+
+type Pending = Arc<Mutex<HashMap<String, oneshot::Sender<Value>>>>;
+type Writer = Arc<tokio::sync::Mutex<Box<dyn AsyncWrite + Send + Unpin>>>;
+
+/// Transport talking to an MCP server run as a child process
+///
+/// Messages are exchanged as JSON lines over the standard input and output of the process,
+/// and its standard error is inherited. Responses are read by a background task, so several
+/// requests may be in flight at once; creating the transport requires a Tokio runtime. The
+/// process is killed when the transport is dropped.
+///
+/// [`StdioTransport::streams`] exchanges the same JSON lines over other byte streams, such
+/// as a socket or an in-memory pipe to an [`McpServer`](super::McpServer).
+pub struct StdioTransport {
+    stdin: Writer,
+    pending: Pending,
+    child: Option<Child>,
+}
+
+impl StdioTransport {
+    /// Starts a server process from a program and its arguments
+    pub fn new<I, S>(program: &str, args: I) -> Result<Self, LLMError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<std::ffi::OsStr>,
+    {
+        let mut command = std::process::Command::new(program);
+        command.args(args);
+        Self::spawn(command)
+    }
+
+    /// Starts a server process from a command, e.g. to set its environment
+    pub fn spawn(command: std::process::Command) -> Result<Self, LLMError> {
+        let program = command.get_program().to_string_lossy().into_owned();
+        let mut child = Command::from(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| {
+                LLMError::InvalidRequest(format!("Cannot start MCP server {}: {}", program, e))
+            })?;
+
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(LLMError::InvalidRequest(format!(
+                "Cannot open the standard streams of MCP server {}",
+                program
+            )));
+        };
+        Ok(Self::start(stdout, stdin, Some(child)))
+    }
+
+    /// Talks to a server reading the lines written to `writer` and answering on `reader`
+    pub fn streams<R, W>(reader: R, writer: W) -> Self
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+        W: AsyncWrite + Send + Unpin + 'static,
+    {
+        Self::start(reader, writer, None)
+    }
+
+    fn start<R, W>(reader: R, writer: W, child: Option<Child>) -> Self
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+        W: AsyncWrite + Send + Unpin + 'static,
+    {
+        let stdin: Writer = Arc::new(tokio::sync::Mutex::new(Box::new(writer)));
+        let pending: Pending = Default::default();
+
+        let (writer, responses) = (stdin.clone(), pending.clone());
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(reader).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let Ok(message) = serde_json::from_str::<Value>(&line) else {
+                    continue;
+                };
+                match (message.get("id"), message.get("method")) {
+                    // Requests from the server: only pings are supported
+                    (Some(id), Some(method)) => {
+                        let reply = if method == "ping" {
+                            json!({ "jsonrpc": "2.0", "id": id, "result": {} })
+                        } else {
+                            json!({
+                                "jsonrpc": "2.0",
+                                "id": id,
+                                "error": { "code": -32601, "message": "Method not found" },
+                            })
+                        };
+                        let _ = write_line(&mut *writer.lock().await, &reply).await;
+                    }
+                    (Some(id), None) => {
+                        let sender = responses.lock().unwrap().remove(&id.to_string());
+                        if let Some(sender) = sender {
+                            let _ = sender.send(message);
+                        }
+                    }
+                    // Notifications from the server are ignored
+                    (None, _) => {}
+                }
+            }
+            // Dropping the senders fails the requests still waiting
+            responses.lock().unwrap().clear();
+        });
+
+        Self {
+            stdin,
+            pending,
+            child,
+        }
+    }
+}
+
+#[async_trait]
+impl McpTransport for StdioTransport {
+    async fn request(&self, request: Value) -> Result<Value, LLMError> {
+        let id = request["id"].to_string();
+        let (sender, receiver) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id.clone(), sender);
+
+        if let Err(e) = write_line(&mut *self.stdin.lock().await, &request).await {
+            self.pending.lock().unwrap().remove(&id);
+            return Err(e);
+        }
+        receiver
+            .await
+            .map_err(|_| LLMError::ProviderError("MCP server closed the connection".to_string()))
+    }
+
+    async fn notify(&self, notification: Value) -> Result<(), LLMError> {
+        write_line(&mut *self.stdin.lock().await, &notification).await
+    }
+}
+
+impl std::fmt::Debug for StdioTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StdioTransport")
+            .field("process", &self.child.as_ref().and_then(Child::id))
+            .finish_non_exhaustive()
+    }
+}
+
+async fn write_line(
+    stdin: &mut (dyn AsyncWrite + Send + Unpin),
+    message: &Value,
+) -> Result<(), LLMError> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    stdin
+        .write_all(&line)
+        .await
+        .and(stdin.flush().await)
+        .map_err(|e| LLMError::ProviderError(format!("Cannot write to MCP server: {}", e)))
+}
+
+/// Transport talking to an MCP server over streamable HTTP
+///
+/// Each message is POSTed to the server URL. Servers answer with a JSON response or with
+/// an event stream ending after the response, which is read until then. The session id
+/// assigned by the server during initialization is sent with the following messages.
+#[derive(Debug)]
+pub struct HttpTransport {
+    client: reqwest::Client,
+    url: String,
+    headers: HeaderMap,
+    session: Mutex<Option<String>>,
+    version: Mutex<Option<String>>,
+}
+
+impl HttpTransport {
+    /// Creates a transport posting to the MCP endpoint of a server
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.into(),
+            headers: HeaderMap::new(),
+            session: Mutex::new(None),
+            version: Mutex::new(None),
+        }
+    }
+
+    /// Adds a header sent with every message, e.g. `Authorization`
+    pub fn header(mut self, name: &str, value: &str) -> Result<Self, LLMError> {
+        let name = HeaderName::try_from(name)
+            .map_err(|e| LLMError::InvalidRequest(format!("Invalid header name: {}", e)))?;
+        let value = HeaderValue::try_from(value)
+            .map_err(|e| LLMError::InvalidRequest(format!("Invalid header value: {}", e)))?;
+        self.headers.insert(name, value);
+        Ok(self)
+    }
+
+    async fn post(&self, message: &Value) -> Result<reqwest::Response, LLMError> {
+        let mut request = self
+            .client
+            .post(&self.url)
+            .headers(self.headers.clone())
+            .header(ACCEPT, "application/json, text/event-stream")
+            .json(message);
+        if let Some(session) = self.session.lock().unwrap().clone() {
+            request = request.header(SESSION_HEADER, session);
+        }
+        let version = self.version.lock().unwrap().clone();
+        request = request.header(
+            VERSION_HEADER,
+            version.unwrap_or_else(|| PROTOCOL_VERSION.to_string()),
+        );
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(LLMError::HttpError(format!(
+                "MCP server returned {}: {}",
+                status, body
+            )));
+        }
+        if let Some(session) = response
+            .headers()
+            .get(SESSION_HEADER)
+            .and_then(|v| v.to_str().ok())
+        {
+            *self.session.lock().unwrap() = Some(session.to_string());
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl McpTransport for HttpTransport {
+    async fn request(&self, request: Value) -> Result<Value, LLMError> {
+        let response = self.post(&request).await?;
+        let is_stream = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+        let body = response.text().await?;
+
+        let response = if is_stream {
+            event_data(&body)
+                .into_iter()
+                .filter_map(|data| serde_json::from_str::<Value>(&data).ok())
+                .find(|message| message.get("id") == request.get("id"))
+                .ok_or_else(|| LLMError::ResponseFormatError {
+                    message: "MCP event stream ended without a response".to_string(),
+                    raw_response: body.clone(),
+                })?
+        } else {
+            serde_json::from_str(&body)?
+        };
+
+        if let Some(version) = response["result"]["protocolVersion"].as_str() {
+            *self.version.lock().unwrap() = Some(version.to_string());
+        }
+        Ok(response)
+    }
+
+    async fn notify(&self, notification: Value) -> Result<(), LLMError> {
+        self.post(&notification).await.map(|_| ())
+    }
+}
+
+/// Data of the events of a server-sent event stream
+fn event_data(body: &str) -> Vec<String> {
+    body.replace("\r\n", "\n")
+        .split("\n\n")
+        .filter_map(|event| {
+            let data: Vec<&str> = event
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .map(|data| data.strip_prefix(' ').unwrap_or(data))
+                .collect();
+            (!data.is_empty()).then(|| data.join("\n"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_data_joins_lines_of_each_event() {
+        let body =
+            ": comment\r\nevent: message\r\ndata: {\"id\":\r\ndata:1}\r\n\r\ndata: second\n\n";
+        assert_eq!(event_data(body), vec!["{\"id\":\n1}", "second"]);
+        assert!(event_data("event: ping\n\n").is_empty());
+    }
+
+    #[tokio::test]
+    async fn streams_answer_server_pings() {
+        let (client, server) = tokio::io::duplex(1024);
+        let (client_reader, client_writer) = tokio::io::split(client);
+        let _transport = StdioTransport::streams(client_reader, client_writer);
+
+        let (server_reader, mut server_writer) = tokio::io::split(server);
+        let ping = json!({ "jsonrpc": "2.0", "id": "srv-1", "method": "ping" });
+        write_line(&mut server_writer, &ping).await.unwrap();
+
+        let mut lines = BufReader::new(server_reader).lines();
+        let reply: Value =
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(
+            reply,
+            json!({ "jsonrpc": "2.0", "id": "srv-1", "result": {} })
+        );
+    }
+}
@@ -0,0 +1,455 @@
+//! Typed chat responses derived from JSON schemas.
+//!
+//! [`TypedChat::chat_typed`] derives a JSON schema from a Rust type implementing
+//! [`JsonSchema`], asks the model for a matching JSON value and deserializes the response.
+//! Any provider gets the schema in the prompt; responses are extracted leniently, checked
+//! against the schema and repaired following the validation repair loop. A
+//! [`StructuredLLM`], built with [`LLMBuilder::build_structured`], requests the schema
+//! natively from backends supporting structured output (OpenAI, Azure OpenAI, Google,
+//! Ollama and XAI) and emulates it on Anthropic, by forcing a call to a tool taking the
+//! schema as input.
+//!
+//! Deriving [`JsonSchema`] requires the `schemars` crate as a dependency, or the
+//! `#[schemars(crate = "rllm::structured::schemars")]` attribute.
+//!
+//! ```no_run
+//! use rllm::builder::{LLMBackend, LLMBuilder};
+//! use rllm::chat::ChatMessage;
+//! use rllm::structured::JsonSchema;
+//! use serde::Deserialize;
+//!
+//! /// A pet description
+//! #[derive(Deserialize, JsonSchema)]
+//! struct Pet {
+//!     name: String,
+//!     species: String,
+//!     age: u32,
+//! }
+//!
+//! # async fn run() -> Result<(), rllm::error::LLMError> {
+//! let llm = LLMBuilder::new()
+//!     .backend(LLMBackend::OpenAI)
+//!     .model("gpt-4o-mini")
+//!     .build_structured()?;
+//! let messages = vec![ChatMessage::user().content("Invent a pet").build()];
+//! let pet: Pet = llm.chat_typed(&messages).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`LLMBuilder::build_structured`]: crate::builder::LLMBuilder::build_structured
+
+mod tool_output;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use schemars::generate::SchemaSettings;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::{
+    builder::{LLMBackend, ProviderConfig},
+    chat::{
+        ChatMessage, ChatProvider, ChatResponse, ChatRole, MessageType, StructuredOutputFormat,
+        Tool,
+    },
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    json::{extract_json, extract_json_as},
+    stt::SpeechToTextProvider,
+    validation::{json_schema, Repair},
+    LLMProvider,
+};
+
+pub use schemars::{self, JsonSchema};
+
+pub(crate) use tool_output::{build as build_with_tool, format_tool, WRAPPED_PROPERTY};
+
+/// Number of attempts of typed chats on providers without repair settings
+const DEFAULT_ATTEMPTS: usize = 3;
+
+/// Derives the structured output format of a type
+///
+/// The schema follows draft 2020-12 with subschemas inlined, as expected by most backends.
+/// Its name is the schema name of the type and its description the type's doc comment.
+pub fn schema_for<T: JsonSchema>() -> StructuredOutputFormat {
+    let mut schema = SchemaSettings::draft2020_12()
+        .with(|s| s.inline_subschemas = true)
+        .into_generator()
+        .into_root_schema_for::<T>();
+    schema.remove("$schema");
+    let description = schema
+        .get("description")
+        .and_then(|d| d.as_str().map(str::to_string));
+
+    StructuredOutputFormat {
+        name: format_name(&T::schema_name()),
+        description,
+        schema: Some(schema.to_value()),
+        strict: None,
+    }
+}
+
+/// Replaces the characters backends reject in format and tool names
+pub(crate) fn format_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Renders the instructions asking for JSON matching a format
+pub(crate) fn schema_instructions(format: &StructuredOutputFormat) -> String {
+    let schema = format.schema.clone().unwrap_or(Value::Bool(true));
+    format!(
+        "Respond only with a JSON value conforming to the following JSON schema, \
+         without any other text:\n{}",
+        serde_json::to_string_pretty(&schema).unwrap_or_default()
+    )
+}
+
+/// Appends text to the last user message, or adds it as a new user message
+fn append_to_prompt(messages: &[ChatMessage], text: &str) -> Vec<ChatMessage> {
+    let mut messages = messages.to_vec();
+    match messages.last_mut() {
+        Some(last)
+            if matches!(last.role, ChatRole::User)
+                && matches!(last.message_type, MessageType::Text) =>
+        {
+            last.content = format!("{}\n\n{}", last.content, text);
+        }
+        _ => messages.push(ChatMessage::user().content(text).build()),
+    }
+    messages
+}
+
+/// Chats until the response holds a value of type `T` matching the format
+///
+/// With `prompt`, the schema is added to the conversation, for providers not requesting it
+/// natively.
+pub(crate) async fn chat_as<L, T>(
+    llm: &L,
+    messages: &[ChatMessage],
+    format: &StructuredOutputFormat,
+    repair: &Repair,
+    prompt: bool,
+) -> Result<T, LLMError>
+where
+    L: ChatProvider + ?Sized,
+    T: DeserializeOwned,
+{
+    let schema = format.schema.clone().unwrap_or(Value::Bool(true));
+    let conforms = json_schema(&schema)?;
+    let validate = |response: &str| {
+        let extracted =
+            extract_json(response).map_err(|_| "No JSON value found in response".to_string())?;
+        conforms(&extracted.json)?;
+        serde_json::from_value::<T>(extracted.value)
+            .map(|_| ())
+            .map_err(|e| format!("Response does not match the expected type: {}", e))
+    };
+
+    let messages = if prompt {
+        append_to_prompt(messages, &schema_instructions(format))
+    } else {
+        messages.to_vec()
+    };
+    let text = repair
+        .chat(llm, &messages, None, validate)
+        .await?
+        .text()
+        .unwrap_or_default();
+    extract_json_as(&text)
+}
+
+/// Chat returning values deserialized from the response
+#[async_trait]
+pub trait TypedChat {
+    /// Asks for a value of type `T` and deserializes it from the response
+    ///
+    /// The schema of `T` is added to the last user message. Responses that hold no JSON
+    /// value, or one not matching the schema, are repaired up to 3 attempts.
+    async fn chat_typed<T>(&self, messages: &[ChatMessage]) -> Result<T, LLMError>
+    where
+        T: DeserializeOwned + JsonSchema + Send;
+}
+
+#[async_trait]
+impl<P: ChatProvider + ?Sized> TypedChat for P {
+    async fn chat_typed<T>(&self, messages: &[ChatMessage]) -> Result<T, LLMError>
+    where
+        T: DeserializeOwned + JsonSchema + Send,
+    {
+        let format = schema_for::<T>();
+        chat_as(
+            self,
+            messages,
+            &format,
+            &Repair::new(DEFAULT_ATTEMPTS),
+            true,
+        )
+        .await
+    }
+}
+
+/// Whether a backend supports structured output, natively or by tool call emulation
+fn supports_native_schema(backend: &LLMBackend) -> bool {
+    matches!(
+        backend,
+        LLMBackend::Anthropic
+            | LLMBackend::OpenAI
+            | LLMBackend::AzureOpenAI
+            | LLMBackend::Google
+            | LLMBackend::Ollama
+            | LLMBackend::XAI
+    )
+}
+
+/// A provider answering typed chats, with native structured output where supported
+///
+/// Built by [`LLMBuilder::build_structured`](crate::builder::LLMBuilder::build_structured).
+/// Plain requests go to the configured provider; typed chats on backends with native
+/// structured output use a provider built with the requested schema, cached per schema.
+pub struct StructuredLLM {
+    /// Configuration used to build providers per schema
+    config: ProviderConfig,
+    /// Provider built from the configuration as is
+    inner: Box<dyn LLMProvider>,
+    /// Repair loop settings of typed chats
+    repair: Repair,
+    /// Providers requesting a schema natively, by schema
+    native: Mutex<HashMap<String, Arc<dyn LLMProvider>>>,
+}
+
+impl StructuredLLM {
+    pub(crate) fn new(config: ProviderConfig, inner: Box<dyn LLMProvider>, repair: Repair) -> Self {
+        Self {
+            config,
+            inner,
+            repair,
+            native: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Provider requesting the format natively, if the backend supports it
+    fn native_provider(
+        &self,
+        format: &StructuredOutputFormat,
+    ) -> Result<Option<Arc<dyn LLMProvider>>, LLMError> {
+        if !self
+            .config
+            .backend
+            .as_ref()
+            .is_some_and(supports_native_schema)
+        {
+            return Ok(None);
+        }
+        let key = serde_json::to_string(format)?;
+        let mut native = self.native.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(provider) = native.get(&key) {
+            return Ok(Some(provider.clone()));
+        }
+
+        let mut config = self.config.clone();
+        config.json_schema = Some(format.clone());
+        let provider: Arc<dyn LLMProvider> = Arc::from(config.build()?);
+        native.insert(key, provider.clone());
+        Ok(Some(provider))
+    }
+
+    /// Asks for a value of type `T` and deserializes it from the response
+    ///
+    /// Backends with native structured output receive the schema of `T` as response
+    /// format; other backends get it in the last user message. Responses are checked
+    /// against the schema and repaired with the builder's repair settings.
+    pub async fn chat_typed<T>(&self, messages: &[ChatMessage]) -> Result<T, LLMError>
+    where
+        T: DeserializeOwned + JsonSchema + Send,
+    {
+        let format = schema_for::<T>();
+        match self.native_provider(&format)? {
+            Some(native) => chat_as(native.as_ref(), messages, &format, &self.repair, false).await,
+            None => chat_as(self.inner.as_ref(), messages, &format, &self.repair, true).await,
+        }
+    }
+}
+
+impl LLMProvider for StructuredLLM {
+    fn tools(&self) -> Option<&[Tool]> {
+        self.inner.tools()
+    }
+}
+
+#[async_trait]
+impl ChatProvider for StructuredLLM {
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        self.inner.chat_with_tools(messages, tools).await
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for StructuredLLM {
+    async fn complete(&self, req: &CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        self.inner.complete(req).await
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for StructuredLLM {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        self.inner.embed(input).await
+    }
+}
+
+#[async_trait]
+impl SpeechToTextProvider for StructuredLLM {
+    async fn transcribe(&self, audio: Vec<u8>) -> Result<String, LLMError> {
+        self.inner.transcribe(audio).await
+    }
+
+    async fn transcribe_file(&self, file_path: &str) -> Result<String, LLMError> {
+        self.inner.transcribe_file(file_path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockLLM;
+    use serde::Deserialize;
+
+    /// A pet description
+    #[derive(Debug, PartialEq, Deserialize, JsonSchema)]
+    struct Pet {
+        name: String,
+        age: u32,
+    }
+
+    #[derive(Deserialize, JsonSchema)]
+    #[schemars(rename = "pet list.v2")]
+    struct Pets {
+        _pets: Vec<Pet>,
+    }
+
+    fn structured(backend: Option<LLMBackend>, inner: MockLLM) -> StructuredLLM {
+        let mut config = ProviderConfig::default();
+        config.backend = backend;
+        StructuredLLM::new(config, Box::new(inner), Repair::new(2))
+    }
+
+    #[test]
+    fn schema_takes_name_and_description_from_the_type() {
+        let format = schema_for::<Pet>();
+        assert_eq!(format.name, "Pet");
+        assert_eq!(format.description.as_deref(), Some("A pet description"));
+        let schema = format.schema.unwrap();
+        assert!(schema.get("$schema").is_none());
+        assert_eq!(schema["properties"]["age"]["type"], "integer");
+
+        let format = schema_for::<Pets>();
+        assert_eq!(format.name, "pet_list_v2");
+        assert_eq!(format.description, None);
+        assert_eq!(format_name("a.b c<d>-e"), "a_b_c_d_-e");
+    }
+
+    #[test]
+    fn appends_to_the_last_user_message() {
+        let messages = vec![
+            ChatMessage::assistant().content("Hello").build(),
+            ChatMessage::user().content("Invent a pet").build(),
+        ];
+        let appended = append_to_prompt(&messages, "Use JSON");
+        assert_eq!(appended.len(), 2);
+        assert_eq!(appended[1].content, "Invent a pet\n\nUse JSON");
+    }
+
+    #[test]
+    fn adds_a_user_message_after_other_messages() {
+        let messages = vec![ChatMessage::assistant().content("Hello").build()];
+        let appended = append_to_prompt(&messages, "Use JSON");
+        assert_eq!(appended.len(), 2);
+        assert!(matches!(appended[1].role, ChatRole::User));
+        assert_eq!(appended[1].content, "Use JSON");
+
+        assert_eq!(append_to_prompt(&[], "Use JSON").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn typed_chat_sends_the_schema_in_the_prompt() {
+        let llm = MockLLM::new(["```json\n{\"name\": \"Rex\", \"age\": 3}\n```"]);
+        let messages = vec![ChatMessage::user().content("Invent a pet").build()];
+        let pet: Pet = llm.chat_typed(&messages).await.unwrap();
+        assert_eq!(
+            pet,
+            Pet {
+                name: "Rex".to_string(),
+                age: 3
+            }
+        );
+
+        let requests = llm.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        let prompt = &requests[0][0].content;
+        assert!(prompt.starts_with("Invent a pet\n\nRespond only with a JSON value"));
+        assert!(prompt.contains("\"age\""));
+    }
+
+    #[tokio::test]
+    async fn typed_chat_repairs_mismatching_responses() {
+        let llm = MockLLM::new([
+            "I cannot decide.",
+            "{\"name\": \"Rex\", \"age\": -1}",
+            "{\"name\": \"Rex\", \"age\": 3}",
+        ]);
+        let messages = vec![ChatMessage::user().content("Invent a pet").build()];
+        let pet: Pet = llm.chat_typed(&messages).await.unwrap();
+        assert_eq!(pet.age, 3);
+        assert_eq!(llm.requests.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn structured_chat_prompts_without_native_support() {
+        let llm = structured(None, MockLLM::new(["{\"name\": \"Rex\", \"age\": 3}"]));
+        assert!(llm.native_provider(&schema_for::<Pet>()).unwrap().is_none());
+
+        let messages = vec![ChatMessage::user().content("Invent a pet").build()];
+        let pet: Pet = llm.chat_typed(&messages).await.unwrap();
+        assert_eq!(pet.name, "Rex");
+    }
+
+    #[tokio::test]
+    async fn structured_chat_fails_after_the_configured_attempts() {
+        let llm = structured(None, MockLLM::new(["not json"]));
+        let messages = vec![ChatMessage::user().content("Invent a pet").build()];
+        let err = llm.chat_typed::<Pet>(&messages).await.unwrap_err();
+        let attempts = crate::validation::ValidationAttempt::from_error(&err).unwrap();
+        assert_eq!(attempts.len(), 2);
+    }
+
+    #[test]
+    fn native_providers_are_cached_per_schema() {
+        let llm = structured(Some(LLMBackend::Ollama), MockLLM::default());
+        let pet = schema_for::<Pet>();
+        let first = llm.native_provider(&pet).unwrap().unwrap();
+        let second = llm.native_provider(&pet).unwrap().unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        let other = llm.native_provider(&schema_for::<Pets>()).unwrap().unwrap();
+        assert!(!Arc::ptr_eq(&first, &other));
+        assert_eq!(llm.native.lock().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,449 @@
+//! Structured output emulated with a forced tool call, for backends without a JSON schema
+//! response format.
+//!
+//! The requested schema becomes the input schema of a single tool, and the tool choice
+//! forces the model to call it. The input of that call is returned as the response text,
+//! so callers see the same JSON text as with a native response format, and typed chats
+//! check it against the requested schema like any other response.
+//!
+//! Tool parameters are flatter than JSON schemas: only the top-level properties, their
+//! types, array items and string enums map to parameter fields. Nested objects,
+//! alternatives and constants are flattened into the parameter description, which spells
+//! out their schema for the model; the validation of typed chats enforces them.
+
+use std::{collections::HashMap, fmt};
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::{
+    builder::ProviderConfig,
+    chat::{
+        ChatMessage, ChatProvider, ChatResponse, FunctionTool, ParameterProperty, ParametersSchema,
+        StructuredOutputFormat, Tool, ToolChoice,
+    },
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    stt::SpeechToTextProvider,
+    LLMProvider, ToolCall,
+};
+
+use super::format_name;
+
+/// Property wrapping schemas whose root is not an object, as tool inputs must be objects
+pub(crate) const WRAPPED_PROPERTY: &str = "value";
+
+/// Builds a provider answering with the input of a forced call to a tool taking `format`
+///
+/// Backends reject forced tool calls while extended thinking is enabled, so with reasoning
+/// the model is only given the tool and left to call it. The structured output tool is the
+/// only tool of the provider, so configured tools are rejected.
+pub(crate) fn build(
+    config: &ProviderConfig,
+    format: &StructuredOutputFormat,
+) -> Result<Box<dyn LLMProvider>, LLMError> {
+    if !config.tools.is_empty() {
+        return Err(LLMError::InvalidRequest(
+            "Structured output through a tool call cannot be combined with other tools".to_string(),
+        ));
+    }
+    let (tool, wrapped) = format_tool(format);
+
+    let mut config = config.clone();
+    config.json_schema = None;
+    config.tool_choice = Some(if config.reasoning == Some(true) {
+        ToolChoice::Auto
+    } else {
+        ToolChoice::Tool(tool.function.name.clone())
+    });
+    config.tools = vec![tool.clone()];
+
+    Ok(Box::new(ToolOutputLLM {
+        inner: config.build()?,
+        tool,
+        wrapped,
+    }))
+}
+
+/// Converts a structured output format into a tool, and whether its schema was wrapped
+pub(crate) fn format_tool(format: &StructuredOutputFormat) -> (Tool, bool) {
+    let schema = format.schema.clone().unwrap_or(Value::Bool(true));
+    let is_object = schema.get("type").and_then(Value::as_str) == Some("object")
+        || schema.get("properties").is_some();
+
+    let parameters = if is_object {
+        let properties = schema
+            .get("properties")
+            .and_then(Value::as_object)
+            .map(|properties| {
+                properties
+                    .iter()
+                    .map(|(name, property)| (name.clone(), to_property(property)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let required = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|required| {
+                required
+                    .iter()
+                    .filter_map(|r| r.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        ParametersSchema {
+            schema_type: "object".to_string(),
+            properties,
+            required,
+        }
+    } else {
+        ParametersSchema {
+            schema_type: "object".to_string(),
+            properties: HashMap::from([(WRAPPED_PROPERTY.to_string(), to_property(&schema))]),
+            required: vec![WRAPPED_PROPERTY.to_string()],
+        }
+    };
+
+    let tool = Tool {
+        tool_type: "function".to_string(),
+        function: FunctionTool {
+            name: format_name(&format.name),
+            description: format
+                .description
+                .clone()
+                .unwrap_or_else(|| "Reports the response in a structured format".to_string()),
+            parameters,
+        },
+    };
+    (tool, !is_object)
+}
+
+/// Converts a JSON schema into a tool parameter
+///
+/// Tool parameters only describe types, array items and string enums. The structure of
+/// nested objects and alternatives is spelled out in the description.
+fn to_property(schema: &Value) -> ParameterProperty {
+    let property_type = match schema.get("type") {
+        Some(Value::String(t)) => t.clone(),
+        Some(Value::Array(types)) => types
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|t| *t != "null")
+            .unwrap_or("null")
+            .to_string(),
+        _ if schema.get("properties").is_some() => "object".to_string(),
+        _ => ["anyOf", "oneOf"]
+            .iter()
+            .filter_map(|key| schema.get(*key)?.as_array())
+            .flatten()
+            .map(|alternative| to_property(alternative).property_type)
+            .find(|t| t != "null")
+            .unwrap_or_else(|| "string".to_string()),
+    };
+    let items = match schema.get("items") {
+        Some(items) if property_type == "array" => Some(Box::new(to_property(items))),
+        _ => None,
+    };
+    let enum_list = schema
+        .get("enum")
+        .and_then(Value::as_array)
+        .and_then(|values| {
+            values
+                .iter()
+                .map(|v| v.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+        });
+
+    let mut description = schema
+        .get("description")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    // Scalar bounds are left to validation; structure the parameter cannot express is not
+    let structured = schema.as_object().is_some_and(|members| {
+        members.keys().any(|key| {
+            matches!(
+                key.as_str(),
+                "properties" | "additionalProperties" | "oneOf" | "anyOf" | "allOf" | "const"
+            )
+        })
+    });
+    if structured {
+        let mut shape = schema.clone();
+        if let Some(members) = shape.as_object_mut() {
+            members.remove("description");
+            members.remove("title");
+        }
+        if !description.is_empty() {
+            if !description.ends_with('.') {
+                description.push('.');
+            }
+            description.push(' ');
+        }
+        description.push_str(&format!("Must match the JSON schema: {}", shape));
+    }
+
+    ParameterProperty {
+        property_type,
+        description,
+        items,
+        enum_list,
+    }
+}
+
+/// Response holding the input of the structured output tool call as text
+#[derive(Debug)]
+struct ToolOutputResponse {
+    text: Option<String>,
+    thinking: Option<String>,
+}
+
+impl fmt::Display for ToolOutputResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text.as_deref().unwrap_or_default())
+    }
+}
+
+impl ChatResponse for ToolOutputResponse {
+    fn text(&self) -> Option<String> {
+        self.text.clone()
+    }
+
+    fn tool_calls(&self) -> Option<Vec<ToolCall>> {
+        None
+    }
+
+    fn thinking(&self) -> Option<String> {
+        self.thinking.clone()
+    }
+}
+
+/// A provider forced to call a single tool, answering with the input of that call
+struct ToolOutputLLM {
+    /// Provider built with the tool and the forced tool choice
+    inner: Box<dyn LLMProvider>,
+    /// Tool whose input schema is the requested schema
+    tool: Tool,
+    /// Whether the schema was wrapped in the [`WRAPPED_PROPERTY`] member
+    wrapped: bool,
+}
+
+impl ToolOutputLLM {
+    /// Extracts the tool input from a call, unwrapping it if needed
+    ///
+    /// The input is not checked here: typed chats validate it like a text response, so a
+    /// mismatch goes through their repair loop.
+    fn output(&self, call: &ToolCall) -> String {
+        if self.wrapped {
+            serde_json::from_str::<Value>(&call.function.arguments)
+                .ok()
+                .and_then(|mut input| input.get_mut(WRAPPED_PROPERTY).map(Value::take))
+                .map(|value| value.to_string())
+                .unwrap_or_else(|| call.function.arguments.clone())
+        } else {
+            call.function.arguments.clone()
+        }
+    }
+}
+
+impl LLMProvider for ToolOutputLLM {}
+
+#[async_trait]
+impl ChatProvider for ToolOutputLLM {
+    /// Sends the conversation with the structured output tool only, and returns the input
+    /// of its call, or the response text when the model did not call it.
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        _tools: Option<&[Tool]>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        let response = self
+            .inner
+            .chat_with_tools(messages, Some(std::slice::from_ref(&self.tool)))
+            .await?;
+        let call = response
+            .tool_calls()
+            .unwrap_or_default()
+            .into_iter()
+            .find(|call| call.function.name == self.tool.function.name);
+
+        Ok(Box::new(ToolOutputResponse {
+            text: match call {
+                Some(call) => Some(self.output(&call)),
+                None => response.text(),
+            },
+            thinking: response.thinking(),
+        }))
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for ToolOutputLLM {
+    async fn complete(&self, req: &CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        self.inner.complete(req).await
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for ToolOutputLLM {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        self.inner.embed(input).await
+    }
+}
+
+#[async_trait]
+impl SpeechToTextProvider for ToolOutputLLM {
+    async fn transcribe(&self, audio: Vec<u8>) -> Result<String, LLMError> {
+        self.inner.transcribe(audio).await
+    }
+
+    async fn transcribe_file(&self, file_path: &str) -> Result<String, LLMError> {
+        self.inner.transcribe_file(file_path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        structured::chat_as,
+        testing::{MockLLM, Reply},
+        validation::Repair,
+        FunctionCall,
+    };
+
+    fn format(schema: Value) -> StructuredOutputFormat {
+        StructuredOutputFormat {
+            name: "answer".to_string(),
+            description: None,
+            schema: Some(schema),
+            strict: None,
+        }
+    }
+
+    fn provider(schema: Value, inner: MockLLM) -> ToolOutputLLM {
+        let (tool, wrapped) = format_tool(&format(schema));
+        ToolOutputLLM {
+            inner: Box::new(inner),
+            tool,
+            wrapped,
+        }
+    }
+
+    fn call(arguments: &str) -> ToolCall {
+        ToolCall {
+            id: "call_1".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "answer".to_string(),
+                arguments: arguments.to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn object_schemas_become_tool_parameters() {
+        let (tool, wrapped) = format_tool(&format(json!({
+            "type": "object",
+            "properties": {
+                "tags": { "type": "array", "items": { "type": "string" } },
+                "owner": {
+                    "type": "object",
+                    "description": "Who owns it",
+                    "properties": { "name": { "type": "string" } }
+                }
+            },
+            "required": ["tags"]
+        })));
+
+        assert!(!wrapped);
+        let parameters = &tool.function.parameters;
+        assert_eq!(parameters.required, vec!["tags"]);
+        let tags = &parameters.properties["tags"];
+        assert_eq!(tags.items.as_ref().unwrap().property_type, "string");
+        let owner = &parameters.properties["owner"];
+        assert_eq!(owner.property_type, "object");
+        assert!(owner
+            .description
+            .starts_with("Who owns it. Must match the JSON schema: {"));
+    }
+
+    #[test]
+    fn other_schemas_are_wrapped() {
+        let (tool, wrapped) = format_tool(&format(json!({ "type": "array" })));
+        assert!(wrapped);
+        assert_eq!(tool.function.parameters.required, vec![WRAPPED_PROPERTY]);
+    }
+
+    #[test]
+    fn outputs_are_unwrapped() {
+        let llm = provider(
+            json!({ "type": "array", "items": { "type": "integer" } }),
+            MockLLM::default(),
+        );
+        assert_eq!(llm.output(&call(r#"{"value": [1, 2]}"#)), "[1,2]");
+        // Inputs not matching the schema are left to the caller's validation
+        assert_eq!(llm.output(&call(r#"{"value": ["one"]}"#)), r#"["one"]"#);
+        assert_eq!(llm.output(&call("not json")), "not json");
+    }
+
+    #[tokio::test]
+    async fn responds_with_the_call_input_or_the_text() {
+        let schema = json!({ "type": "object" });
+        let llm = provider(
+            schema.clone(),
+            MockLLM::new([Reply::call("answer", r#"{"a": 1}"#)]),
+        );
+        let response = llm.chat(&[]).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some(r#"{"a": 1}"#));
+        assert!(response.tool_calls().is_none());
+
+        let llm = provider(schema, MockLLM::new(["I would rather not."]));
+        let response = llm.chat(&[]).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some("I would rather not."));
+    }
+
+    #[tokio::test]
+    async fn mismatching_inputs_are_repaired() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "owner": {
+                    "type": "object",
+                    "properties": { "name": { "type": "string" } },
+                    "required": ["name"]
+                }
+            },
+            "required": ["owner"]
+        });
+        let llm = provider(
+            schema.clone(),
+            MockLLM::new([
+                Reply::call("answer", r#"{"owner": {}}"#),
+                Reply::call("answer", r#"{"owner": {"name": "Jon"}}"#),
+            ]),
+        );
+        let messages = vec![ChatMessage::user().content("Who owns Garfield?").build()];
+
+        let value: Value = chat_as(&llm, &messages, &format(schema), &Repair::new(3), false)
+            .await
+            .unwrap();
+        assert_eq!(value, json!({ "owner": { "name": "Jon" } }));
+    }
+
+    #[test]
+    fn configured_tools_are_rejected() {
+        let format = format(json!({ "type": "object" }));
+        let mut config = ProviderConfig::default();
+        config.tools = vec![format_tool(&format).0];
+
+        assert!(matches!(
+            build(&config, &format),
+            Err(LLMError::InvalidRequest(_))
+        ));
+    }
+}
@@ -0,0 +1,232 @@
+//! Test doubles shared by the unit tests of the crate.
+
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+
+use crate::{
+    chat::{ChatMessage, ChatProvider, ChatResponse, Tool},
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    stt::SpeechToTextProvider,
+    FunctionCall, LLMProvider, ToolCall,
+};
+
+/// Scripted answer of a [`MockLLM`]
+#[derive(Debug, Clone)]
+pub(crate) enum Reply {
+    Text(String),
+    /// A call to the named tool with the given JSON arguments
+    Call {
+        name: String,
+        arguments: String,
+    },
+}
+
+impl Reply {
+    pub(crate) fn call(name: &str, arguments: &str) -> Self {
+        Reply::Call {
+            name: name.to_string(),
+            arguments: arguments.to_string(),
+        }
+    }
+
+    fn text(&self) -> String {
+        match self {
+            Reply::Text(text) => text.clone(),
+            Reply::Call { arguments, .. } => arguments.clone(),
+        }
+    }
+}
+
+impl From<&str> for Reply {
+    fn from(text: &str) -> Self {
+        Reply::Text(text.to_string())
+    }
+}
+
+impl From<String> for Reply {
+    fn from(text: String) -> Self {
+        Reply::Text(text)
+    }
+}
+
+/// Provider answering with scripted responses or tool calls, the last one repeating
+///
+/// Embeddings count the letters `a` to `z` of each input, so texts sharing words are
+/// similar.
+#[derive(Default)]
+pub(crate) struct MockLLM {
+    responses: Mutex<VecDeque<Reply>>,
+    /// Conversations received, in order
+    pub(crate) requests: Mutex<Vec<Vec<ChatMessage>>>,
+    /// Number of embedding requests
+    pub(crate) embed_calls: AtomicUsize,
+    surplus_embeddings: usize,
+    error: Option<String>,
+    /// Time each chat request takes
+    delay: Option<Duration>,
+    in_flight: AtomicUsize,
+    max_in_flight: Arc<AtomicUsize>,
+}
+
+impl MockLLM {
+    pub(crate) fn new<I, S>(responses: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Reply>,
+    {
+        Self {
+            responses: Mutex::new(responses.into_iter().map(Into::into).collect()),
+            ..Self::default()
+        }
+    }
+
+    /// Provider whose chats and completions fail with `message`
+    pub(crate) fn failing(message: impl Into<String>) -> Self {
+        Self {
+            error: Some(message.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Makes each chat request take `delay`, to observe concurrent requests
+    pub(crate) fn delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Returns `count` embeddings more than requested
+    pub(crate) fn surplus_embeddings(mut self, count: usize) -> Self {
+        self.surplus_embeddings = count;
+        self
+    }
+
+    /// Largest number of chat requests in flight at once, readable after the mock is
+    /// moved into the code under test
+    pub(crate) fn max_in_flight(&self) -> Arc<AtomicUsize> {
+        self.max_in_flight.clone()
+    }
+
+    pub(crate) fn embed_calls(&self) -> usize {
+        self.embed_calls.load(Ordering::SeqCst)
+    }
+
+    fn check_error(&self) -> Result<(), LLMError> {
+        match &self.error {
+            Some(message) => Err(LLMError::ProviderError(message.clone())),
+            None => Ok(()),
+        }
+    }
+
+    fn next_reply(&self) -> Reply {
+        let mut responses = self.responses.lock().unwrap();
+        match responses.len() {
+            0 => Reply::Text(String::new()),
+            1 => responses[0].clone(),
+            _ => responses.pop_front().unwrap(),
+        }
+    }
+
+    fn next_response(&self) -> String {
+        self.next_reply().text()
+    }
+}
+
+#[derive(Debug)]
+struct MockResponse(Reply);
+
+impl fmt::Display for MockResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.text())
+    }
+}
+
+impl ChatResponse for MockResponse {
+    fn text(&self) -> Option<String> {
+        match &self.0 {
+            Reply::Text(text) => Some(text.clone()),
+            Reply::Call { .. } => None,
+        }
+    }
+
+    fn tool_calls(&self) -> Option<Vec<ToolCall>> {
+        match &self.0 {
+            Reply::Text(_) => None,
+            Reply::Call { name, arguments } => Some(vec![ToolCall {
+                id: "call_1".to_string(),
+                call_type: "function".to_string(),
+                function: FunctionCall {
+                    name: name.clone(),
+                    arguments: arguments.clone(),
+                },
+            }]),
+        }
+    }
+}
+
+impl LLMProvider for MockLLM {}
+
+#[async_trait]
+impl ChatProvider for MockLLM {
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        _tools: Option<&[Tool]>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        self.requests.lock().unwrap().push(messages.to_vec());
+        self.check_error()?;
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+        if let Some(delay) = self.delay {
+            tokio::time::sleep(delay).await;
+        }
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        Ok(Box::new(MockResponse(self.next_reply())))
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for MockLLM {
+    async fn complete(&self, _req: &CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        self.check_error()?;
+        Ok(CompletionResponse {
+            text: self.next_response(),
+        })
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for MockLLM {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        self.embed_calls.fetch_add(1, Ordering::SeqCst);
+        let padding = vec![String::new(); self.surplus_embeddings];
+        Ok(input
+            .iter()
+            .chain(&padding)
+            .map(|text| {
+                let mut counts = vec![0.0; 26];
+                for c in text.to_lowercase().chars().filter(char::is_ascii_lowercase) {
+                    counts[(c as u8 - b'a') as usize] += 1.0;
+                }
+                counts
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl SpeechToTextProvider for MockLLM {
+    async fn transcribe(&self, _audio: Vec<u8>) -> Result<String, LLMError> {
+        Ok(self.next_response())
+    }
+}
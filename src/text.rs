@@ -0,0 +1,38 @@
+//! Text measurement shared by scorers, validators and the tool runner.
+
+/// Unit in which lengths are measured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthUnit {
+    Chars,
+    Words,
+    /// Estimated tokens: the character count divided by 4, rounded up
+    ///
+    /// This approximates common tokenizers on English text only, and can be far off for
+    /// code or other languages.
+    Tokens,
+}
+
+impl LengthUnit {
+    /// Length of `text` in this unit
+    pub fn measure(&self, text: &str) -> usize {
+        match self {
+            LengthUnit::Chars => text.chars().count(),
+            LengthUnit::Words => text.split_whitespace().count(),
+            LengthUnit::Tokens => text.chars().count().div_ceil(4),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measures_lengths_in_each_unit() {
+        let text = "héllo  wide\tworld";
+        assert_eq!(LengthUnit::Chars.measure(text), 17);
+        assert_eq!(LengthUnit::Words.measure(text), 3);
+        assert_eq!(LengthUnit::Tokens.measure(text), 5);
+        assert_eq!(LengthUnit::Tokens.measure(""), 0);
+    }
+}
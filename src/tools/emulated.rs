@@ -0,0 +1,620 @@
+//! Function calling emulated through the prompt, for backends without native tool calls.
+//!
+//! The tools are described in the system prompt, with a strict JSON format for calling
+//! them. Responses in that format are validated against the tools and their argument
+//! schemas, and surfaced as [`ToolCall`]s; invalid calls are sent back to the model for
+//! repair. Tool calls and results of the conversation are rendered as text in the same
+//! format, so the model sees its previous calls as it made them.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::{
+    builder::{ProviderConfig, ValidatorFn},
+    chat::{ChatMessage, ChatProvider, ChatResponse, MessageType, Tool, ToolChoice},
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    json::extract_json,
+    stt::SpeechToTextProvider,
+    validation::{json_schema, Repair},
+    FunctionCall, LLMProvider, ToolCall,
+};
+
+/// Attempts at obtaining valid calls before giving up
+const EMULATION_ATTEMPTS: usize = 3;
+
+/// Builds a provider emulating function calling with the configured tools
+///
+/// The configured tools are prepared right away, so that invalid parameter schemas fail
+/// the build.
+pub(crate) fn build(config: &ProviderConfig) -> Result<Box<dyn LLMProvider>, LLMError> {
+    let mut base = config.clone();
+    base.emulate_tools = Some(false);
+    base.tools = Vec::new();
+    base.tool_choice = None;
+
+    let llm = PromptToolsLLM {
+        inner: base.build()?,
+        config: base,
+        tools: config.tools.clone(),
+        tool_choice: config.tool_choice.clone(),
+        prompted: Mutex::new(HashMap::new()),
+        repair: Repair::new(EMULATION_ATTEMPTS),
+        next_id: AtomicUsize::new(1),
+    };
+    if !llm.tools.is_empty() {
+        llm.prompted(&llm.tools)?;
+    }
+    Ok(Box::new(llm))
+}
+
+/// Tools with the validators of their arguments
+struct ToolSet {
+    tools: Vec<Tool>,
+    /// Validator of the parameter schema of each tool, in the order of `tools`
+    validators: Vec<Box<ValidatorFn>>,
+}
+
+impl ToolSet {
+    fn new(tools: &[Tool]) -> Result<Self, LLMError> {
+        let validators = tools
+            .iter()
+            .map(|tool| json_schema(&serde_json::to_value(&tool.function.parameters)?))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            tools: tools.to_vec(),
+            validators,
+        })
+    }
+}
+
+/// A tool set with the provider whose system prompt describes it
+struct Prompted {
+    provider: Arc<dyn LLMProvider>,
+    tools: ToolSet,
+}
+
+/// A provider calling tools through its prompt
+struct PromptToolsLLM {
+    /// Provider without tool instructions, for requests without tools
+    inner: Box<dyn LLMProvider>,
+    /// Configuration of `inner`, extended with the instructions of each tool set
+    config: ProviderConfig,
+    /// Tools configured on the builder, used when a request gives none
+    tools: Vec<Tool>,
+    tool_choice: Option<ToolChoice>,
+    /// Providers whose system prompt describes a tool set, by tool set
+    prompted: Mutex<HashMap<String, Arc<Prompted>>>,
+    repair: Repair,
+    /// Counter of the ids given to parsed calls
+    next_id: AtomicUsize,
+}
+
+impl PromptToolsLLM {
+    /// Provider whose system prompt describes the tools, with their validators
+    fn prompted(&self, tools: &[Tool]) -> Result<Arc<Prompted>, LLMError> {
+        let key = serde_json::to_string(tools)?;
+        let mut prompted = self.prompted.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = prompted.get(&key) {
+            return Ok(entry.clone());
+        }
+
+        let instructions = tool_instructions(tools, self.tool_choice.as_ref());
+        let mut config = self.config.clone();
+        config.system = Some(match &config.system {
+            Some(system) => format!("{}\n\n{}", system, instructions),
+            None => instructions,
+        });
+        let entry = Arc::new(Prompted {
+            provider: Arc::from(config.build()?),
+            tools: ToolSet::new(tools)?,
+        });
+        prompted.insert(key, entry.clone());
+        Ok(entry)
+    }
+
+    /// Converts parsed calls into tool calls with fresh ids
+    fn to_tool_calls(&self, calls: Vec<(String, Value)>) -> Vec<ToolCall> {
+        calls
+            .into_iter()
+            .map(|(name, arguments)| ToolCall {
+                id: format!("call_{}", self.next_id.fetch_add(1, Ordering::Relaxed)),
+                call_type: "function".to_string(),
+                function: FunctionCall {
+                    name,
+                    arguments: arguments.to_string(),
+                },
+            })
+            .collect()
+    }
+}
+
+/// System prompt instructions describing the tools and the call format
+fn tool_instructions(tools: &[Tool], choice: Option<&ToolChoice>) -> String {
+    let descriptions: Vec<Value> = tools
+        .iter()
+        .map(|tool| {
+            json!({
+                "name": tool.function.name,
+                "description": tool.function.description,
+                "parameters": tool.function.parameters,
+            })
+        })
+        .collect();
+    let requirement = match choice {
+        Some(ToolChoice::Any) => "\n\nYou must call at least one tool.".to_string(),
+        Some(ToolChoice::Tool(name)) => format!("\n\nYou must call the tool {}.", name),
+        _ => String::new(),
+    };
+
+    format!(
+        "You can call the following tools, each given with its name, its description and \
+         the JSON schema of its arguments:\n{}\n\n\
+         To call tools, respond only with a JSON object in this exact format, without any \
+         other text:\n\
+         {{\"tool_calls\": [{{\"name\": \"<tool name>\", \"arguments\": {{<arguments>}}}}]}}\n\
+         The arguments must match the schema of the tool. You may call several tools at once; \
+         their results will be given to you in the next message. When you do not need a tool, \
+         answer in plain text.{}",
+        serde_json::to_string_pretty(&descriptions).unwrap_or_default(),
+        requirement
+    )
+}
+
+/// Parses the calls of a response, `None` for a plain text answer
+///
+/// Calls must name one of the tools and give arguments matching its schema.
+fn parse_calls(
+    text: &str,
+    tools: &ToolSet,
+    choice: Option<&ToolChoice>,
+) -> Result<Option<Vec<(String, Value)>>, String> {
+    let requested = extract_json(text)
+        .ok()
+        .and_then(|extracted| extracted.value.get("tool_calls").cloned());
+    let Some(requested) = requested else {
+        return match choice {
+            Some(ToolChoice::Any | ToolChoice::Tool(_)) => {
+                Err("A tool call is required, in the format {\"tool_calls\": [...]}".to_string())
+            }
+            _ => Ok(None),
+        };
+    };
+    let Value::Array(requested) = requested else {
+        return Err("tool_calls must be an array of calls".to_string());
+    };
+
+    let mut calls = Vec::new();
+    for call in requested {
+        let Some(name) = call.get("name").and_then(Value::as_str) else {
+            return Err("Each tool call needs the name of the tool".to_string());
+        };
+        let Some(index) = tools.tools.iter().position(|t| t.function.name == name) else {
+            let available: Vec<&str> = tools
+                .tools
+                .iter()
+                .map(|t| t.function.name.as_str())
+                .collect();
+            return Err(format!(
+                "Unknown tool {}, available tools are: {}",
+                name,
+                available.join(", ")
+            ));
+        };
+        if let Some(ToolChoice::Tool(required)) = choice {
+            if name != required {
+                return Err(format!("Only the tool {} may be called", required));
+            }
+        }
+
+        let arguments = match call.get("arguments") {
+            None | Some(Value::Null) => json!({}),
+            Some(arguments @ Value::Object(_)) => arguments.clone(),
+            Some(_) => return Err(format!("The arguments of {} must be an object", name)),
+        };
+        (tools.validators[index])(&arguments.to_string())
+            .map_err(|e| format!("Invalid arguments for {}: {}", name, e))?;
+        calls.push((name.to_string(), arguments));
+    }
+    Ok(Some(calls))
+}
+
+/// Renders tool calls and results of a conversation as text messages
+fn render(messages: &[ChatMessage]) -> Vec<ChatMessage> {
+    let payload = |call: &ToolCall| {
+        serde_json::from_str(&call.function.arguments)
+            .unwrap_or_else(|_| Value::String(call.function.arguments.clone()))
+    };
+    messages
+        .iter()
+        .map(|message| {
+            let content = match &message.message_type {
+                MessageType::ToolUse(calls) => {
+                    let calls: Vec<Value> = calls
+                        .iter()
+                        .map(|c| json!({ "name": c.function.name, "arguments": payload(c) }))
+                        .collect();
+                    let request = json!({ "tool_calls": calls }).to_string();
+                    match message.content.trim() {
+                        "" => request,
+                        text => format!("{}\n{}", text, request),
+                    }
+                }
+                MessageType::ToolResult(results) => {
+                    let results: Vec<Value> = results
+                        .iter()
+                        .map(|r| json!({ "name": r.function.name, "result": payload(r) }))
+                        .collect();
+                    format!("Tool results:\n{}", Value::Array(results))
+                }
+                _ => return message.clone(),
+            };
+            ChatMessage {
+                role: message.role.clone(),
+                message_type: MessageType::Text,
+                content,
+            }
+        })
+        .collect()
+}
+
+/// Response whose tool calls were parsed from its text
+#[derive(Debug)]
+struct PromptToolsResponse {
+    text: Option<String>,
+    tool_calls: Option<Vec<ToolCall>>,
+    thinking: Option<String>,
+}
+
+impl fmt::Display for PromptToolsResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text.as_deref().unwrap_or_default())
+    }
+}
+
+impl ChatResponse for PromptToolsResponse {
+    fn text(&self) -> Option<String> {
+        self.text.clone()
+    }
+
+    fn tool_calls(&self) -> Option<Vec<ToolCall>> {
+        self.tool_calls.clone()
+    }
+
+    fn thinking(&self) -> Option<String> {
+        self.thinking.clone()
+    }
+}
+
+impl LLMProvider for PromptToolsLLM {
+    fn tools(&self) -> Option<&[Tool]> {
+        Some(&self.tools)
+            .filter(|tools| !tools.is_empty())
+            .map(Vec::as_slice)
+    }
+}
+
+#[async_trait]
+impl ChatProvider for PromptToolsLLM {
+    /// Sends the conversation with the tools described in the system prompt, and parses
+    /// the calls of the response
+    ///
+    /// Without tools, or with [`ToolChoice::None`], the conversation is sent as is.
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        let tools = tools.unwrap_or(&self.tools);
+        let messages = render(messages);
+        if tools.is_empty() || matches!(self.tool_choice, Some(ToolChoice::None)) {
+            return self.inner.chat(&messages).await;
+        }
+
+        let prompted = self.prompted(tools)?;
+        let choice = self.tool_choice.as_ref();
+        let (text, thinking) = {
+            let response = self
+                .repair
+                .chat(prompted.provider.as_ref(), &messages, None, |text| {
+                    parse_calls(text, &prompted.tools, choice).map(|_| ())
+                })
+                .await?;
+            (response.text().unwrap_or_default(), response.thinking())
+        };
+
+        let calls = parse_calls(&text, &prompted.tools, choice).map_err(|message| {
+            LLMError::ResponseFormatError {
+                message,
+                raw_response: text.clone(),
+            }
+        })?;
+        Ok(Box::new(match calls {
+            Some(calls) => PromptToolsResponse {
+                text: None,
+                tool_calls: Some(self.to_tool_calls(calls)),
+                thinking,
+            },
+            None => PromptToolsResponse {
+                text: Some(text),
+                tool_calls: None,
+                thinking,
+            },
+        }))
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for PromptToolsLLM {
+    async fn complete(&self, req: &CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        self.inner.complete(req).await
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for PromptToolsLLM {
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        self.inner.embed(input).await
+    }
+}
+
+#[async_trait]
+impl SpeechToTextProvider for PromptToolsLLM {
+    async fn transcribe(&self, audio: Vec<u8>) -> Result<String, LLMError> {
+        self.inner.transcribe(audio).await
+    }
+
+    async fn transcribe_file(&self, file_path: &str) -> Result<String, LLMError> {
+        self.inner.transcribe_file(file_path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        builder::{FunctionBuilder, LLMBackend, LLMBuilder, ParamBuilder},
+        testing::MockLLM,
+    };
+
+    fn add_tool() -> Tool {
+        FunctionBuilder::new("add")
+            .description("Adds two integers")
+            .param(ParamBuilder::new("a").type_of("integer"))
+            .param(ParamBuilder::new("b").type_of("integer"))
+            .required(vec!["a".to_string(), "b".to_string()])
+            .build()
+    }
+
+    fn tools() -> ToolSet {
+        ToolSet::new(&[add_tool(), FunctionBuilder::new("now").build()]).unwrap()
+    }
+
+    /// Emulating provider whose prompted provider for `tools()` is `prompted`
+    fn emulated(
+        inner: MockLLM,
+        prompted: Arc<MockLLM>,
+        choice: Option<ToolChoice>,
+    ) -> PromptToolsLLM {
+        let tools = tools();
+        let key = serde_json::to_string(&tools.tools).unwrap();
+        PromptToolsLLM {
+            inner: Box::new(inner),
+            config: ProviderConfig::default(),
+            tools: tools.tools.clone(),
+            tool_choice: choice,
+            prompted: Mutex::new(HashMap::from([(
+                key,
+                Arc::new(Prompted {
+                    provider: prompted,
+                    tools,
+                }),
+            )])),
+            repair: Repair::new(EMULATION_ATTEMPTS),
+            next_id: AtomicUsize::new(1),
+        }
+    }
+
+    fn question() -> Vec<ChatMessage> {
+        vec![ChatMessage::user().content("What is 1 + 2?").build()]
+    }
+
+    #[test]
+    fn parses_calls_from_the_response() {
+        let calls = parse_calls(
+            "Sure:\n```json\n{\"tool_calls\": [{\"name\": \"add\", \"arguments\": {\"a\": 1, \"b\": 2}}, {\"name\": \"now\"}]}\n```",
+            &tools(),
+            None,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            calls,
+            vec![
+                ("add".to_string(), json!({ "a": 1, "b": 2 })),
+                ("now".to_string(), json!({})),
+            ]
+        );
+
+        assert_eq!(parse_calls("It is 3.", &tools(), None), Ok(None));
+        assert_eq!(parse_calls("{\"answer\": 3}", &tools(), None), Ok(None));
+    }
+
+    #[test]
+    fn rejects_invalid_calls() {
+        let error = |text: &str| parse_calls(text, &tools(), None).unwrap_err();
+
+        assert_eq!(
+            error(r#"{"tool_calls": {"name": "add"}}"#),
+            "tool_calls must be an array of calls"
+        );
+        assert_eq!(
+            error(r#"{"tool_calls": [{"arguments": {}}]}"#),
+            "Each tool call needs the name of the tool"
+        );
+        assert_eq!(
+            error(r#"{"tool_calls": [{"name": "sub"}]}"#),
+            "Unknown tool sub, available tools are: add, now"
+        );
+        assert_eq!(
+            error(r#"{"tool_calls": [{"name": "add", "arguments": [1, 2]}]}"#),
+            "The arguments of add must be an object"
+        );
+        assert!(
+            error(r#"{"tool_calls": [{"name": "add", "arguments": {"a": "1", "b": 2}}]}"#)
+                .starts_with("Invalid arguments for add: ")
+        );
+    }
+
+    #[test]
+    fn enforces_the_tool_choice() {
+        let any = Some(ToolChoice::Any);
+        assert!(parse_calls("It is 3.", &tools(), any.as_ref()).is_err());
+        assert!(parse_calls(
+            r#"{"tool_calls": [{"name": "now"}]}"#,
+            &tools(),
+            any.as_ref()
+        )
+        .unwrap()
+        .is_some());
+
+        let add = Some(ToolChoice::Tool("add".to_string()));
+        assert_eq!(
+            parse_calls(
+                r#"{"tool_calls": [{"name": "now"}]}"#,
+                &tools(),
+                add.as_ref()
+            ),
+            Err("Only the tool add may be called".to_string())
+        );
+        assert!(parse_calls("It is 3.", &tools(), add.as_ref()).is_err());
+
+        let instructions = tool_instructions(&tools().tools, add.as_ref());
+        assert!(instructions.contains("\"name\": \"add\""));
+        assert!(instructions.ends_with("You must call the tool add."));
+        assert!(tool_instructions(&tools().tools, any.as_ref())
+            .ends_with("You must call at least one tool."));
+    }
+
+    #[test]
+    fn renders_calls_and_results_as_text() {
+        let call = |arguments: &str| ToolCall {
+            id: "call_1".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "add".to_string(),
+                arguments: arguments.to_string(),
+            },
+        };
+        let messages = vec![
+            ChatMessage::user().content("What is 1 + 2?").build(),
+            ChatMessage::assistant()
+                .tool_use(vec![call(r#"{"a":1,"b":2}"#)])
+                .content("Let me add.")
+                .build(),
+            ChatMessage::user()
+                .tool_result(vec![call("3"), call("not json")])
+                .build(),
+        ];
+
+        let rendered = render(&messages);
+        assert_eq!(rendered[0].content, "What is 1 + 2?");
+        assert!(rendered
+            .iter()
+            .all(|m| matches!(m.message_type, MessageType::Text)));
+        assert_eq!(
+            rendered[1].content,
+            "Let me add.\n{\"tool_calls\":[{\"arguments\":{\"a\":1,\"b\":2},\"name\":\"add\"}]}"
+        );
+        assert_eq!(
+            rendered[2].content,
+            "Tool results:\n[{\"name\":\"add\",\"result\":3},{\"name\":\"add\",\"result\":\"not json\"}]"
+        );
+    }
+
+    #[test]
+    fn invalid_tool_schemas_are_rejected() {
+        let mut tool = add_tool();
+        tool.function
+            .parameters
+            .properties
+            .get_mut("a")
+            .unwrap()
+            .property_type = "integral".to_string();
+        assert!(ToolSet::new(&[tool]).is_err());
+    }
+
+    #[tokio::test]
+    async fn repairs_invalid_calls() {
+        let prompted = Arc::new(MockLLM::new([
+            r#"{"tool_calls": [{"name": "add", "arguments": {"a": 1}}]}"#,
+            r#"{"tool_calls": [{"name": "add", "arguments": {"a": 1, "b": 2}}]}"#,
+        ]));
+        let llm = emulated(MockLLM::default(), prompted.clone(), None);
+
+        let response = llm.chat_with_tools(&question(), None).await.unwrap();
+        assert_eq!(response.text(), None);
+        let calls = response.tool_calls().unwrap();
+        assert_eq!(calls[0].id, "call_1");
+        assert_eq!(calls[0].function.name, "add");
+        assert_eq!(
+            serde_json::from_str::<Value>(&calls[0].function.arguments).unwrap(),
+            json!({ "a": 1, "b": 2 })
+        );
+
+        let requests = prompted.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].len() > requests[0].len());
+    }
+
+    #[tokio::test]
+    async fn answers_in_text_unless_a_call_is_required() {
+        let prompted = Arc::new(MockLLM::new(["It is 3."]));
+        let llm = emulated(MockLLM::default(), prompted.clone(), None);
+        let response = llm.chat_with_tools(&question(), None).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some("It is 3."));
+        assert!(response.tool_calls().is_none());
+
+        let llm = emulated(MockLLM::default(), prompted.clone(), Some(ToolChoice::Any));
+        let error = llm.chat_with_tools(&question(), None).await.unwrap_err();
+        let attempts = crate::validation::ValidationAttempt::from_error(&error).unwrap();
+        assert_eq!(attempts.len(), EMULATION_ATTEMPTS);
+    }
+
+    #[tokio::test]
+    async fn disabled_tools_go_to_the_plain_provider() {
+        let prompted = Arc::new(MockLLM::new(["unused"]));
+        let llm = emulated(
+            MockLLM::new(["It is 3."]),
+            prompted.clone(),
+            Some(ToolChoice::None),
+        );
+        let response = llm.chat_with_tools(&question(), None).await.unwrap();
+        assert_eq!(response.text().as_deref(), Some("It is 3."));
+        assert!(prompted.requests.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn phind_emulates_tools_by_default() {
+        let builder = || {
+            LLMBuilder::new()
+                .backend(LLMBackend::Phind)
+                .function(FunctionBuilder::new("now"))
+        };
+        let llm = builder().build().unwrap();
+        assert_eq!(llm.tools().map(<[Tool]>::len), Some(1));
+
+        let llm = builder().emulate_tools(false).build().unwrap();
+        assert!(llm.tools().is_none());
+    }
+}
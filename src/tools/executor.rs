@@ -0,0 +1,251 @@
+//! Concurrent execution of the tool calls of a response.
+
+use std::time::Duration;
+
+use futures::{stream, StreamExt};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{FunctionCall, ToolCall};
+
+use super::{
+    registry::parse_arguments, ApprovalDecision, ApprovalEntry, ToolError, ToolPolicy, ToolRegistry,
+};
+
+/// A tool call executed during a run, with its result
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolCallRecord {
+    /// Call requested by the model
+    pub call: ToolCall,
+    /// Handler result, or the error reported to the model
+    pub result: Result<Value, ToolError>,
+    /// Approval decision, when the executor has a policy
+    pub approval: Option<ApprovalEntry>,
+    /// Time spent executing the call
+    pub elapsed: Duration,
+}
+
+impl ToolCallRecord {
+    /// Call result as sent back to the model
+    pub fn to_tool_result(&self) -> ToolCall {
+        let output = match &self.result {
+            Ok(value) => value.clone(),
+            Err(error) => error.to_value(),
+        };
+        ToolCall {
+            id: self.call.id.clone(),
+            call_type: self.call.call_type.clone(),
+            function: FunctionCall {
+                name: self.call.function.name.clone(),
+                arguments: output.to_string(),
+            },
+        }
+    }
+}
+
+/// Executes the tool calls of a response, several at a time
+///
+/// Models may request several independent calls in one turn. Up to `concurrency` of them
+/// run at once, each bounded by an optional timeout, and the records come back in the
+/// order of the calls, so that every result matches its call id. With a [`ToolPolicy`],
+/// each call is reviewed before its execution; the timeout does not cover the review.
+#[derive(Debug, Clone)]
+pub struct ToolExecutor {
+    concurrency: usize,
+    timeout: Option<Duration>,
+    policy: Option<ToolPolicy>,
+}
+
+impl Default for ToolExecutor {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            timeout: None,
+            policy: None,
+        }
+    }
+}
+
+impl ToolExecutor {
+    /// Creates an executor running up to 4 calls at once, without timeout
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of calls running at once, 1 running them sequentially
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Sets the time limit of each call, reported to the model as a tool error when exceeded
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the policy approving, denying or rewriting calls before their execution
+    pub fn policy(mut self, policy: ToolPolicy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Executes the calls with the registry's handlers, returning records in call order
+    pub async fn execute(
+        &self,
+        registry: &ToolRegistry,
+        calls: &[ToolCall],
+    ) -> Vec<ToolCallRecord> {
+        // Creating the futures up front keeps the closure out of the stream type, whose
+        // lifetimes would otherwise make the returned future not `Send`
+        let runs: Vec<_> = calls
+            .iter()
+            .map(|call| self.execute_one(registry, call))
+            .collect();
+        stream::iter(runs)
+            .buffered(self.concurrency)
+            .collect()
+            .await
+    }
+
+    async fn execute_one(&self, registry: &ToolRegistry, call: &ToolCall) -> ToolCallRecord {
+        let start = std::time::Instant::now();
+        let (result, approval) = match (&self.policy, parse_arguments(call)) {
+            (Some(policy), Ok(arguments)) => {
+                let approval = policy.review(call, &arguments).await;
+                let result = match &approval.decision {
+                    ApprovalDecision::Approve => self.run(registry, call).await,
+                    ApprovalDecision::Deny(message) => Err(ToolError::new(message.clone())),
+                    ApprovalDecision::Rewrite(arguments) => {
+                        let mut rewritten = call.clone();
+                        rewritten.function.arguments = arguments.to_string();
+                        self.run(registry, &rewritten).await
+                    }
+                };
+                (result, Some(approval))
+            }
+            (Some(_), Err(error)) => (Err(error), None),
+            (None, _) => (self.run(registry, call).await, None),
+        };
+        ToolCallRecord {
+            call: call.clone(),
+            result,
+            approval,
+            elapsed: start.elapsed(),
+        }
+    }
+
+    /// Executes a call within the timeout
+    async fn run(&self, registry: &ToolRegistry, call: &ToolCall) -> Result<Value, ToolError> {
+        match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, registry.call(call))
+                .await
+                .unwrap_or_else(|_| {
+                    Err(ToolError::new(format!(
+                        "Tool call timed out after {} ms",
+                        timeout.as_millis()
+                    )))
+                }),
+            None => registry.call(call).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+    use crate::tools::ToolDefinition;
+
+    #[derive(Deserialize, schemars::JsonSchema)]
+    struct SleepArgs {
+        ms: u64,
+    }
+
+    /// Registry with a `sleep` tool tracking the largest number of calls running at once
+    fn registry(max_running: Arc<AtomicUsize>) -> ToolRegistry {
+        let running = Arc::new(AtomicUsize::new(0));
+        ToolRegistry::new().register(ToolDefinition::typed(
+            "sleep",
+            "Sleeps",
+            move |args: SleepArgs| {
+                let (running, max_running) = (running.clone(), max_running.clone());
+                async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(args.ms)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    Ok(json!(args.ms))
+                }
+            },
+        ))
+    }
+
+    fn call(id: usize, ms: u64) -> ToolCall {
+        ToolCall {
+            id: format!("call_{}", id),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "sleep".to_string(),
+                arguments: json!({ "ms": ms }).to_string(),
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn records_follow_call_order_within_the_concurrency_limit() {
+        let max_running = Arc::new(AtomicUsize::new(0));
+        let registry = registry(max_running.clone());
+        let calls: Vec<ToolCall> = [30, 5, 20, 1]
+            .iter()
+            .enumerate()
+            .map(|(i, &ms)| call(i, ms))
+            .collect();
+
+        let records = ToolExecutor::new()
+            .concurrency(2)
+            .execute(&registry, &calls)
+            .await;
+
+        let ids: Vec<&str> = records.iter().map(|r| r.call.id.as_str()).collect();
+        assert_eq!(ids, vec!["call_0", "call_1", "call_2", "call_3"]);
+        assert_eq!(records[2].result.as_ref().unwrap(), &json!(20));
+        assert_eq!(max_running.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn slow_calls_time_out() {
+        let registry = registry(Default::default());
+        let records = ToolExecutor::new()
+            .timeout(Duration::from_millis(10))
+            .execute(&registry, &[call(0, 200), call(1, 0)])
+            .await;
+
+        let error = records[0].result.as_ref().unwrap_err();
+        assert_eq!(error.message, "Tool call timed out after 10 ms");
+        assert!(records[1].result.is_ok());
+        assert_eq!(
+            records[0].to_tool_result().function.arguments,
+            json!({ "error": "Tool call timed out after 10 ms" }).to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn execution_can_be_spawned() {
+        let registry = registry(Default::default());
+        let records =
+            tokio::spawn(
+                async move { ToolExecutor::new().execute(&registry, &[call(0, 1)]).await },
+            )
+            .await
+            .unwrap();
+        assert_eq!(records.len(), 1);
+    }
+}
@@ -0,0 +1,161 @@
+//! Tools defined from typed Rust functions.
+
+use std::{future::Future, sync::Arc};
+
+use schemars::JsonSchema;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::{
+    chat::Tool,
+    structured::{format_tool, schema_for, WRAPPED_PROPERTY},
+};
+
+use super::{ToolError, ToolHandler};
+
+/// A tool definition with the handler executing its calls
+///
+/// Usually generated by the `#[tool]` attribute macro (`macros` feature), and added to a
+/// registry with [`ToolRegistry::register`](super::ToolRegistry::register).
+#[derive(Clone)]
+pub struct ToolDefinition {
+    /// Definition sent to the model
+    pub tool: Tool,
+    /// Handler called with the arguments of each call
+    pub handler: Arc<ToolHandler>,
+}
+
+impl ToolDefinition {
+    /// Defines a tool taking JSON arguments
+    pub fn new<F, Fut>(tool: Tool, handler: F) -> Self
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Value, ToolError>> + Send + 'static,
+    {
+        Self {
+            tool,
+            handler: Arc::new(move |args| Box::pin(handler(args))),
+        }
+    }
+
+    /// Defines a tool whose arguments are the fields of `A`
+    ///
+    /// The parameters are derived from the JSON schema of `A`, field doc comments becoming
+    /// parameter descriptions. As tool arguments are objects, an `A` that is not a struct
+    /// (e.g. a vector or an enum) is taken as a single `value` argument. Arguments that do
+    /// not deserialize into `A` are reported to the model as a tool error without calling
+    /// the handler.
+    pub fn typed<A, F, Fut>(
+        name: impl Into<String>,
+        description: impl Into<String>,
+        handler: F,
+    ) -> Self
+    where
+        A: DeserializeOwned + JsonSchema + Send + 'static,
+        F: Fn(A) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Value, ToolError>> + Send + 'static,
+    {
+        let mut format = schema_for::<A>();
+        format.name = name.into();
+        format.description = Some(description.into());
+        let (tool, wrapped) = format_tool(&format);
+
+        let handler = Arc::new(handler);
+        Self::new(tool, move |mut args| {
+            let handler = handler.clone();
+            async move {
+                if wrapped {
+                    args = args
+                        .get_mut(WRAPPED_PROPERTY)
+                        .map(Value::take)
+                        .ok_or_else(|| {
+                            ToolError::new(format!("Missing argument {}", WRAPPED_PROPERTY))
+                        })?;
+                }
+                let args = serde_json::from_value::<A>(args)?;
+                handler(args).await
+            }
+        })
+    }
+}
+
+impl std::fmt::Debug for ToolDefinition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToolDefinition")
+            .field("tool", &self.tool)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Converts the result of a tool function into a handler result
+#[doc(hidden)]
+pub fn into_tool_result<T, E>(result: Result<T, E>) -> Result<Value, ToolError>
+where
+    T: Serialize,
+    E: Into<ToolError>,
+{
+    to_tool_value(result.map_err(Into::into)?)
+}
+
+/// Converts the output of an infallible tool function into a handler result
+#[doc(hidden)]
+pub fn to_tool_value<T: Serialize>(output: T) -> Result<Value, ToolError> {
+    serde_json::to_value(output)
+        .map_err(|e| ToolError::new(format!("Tool output is not serializable: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+
+    /// Arguments of the test tool
+    #[derive(Deserialize, JsonSchema)]
+    struct AddArgs {
+        /// First term
+        a: i64,
+        /// Second term
+        b: i64,
+    }
+
+    #[tokio::test]
+    async fn typed_tools_deserialize_struct_arguments() {
+        let tool = ToolDefinition::typed("add", "Adds two numbers", |args: AddArgs| async move {
+            to_tool_value(args.a + args.b)
+        });
+
+        let parameters = &tool.tool.function.parameters;
+        assert_eq!(parameters.properties["a"].description, "First term");
+        assert_eq!((tool.handler)(json!({ "a": 1, "b": 2 })).await.unwrap(), 3);
+
+        let error = (tool.handler)(json!({ "a": 1 })).await.unwrap_err();
+        assert!(error.message.contains("b"), "{}", error.message);
+    }
+
+    #[tokio::test]
+    async fn typed_tools_unwrap_non_struct_arguments() {
+        let tool = ToolDefinition::typed("sum", "Sums numbers", |numbers: Vec<i64>| async move {
+            to_tool_value(numbers.iter().sum::<i64>())
+        });
+
+        assert_eq!(
+            tool.tool.function.parameters.required,
+            vec![WRAPPED_PROPERTY]
+        );
+        let sum = (tool.handler)(json!({ "value": [1, 2, 3] })).await.unwrap();
+        assert_eq!(sum, 6);
+        assert!((tool.handler)(json!([1, 2, 3])).await.is_err());
+    }
+
+    #[test]
+    fn tool_results_are_serialized() {
+        assert_eq!(
+            into_tool_result::<_, ToolError>(Ok("done")).unwrap(),
+            "done"
+        );
+        let error = into_tool_result::<(), _>(Err(ToolError::new("failed"))).unwrap_err();
+        assert_eq!(error.message, "failed");
+    }
+}
@@ -0,0 +1,74 @@
+//! Tool registries and automatic tool execution.
+//!
+//! A [`ToolRegistry`] pairs function tool definitions with asynchronous Rust handlers.
+//! [`ToolRunner`] sends a conversation with those tools, executes the calls the model
+//! makes, appends their results and asks again, until the model answers or a limit is
+//! reached. The calls of a response run concurrently through a [`ToolExecutor`], with a
+//! concurrency limit and per-call timeouts. A [`ToolPolicy`] reviews each call first: its
+//! allow and deny rules and its approval hook approve the call, deny it with a message to
+//! the model, or rewrite its arguments. Handlers return a [`ToolError`] to report a failure
+//! to the model instead of aborting the run.
+//!
+//! Backends without function calling, such as Phind, emulate it through the prompt (see
+//! [`LLMBuilder::emulate_tools`](crate::builder::LLMBuilder::emulate_tools)), so the same
+//! registries and runners work with every backend.
+//!
+//! With the `macros` feature, the `#[tool]` attribute turns an async or plain function into
+//! a [`ToolDefinition`]: its doc comment describes the tool, the `# Arguments` section of
+//! the doc comment describes the parameters, and the parameter types give their schema.
+//!
+//! ```no_run
+//! use rllm::builder::{FunctionBuilder, LLMBackend, LLMBuilder, ParamBuilder};
+//! use rllm::chat::ChatMessage;
+//! use rllm::tools::{ToolError, ToolRegistry, ToolRunner};
+//! use serde_json::json;
+//!
+//! # async fn run() -> Result<(), rllm::error::LLMError> {
+//! let llm = LLMBuilder::new().backend(LLMBackend::OpenAI).build()?;
+//! let registry = ToolRegistry::new().function(
+//!     FunctionBuilder::new("get_weather")
+//!         .description("Get the current weather in a city")
+//!         .param(ParamBuilder::new("city").type_of("string"))
+//!         .required(vec!["city".to_string()]),
+//!     |args| async move {
+//!         let city = args["city"].as_str().ok_or(ToolError::new("city is required"))?;
+//!         Ok(json!({ "city": city, "forecast": "sunny" }))
+//!     },
+//! );
+//!
+//! let run = ToolRunner::new(llm.as_ref(), registry)
+//!     .max_iterations(5)
+//!     .run(&[ChatMessage::user().content("Weather in Paris?").build()])
+//!     .await?;
+//! println!("{}", run.answer.unwrap_or_default());
+//! # Ok(())
+//! # }
+//! ```
+
+mod emulated;
+mod executor;
+mod function;
+mod policy;
+mod registry;
+mod runner;
+
+pub(crate) use emulated::build as build_emulated;
+pub use executor::{ToolCallRecord, ToolExecutor};
+pub use function::ToolDefinition;
+pub use policy::{ApprovalDecision, ApprovalEntry, ApprovalFuture, ApprovalSource, ToolPolicy};
+pub use registry::{ToolError, ToolFuture, ToolHandler, ToolRegistry};
+pub use runner::{StopReason, ToolRun, ToolRunner};
+
+/// Turns a function into a tool definition, see the `rllm-macros` crate
+#[cfg(feature = "macros")]
+pub use rllm_macros::tool;
+
+/// Items used by the code generated by `#[tool]`
+#[doc(hidden)]
+pub mod __private {
+    pub use schemars;
+    pub use serde;
+    pub use serde_json;
+
+    pub use super::function::{into_tool_result, to_tool_value};
+}
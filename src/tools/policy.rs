@@ -0,0 +1,400 @@
+//! Approval of tool calls before their execution.
+//!
+//! A [`ToolPolicy`] checks each call against its rules in order, the first matching rule
+//! deciding. Argument patterns of allow rules must match the whole value, while those of
+//! deny rules may match any part of it, so a loose pattern never approves more than meant.
+//! Calls no rule matches go to the hook, or get the default decision.
+
+use std::{fmt, future::Future, pin::Pin, sync::Arc};
+
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{error::LLMError, ToolCall};
+
+/// Decision taken on a tool call before its execution
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum ApprovalDecision {
+    /// Executes the call as requested
+    Approve,
+    /// Skips the call, reporting the message to the model as the call error
+    Deny(String),
+    /// Executes the call with these arguments instead
+    Rewrite(Value),
+}
+
+/// What took an approval decision
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum ApprovalSource {
+    /// The declarative rule at this position in the policy
+    Rule(usize),
+    /// The approval hook
+    Hook,
+    /// The default decision, when no rule matched and no hook is set
+    Default,
+}
+
+/// Approval decision on a tool call, as logged
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalEntry {
+    /// Call requested by the model
+    pub call: ToolCall,
+    /// Decision taken
+    pub decision: ApprovalDecision,
+    /// What took the decision
+    pub source: ApprovalSource,
+}
+
+/// Boxed future returned by approval hooks
+pub type ApprovalFuture = Pin<Box<dyn Future<Output = ApprovalDecision> + Send>>;
+
+type ApprovalHook = dyn Fn(ToolCall, Value) -> ApprovalFuture + Send + Sync;
+type ApprovalLogger = dyn Fn(&ApprovalEntry) + Send + Sync;
+
+/// Declarative rule on tool names and arguments
+#[derive(Debug, Clone)]
+struct Rule {
+    /// Tool name, ending with `*` to match a prefix
+    tool: String,
+    /// JSON pointer of an argument and the pattern its value must match, anchored for
+    /// allow rules
+    argument: Option<(String, Regex)>,
+    /// `None` allows the call, `Some` denies it with the message
+    deny: Option<String>,
+}
+
+impl Rule {
+    fn matches(&self, call: &ToolCall, arguments: &Value) -> bool {
+        let name = &call.function.name;
+        let name_matches = match self.tool.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => *name == self.tool,
+        };
+        name_matches
+            && self.argument.as_ref().is_none_or(|(pointer, pattern)| {
+                match arguments.pointer(pointer) {
+                    Some(Value::String(text)) => pattern.is_match(text),
+                    Some(value) => pattern.is_match(&value.to_string()),
+                    None => false,
+                }
+            })
+    }
+}
+
+/// Approval policy applied to each tool call before its execution
+///
+/// Rules are checked in the order they were added, and the first matching rule approves
+/// or denies the call. Calls no rule matches go to the approval hook, for instance to ask a
+/// human, which may also rewrite the arguments. Without hook, they are approved unless
+/// [`ToolPolicy::deny_unlisted`] was set. Every decision is passed to the logger, and kept
+/// in the [`ToolCallRecord`](super::ToolCallRecord) of the call.
+///
+/// ```
+/// use rllm::tools::{ApprovalDecision, ToolPolicy};
+///
+/// let policy = ToolPolicy::new()
+///     .deny_argument("run_sql", "/query", r"(?i)\b(drop|delete|update)\b", "Only read queries are allowed")
+///     .unwrap()
+///     .allow("run_sql")
+///     .allow("get_*")
+///     .hook(|call, _arguments| async move {
+///         ApprovalDecision::Deny(format!("{} needs a human approval", call.function.name))
+///     })
+///     .logger(|entry| println!("{:?} {:?}", entry.decision, entry.call));
+/// ```
+#[derive(Clone, Default)]
+pub struct ToolPolicy {
+    rules: Vec<Rule>,
+    hook: Option<Arc<ApprovalHook>>,
+    logger: Option<Arc<ApprovalLogger>>,
+    unlisted: Option<String>,
+}
+
+impl ToolPolicy {
+    /// Creates a policy approving every call
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Approves calls of a tool; a name ending with `*` matches every tool with that prefix
+    pub fn allow(mut self, tool: impl Into<String>) -> Self {
+        self.rules.push(Rule {
+            tool: tool.into(),
+            argument: None,
+            deny: None,
+        });
+        self
+    }
+
+    /// Denies calls of a tool with a message for the model
+    pub fn deny(mut self, tool: impl Into<String>, message: impl Into<String>) -> Self {
+        self.rules.push(Rule {
+            tool: tool.into(),
+            argument: None,
+            deny: Some(message.into()),
+        });
+        self
+    }
+
+    /// Approves calls of a tool whose argument at `pointer` fully matches `pattern`
+    ///
+    /// `pointer` is a JSON pointer into the arguments, e.g. `/table`, or empty for the whole
+    /// arguments; non-string values are matched in their JSON form. The pattern is anchored at both ends: `users|orders`
+    /// approves `users` but not `users; DROP TABLE users`.
+    pub fn allow_argument(
+        mut self,
+        tool: impl Into<String>,
+        pointer: impl Into<String>,
+        pattern: &str,
+    ) -> Result<Self, LLMError> {
+        self.rules.push(Rule {
+            tool: tool.into(),
+            argument: Some((
+                check_pointer(pointer)?,
+                compile(&format!("^(?:{})$", pattern))?,
+            )),
+            deny: None,
+        });
+        Ok(self)
+    }
+
+    /// Denies calls of a tool whose argument at `pointer` contains a match of `pattern`
+    ///
+    /// `pointer` is interpreted as in [`ToolPolicy::allow_argument`], but the pattern is not
+    /// anchored: `DROP` denies any value containing `DROP`. Use `^` and `$` to match whole
+    /// values only.
+    pub fn deny_argument(
+        mut self,
+        tool: impl Into<String>,
+        pointer: impl Into<String>,
+        pattern: &str,
+        message: impl Into<String>,
+    ) -> Result<Self, LLMError> {
+        self.rules.push(Rule {
+            tool: tool.into(),
+            argument: Some((check_pointer(pointer)?, compile(pattern)?)),
+            deny: Some(message.into()),
+        });
+        Ok(self)
+    }
+
+    /// Denies the calls no rule matches when no hook is set
+    pub fn deny_unlisted(mut self, message: impl Into<String>) -> Self {
+        self.unlisted = Some(message.into());
+        self
+    }
+
+    /// Sets the hook deciding on the calls no rule matches
+    ///
+    /// The hook receives the call and its parsed arguments.
+    pub fn hook<F, Fut>(mut self, hook: F) -> Self
+    where
+        F: Fn(ToolCall, Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ApprovalDecision> + Send + 'static,
+    {
+        self.hook = Some(Arc::new(move |call, arguments| {
+            Box::pin(hook(call, arguments))
+        }));
+        self
+    }
+
+    /// Sets the function receiving every approval decision
+    pub fn logger<F>(mut self, logger: F) -> Self
+    where
+        F: Fn(&ApprovalEntry) + Send + Sync + 'static,
+    {
+        self.logger = Some(Arc::new(logger));
+        self
+    }
+
+    /// Decides on a call and logs the decision
+    pub async fn review(&self, call: &ToolCall, arguments: &Value) -> ApprovalEntry {
+        let rule = self
+            .rules
+            .iter()
+            .enumerate()
+            .find(|(_, rule)| rule.matches(call, arguments));
+        let (decision, source) = match (rule, &self.hook) {
+            (Some((i, rule)), _) => (
+                match &rule.deny {
+                    Some(message) => ApprovalDecision::Deny(message.clone()),
+                    None => ApprovalDecision::Approve,
+                },
+                ApprovalSource::Rule(i),
+            ),
+            (None, Some(hook)) => (
+                hook(call.clone(), arguments.clone()).await,
+                ApprovalSource::Hook,
+            ),
+            (None, None) => (
+                match &self.unlisted {
+                    Some(message) => ApprovalDecision::Deny(message.clone()),
+                    None => ApprovalDecision::Approve,
+                },
+                ApprovalSource::Default,
+            ),
+        };
+
+        let entry = ApprovalEntry {
+            call: call.clone(),
+            decision,
+            source,
+        };
+        if let Some(logger) = &self.logger {
+            logger(&entry);
+        }
+        entry
+    }
+}
+
+impl fmt::Debug for ToolPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ToolPolicy")
+            .field("rules", &self.rules)
+            .field("hook", &self.hook.is_some())
+            .field("unlisted", &self.unlisted)
+            .finish_non_exhaustive()
+    }
+}
+
+fn check_pointer(pointer: impl Into<String>) -> Result<String, LLMError> {
+    let pointer = pointer.into();
+    if !pointer.is_empty() && !pointer.starts_with('/') {
+        return Err(LLMError::InvalidRequest(format!(
+            "Invalid JSON pointer '{}': expected '/' at the start, e.g. '/{}'",
+            pointer, pointer
+        )));
+    }
+    Ok(pointer)
+}
+
+fn compile(pattern: &str) -> Result<Regex, LLMError> {
+    Regex::new(pattern).map_err(|e| LLMError::InvalidRequest(format!("Invalid pattern: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::FunctionCall;
+
+    fn call(name: &str) -> ToolCall {
+        ToolCall {
+            id: "call_1".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: name.to_string(),
+                arguments: String::new(),
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn allow_patterns_match_whole_values() {
+        let policy = ToolPolicy::new()
+            .allow_argument("query", "/table", "users|orders")
+            .unwrap()
+            .deny_unlisted("Not allowed");
+
+        let allowed = policy
+            .review(&call("query"), &json!({ "table": "orders" }))
+            .await;
+        assert_eq!(allowed.decision, ApprovalDecision::Approve);
+        assert_eq!(allowed.source, ApprovalSource::Rule(0));
+
+        for table in ["users; DROP TABLE users", "all_orders"] {
+            let denied = policy
+                .review(&call("query"), &json!({ "table": table }))
+                .await;
+            assert_eq!(
+                denied.decision,
+                ApprovalDecision::Deny("Not allowed".into())
+            );
+            assert_eq!(denied.source, ApprovalSource::Default);
+        }
+    }
+
+    #[tokio::test]
+    async fn deny_patterns_match_anywhere_and_rules_apply_in_order() {
+        let policy = ToolPolicy::new()
+            .deny_argument("sql*", "/query", "(?i)drop", "No drops")
+            .unwrap()
+            .allow("sql*")
+            .allow_argument("scale", "/factor", "[0-9]")
+            .unwrap();
+
+        let denied = policy
+            .review(
+                &call("sql_run"),
+                &json!({ "query": "select 1; drop table t" }),
+            )
+            .await;
+        assert_eq!(denied.decision, ApprovalDecision::Deny("No drops".into()));
+
+        let allowed = policy
+            .review(&call("sql_run"), &json!({ "query": "select 1" }))
+            .await;
+        assert_eq!(allowed.source, ApprovalSource::Rule(1));
+
+        // Non-string values are matched in their JSON form
+        let number = policy.review(&call("scale"), &json!({ "factor": 7 })).await;
+        assert_eq!(number.source, ApprovalSource::Rule(2));
+        let large = policy
+            .review(&call("scale"), &json!({ "factor": 70 }))
+            .await;
+        assert_eq!(large.source, ApprovalSource::Default);
+    }
+
+    #[tokio::test]
+    async fn hook_decides_unmatched_calls_and_decisions_are_logged() {
+        let logged = Arc::new(Mutex::new(Vec::new()));
+        let log = logged.clone();
+        let policy =
+            ToolPolicy::new()
+                .deny("shell", "No shell")
+                .hook(|_, arguments| async move {
+                    ApprovalDecision::Rewrite(json!({ "safe": arguments }))
+                })
+                .logger(move |entry| log.lock().unwrap().push(entry.source.clone()));
+
+        let rewritten = policy.review(&call("fetch"), &json!(1)).await;
+        assert_eq!(
+            rewritten.decision,
+            ApprovalDecision::Rewrite(json!({ "safe": 1 }))
+        );
+        policy.review(&call("shell"), &json!({})).await;
+
+        assert_eq!(
+            *logged.lock().unwrap(),
+            vec![ApprovalSource::Hook, ApprovalSource::Rule(0)]
+        );
+    }
+
+    #[test]
+    fn invalid_patterns_are_rejected() {
+        assert!(matches!(
+            ToolPolicy::new().allow_argument("query", "/table", "("),
+            Err(LLMError::InvalidRequest(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_pointers_are_rejected() {
+        assert!(matches!(
+            ToolPolicy::new().allow_argument("query", "table", "users"),
+            Err(LLMError::InvalidRequest(_))
+        ));
+        assert!(matches!(
+            ToolPolicy::new().deny_argument("query", "table", "DROP", "No"),
+            Err(LLMError::InvalidRequest(_))
+        ));
+        assert!(ToolPolicy::new()
+            .allow_argument("query", "", ".*")
+            .and_then(|policy| policy.deny_argument("query", "/table", "DROP", "No"))
+            .is_ok());
+    }
+}
@@ -0,0 +1,224 @@
+//! Registry of Rust tool handlers.
+
+use std::{collections::HashMap, fmt, future::Future, pin::Pin, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{builder::FunctionBuilder, chat::Tool, error::LLMError, ToolCall};
+
+use super::ToolDefinition;
+
+/// Error returned by a tool handler, reported back to the model as the call result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolError {
+    /// Message shown to the model
+    pub message: String,
+    /// Structured details, e.g. the invalid field or allowed values
+    pub details: Option<Value>,
+}
+
+impl ToolError {
+    /// Creates an error with a message only
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    /// Attaches structured details to the error
+    pub fn details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    /// JSON payload sent to the model as the call result
+    pub fn to_value(&self) -> Value {
+        match &self.details {
+            Some(details) => json!({ "error": self.message, "details": details }),
+            None => json!({ "error": self.message }),
+        }
+    }
+}
+
+impl fmt::Display for ToolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ToolError {}
+
+impl From<String> for ToolError {
+    fn from(message: String) -> Self {
+        Self::new(message)
+    }
+}
+
+impl From<&str> for ToolError {
+    fn from(message: &str) -> Self {
+        Self::new(message)
+    }
+}
+
+impl From<LLMError> for ToolError {
+    fn from(error: LLMError) -> Self {
+        Self::new(error.to_string())
+    }
+}
+
+impl From<serde_json::Error> for ToolError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::new(format!("Invalid arguments: {}", error))
+    }
+}
+
+/// Boxed future returned by tool handlers
+pub type ToolFuture = Pin<Box<dyn Future<Output = Result<Value, ToolError>> + Send>>;
+
+/// Tool handler, called with the arguments of a tool call
+pub type ToolHandler = dyn Fn(Value) -> ToolFuture + Send + Sync;
+
+/// Tool definitions sent to the model, with the Rust handlers executing their calls
+#[derive(Default, Clone)]
+pub struct ToolRegistry {
+    tools: Vec<Tool>,
+    handlers: HashMap<String, Arc<ToolHandler>>,
+}
+
+impl ToolRegistry {
+    /// Creates an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a function tool and its asynchronous handler
+    pub fn function<F, Fut>(self, function: FunctionBuilder, handler: F) -> Self
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Value, ToolError>> + Send + 'static,
+    {
+        self.tool(function.build(), handler)
+    }
+
+    /// Registers a tool definition and its asynchronous handler
+    ///
+    /// A tool registered under an existing name replaces it.
+    pub fn tool<F, Fut>(self, tool: Tool, handler: F) -> Self
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Value, ToolError>> + Send + 'static,
+    {
+        self.register(ToolDefinition::new(tool, handler))
+    }
+
+    /// Registers a tool definition with its handler, e.g. one generated by `#[tool]`
+    ///
+    /// A tool registered under an existing name replaces it.
+    pub fn register(mut self, definition: ToolDefinition) -> Self {
+        let name = definition.tool.function.name.clone();
+        self.tools.retain(|t| t.function.name != name);
+        self.tools.push(definition.tool);
+        self.handlers.insert(name, definition.handler);
+        self
+    }
+
+    /// Tool definitions to send to the model
+    pub fn tools(&self) -> &[Tool] {
+        &self.tools
+    }
+
+    /// Whether a tool is registered under this name
+    pub fn contains(&self, name: &str) -> bool {
+        self.handlers.contains_key(name)
+    }
+
+    /// Executes a tool call with the handler registered under its name
+    ///
+    /// Unknown tools and arguments that are not valid JSON are reported as tool errors.
+    pub async fn call(&self, call: &ToolCall) -> Result<Value, ToolError> {
+        let handler = self.handlers.get(&call.function.name).ok_or_else(|| {
+            ToolError::new(format!("Unknown tool '{}'", call.function.name)).details(json!({
+                "available": self.tools.iter().map(|t| &t.function.name).collect::<Vec<_>>()
+            }))
+        })?;
+        handler(parse_arguments(call)?).await
+    }
+}
+
+/// Parses the arguments of a call, empty arguments standing for an empty object
+pub(crate) fn parse_arguments(call: &ToolCall) -> Result<Value, ToolError> {
+    match call.function.arguments.trim() {
+        "" => Ok(Value::Object(Default::default())),
+        arguments => Ok(serde_json::from_str(arguments)?),
+    }
+}
+
+impl fmt::Debug for ToolRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ToolRegistry")
+            .field("tools", &self.tools)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FunctionCall;
+
+    use super::*;
+
+    fn call(name: &str, arguments: &str) -> ToolCall {
+        ToolCall {
+            id: "call_1".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: name.to_string(),
+                arguments: arguments.to_string(),
+            },
+        }
+    }
+
+    fn echo(name: &str) -> FunctionBuilder {
+        FunctionBuilder::new(name).description("Returns its arguments")
+    }
+
+    #[tokio::test]
+    async fn calls_the_handler_registered_under_the_name() {
+        let registry = ToolRegistry::new()
+            .function(echo("echo"), |args| async move { Ok(args) })
+            .function(echo("other"), |_| async move { Ok(json!("other")) });
+
+        assert!(registry.contains("echo"));
+        let result = registry.call(&call("echo", r#"{"a": 1}"#)).await;
+        assert_eq!(result, Ok(json!({ "a": 1 })));
+        // Empty arguments stand for an empty object
+        assert_eq!(registry.call(&call("echo", " ")).await, Ok(json!({})));
+    }
+
+    #[tokio::test]
+    async fn later_registrations_replace_earlier_ones() {
+        let registry = ToolRegistry::new()
+            .function(echo("echo"), |_| async move { Ok(json!(1)) })
+            .function(echo("echo"), |_| async move { Ok(json!(2)) });
+
+        assert_eq!(registry.tools().len(), 1);
+        assert_eq!(registry.call(&call("echo", "{}")).await, Ok(json!(2)));
+    }
+
+    #[tokio::test]
+    async fn unknown_tools_and_invalid_arguments_are_tool_errors() {
+        let registry = ToolRegistry::new().function(echo("echo"), |args| async move { Ok(args) });
+
+        let error = registry.call(&call("missing", "{}")).await.unwrap_err();
+        assert_eq!(
+            error.to_value(),
+            json!({ "error": "Unknown tool 'missing'", "details": { "available": ["echo"] } })
+        );
+
+        let error = registry.call(&call("echo", "{not json")).await.unwrap_err();
+        assert!(error.message.starts_with("Invalid arguments"), "{}", error);
+        assert_eq!(error.to_value(), json!({ "error": error.message }));
+    }
+}
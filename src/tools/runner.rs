@@ -0,0 +1,346 @@
+//! Automatic tool execution loop.
+
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::{
+    chat::{ChatMessage, MessageType},
+    error::LLMError,
+    text::LengthUnit,
+    LLMProvider,
+};
+
+use super::{ToolCallRecord, ToolExecutor, ToolPolicy, ToolRegistry};
+
+/// Why a tool run stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum StopReason {
+    /// The model answered without calling tools
+    FinalAnswer,
+    /// The maximum number of chat requests was reached
+    MaxIterations,
+    /// Executing the requested calls would exceed the tool call budget
+    ToolCallBudget,
+    /// The estimated size of the transcript exceeded the token budget
+    TokenBudget,
+}
+
+/// Outcome of a tool run
+#[derive(Debug, Clone)]
+pub struct ToolRun {
+    /// Conversation, from the initial messages to the last response
+    pub transcript: Vec<ChatMessage>,
+    /// Final answer of the model, if it gave one
+    pub answer: Option<String>,
+    /// Tool calls executed, in order
+    pub calls: Vec<ToolCallRecord>,
+    /// Number of chat requests sent
+    pub iterations: usize,
+    /// Why the run stopped
+    pub stop: StopReason,
+}
+
+impl ToolRun {
+    /// Whether the model gave a final answer within the limits
+    pub fn is_complete(&self) -> bool {
+        self.stop == StopReason::FinalAnswer
+    }
+}
+
+/// Runs a conversation, executing the tool calls of the model until it answers
+///
+/// Each iteration sends the transcript with the registry's tools. When the response holds
+/// tool calls, they are executed concurrently by a [`ToolExecutor`] and appended to the
+/// transcript with their results, and the loop continues; a response without tool calls is
+/// the final answer. Handler errors, unknown tools and invalid arguments are reported to the
+/// model as call results, so it can correct itself.
+pub struct ToolRunner<'a> {
+    llm: &'a dyn LLMProvider,
+    registry: ToolRegistry,
+    executor: ToolExecutor,
+    max_iterations: usize,
+    max_tool_calls: Option<usize>,
+    token_budget: Option<usize>,
+}
+
+impl<'a> ToolRunner<'a> {
+    /// Creates a runner over a provider and the tools it may call
+    pub fn new(llm: &'a dyn LLMProvider, registry: ToolRegistry) -> Self {
+        Self {
+            llm,
+            registry,
+            executor: ToolExecutor::default(),
+            max_iterations: 10,
+            max_tool_calls: None,
+            token_budget: None,
+        }
+    }
+
+    /// Sets the maximum number of chat requests (10 by default)
+    pub fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Sets the maximum number of tool calls executed over the run
+    pub fn max_tool_calls(mut self, max_tool_calls: usize) -> Self {
+        self.max_tool_calls = Some(max_tool_calls);
+        self
+    }
+
+    /// Stops the run once the transcript exceeds an estimated number of tokens
+    pub fn token_budget(mut self, tokens: usize) -> Self {
+        self.token_budget = Some(tokens);
+        self
+    }
+
+    /// Sets how the calls of each response are executed
+    pub fn executor(mut self, executor: ToolExecutor) -> Self {
+        self.executor = executor;
+        self
+    }
+
+    /// Sets the maximum number of calls of a response running at once (4 by default)
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.executor = self.executor.concurrency(concurrency);
+        self
+    }
+
+    /// Sets the time limit of each tool call
+    pub fn call_timeout(mut self, timeout: Duration) -> Self {
+        self.executor = self.executor.timeout(timeout);
+        self
+    }
+
+    /// Sets the policy reviewing each tool call before its execution
+    pub fn policy(mut self, policy: ToolPolicy) -> Self {
+        self.executor = self.executor.policy(policy);
+        self
+    }
+
+    /// Tools available to the model
+    pub fn registry(&self) -> &ToolRegistry {
+        &self.registry
+    }
+
+    /// Runs the conversation until a final answer or a limit
+    ///
+    /// Provider errors abort the run; reaching a limit does not, and is reported by
+    /// [`ToolRun::stop`].
+    pub async fn run(&self, messages: &[ChatMessage]) -> Result<ToolRun, LLMError> {
+        let mut run = ToolRun {
+            transcript: messages.to_vec(),
+            answer: None,
+            calls: Vec::new(),
+            iterations: 0,
+            stop: StopReason::MaxIterations,
+        };
+
+        while run.iterations < self.max_iterations {
+            if self
+                .token_budget
+                .is_some_and(|budget| transcript_tokens(&run.transcript) > budget)
+            {
+                run.stop = StopReason::TokenBudget;
+                return Ok(run);
+            }
+
+            run.iterations += 1;
+            let (text, tool_calls) = {
+                let response = self
+                    .llm
+                    .chat_with_tools(&run.transcript, Some(self.registry.tools()))
+                    .await?;
+                (response.text(), response.tool_calls().unwrap_or_default())
+            };
+
+            if tool_calls.is_empty() {
+                let answer = text.unwrap_or_default();
+                run.transcript
+                    .push(ChatMessage::assistant().content(answer.clone()).build());
+                run.answer = Some(answer);
+                run.stop = StopReason::FinalAnswer;
+                return Ok(run);
+            }
+            if self
+                .max_tool_calls
+                .is_some_and(|max| run.calls.len() + tool_calls.len() > max)
+            {
+                run.stop = StopReason::ToolCallBudget;
+                return Ok(run);
+            }
+
+            let records = self.executor.execute(&self.registry, &tool_calls).await;
+            run.transcript.push(
+                ChatMessage::assistant()
+                    .tool_use(tool_calls)
+                    .content(text.unwrap_or_default())
+                    .build(),
+            );
+            run.transcript.push(
+                ChatMessage::user()
+                    .tool_result(records.iter().map(ToolCallRecord::to_tool_result).collect())
+                    .build(),
+            );
+            run.calls.extend(records);
+        }
+        Ok(run)
+    }
+}
+
+/// Estimated number of tokens of a transcript
+fn transcript_tokens(messages: &[ChatMessage]) -> usize {
+    messages
+        .iter()
+        .map(|m| {
+            let calls = match &m.message_type {
+                MessageType::ToolUse(calls) | MessageType::ToolResult(calls) => calls
+                    .iter()
+                    .map(|c| LengthUnit::Tokens.measure(&c.function.arguments))
+                    .sum(),
+                _ => 0,
+            };
+            LengthUnit::Tokens.measure(&m.content) + calls
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::{
+        builder::{FunctionBuilder, ParamBuilder},
+        testing::{MockLLM, Reply},
+        tools::ToolError,
+    };
+
+    fn registry() -> ToolRegistry {
+        ToolRegistry::new()
+            .function(
+                FunctionBuilder::new("add")
+                    .param(ParamBuilder::new("a").type_of("integer"))
+                    .param(ParamBuilder::new("b").type_of("integer")),
+                |args| async move {
+                    let sum = args["a"].as_i64().unwrap_or(0) + args["b"].as_i64().unwrap_or(0);
+                    Ok(json!(sum))
+                },
+            )
+            .function(FunctionBuilder::new("fail"), |_| async move {
+                Err::<Value, _>(ToolError::new("boom"))
+            })
+    }
+
+    fn question() -> Vec<ChatMessage> {
+        vec![ChatMessage::user().content("What is 1 + 2?").build()]
+    }
+
+    #[tokio::test]
+    async fn executes_calls_until_the_final_answer() {
+        let llm = MockLLM::new([
+            Reply::call("add", r#"{"a": 1, "b": 2}"#),
+            Reply::from("It is 3."),
+        ]);
+        let run = ToolRunner::new(&llm, registry())
+            .run(&question())
+            .await
+            .unwrap();
+
+        assert!(run.is_complete());
+        assert_eq!(run.answer.as_deref(), Some("It is 3."));
+        assert_eq!(run.iterations, 2);
+        assert_eq!(run.calls.len(), 1);
+        assert_eq!(run.calls[0].result, Ok(json!(3)));
+
+        let transcript = &run.transcript;
+        assert_eq!(transcript.len(), 4);
+        assert!(matches!(
+            &transcript[1].message_type,
+            MessageType::ToolUse(calls) if calls[0].function.name == "add"
+        ));
+        assert!(matches!(
+            &transcript[2].message_type,
+            MessageType::ToolResult(results) if results[0].function.arguments == "3"
+        ));
+        assert_eq!(transcript[3].content, "It is 3.");
+
+        // The second request carries the call and its result
+        let requests = llm.requests.lock().unwrap();
+        assert_eq!(requests[1].len(), 3);
+    }
+
+    #[tokio::test]
+    async fn tool_errors_are_reported_to_the_model() {
+        let llm = MockLLM::new([Reply::call("fail", "{}"), Reply::from("It failed.")]);
+        let run = ToolRunner::new(&llm, registry())
+            .run(&question())
+            .await
+            .unwrap();
+
+        assert!(run.is_complete());
+        assert_eq!(run.calls[0].result, Err(ToolError::new("boom")));
+        let MessageType::ToolResult(results) = &run.transcript[2].message_type else {
+            panic!("expected tool results");
+        };
+        assert_eq!(
+            serde_json::from_str::<Value>(&results[0].function.arguments).unwrap(),
+            json!({ "error": "boom" })
+        );
+    }
+
+    #[tokio::test]
+    async fn stops_after_the_maximum_iterations() {
+        let llm = MockLLM::new([Reply::call("add", r#"{"a": 1, "b": 1}"#)]);
+        let run = ToolRunner::new(&llm, registry())
+            .max_iterations(3)
+            .run(&question())
+            .await
+            .unwrap();
+
+        assert_eq!(run.stop, StopReason::MaxIterations);
+        assert!(!run.is_complete());
+        assert_eq!(run.answer, None);
+        assert_eq!(run.iterations, 3);
+        assert_eq!(run.calls.len(), 3);
+        assert_eq!(run.transcript.len(), 7);
+    }
+
+    #[tokio::test]
+    async fn stops_before_exceeding_the_tool_call_budget() {
+        let llm = MockLLM::new([Reply::call("add", r#"{"a": 1, "b": 1}"#)]);
+        let run = ToolRunner::new(&llm, registry())
+            .max_tool_calls(1)
+            .run(&question())
+            .await
+            .unwrap();
+
+        assert_eq!(run.stop, StopReason::ToolCallBudget);
+        assert_eq!(run.iterations, 2);
+        assert_eq!(run.calls.len(), 1);
+        // The calls over budget are neither executed nor added to the transcript
+        assert_eq!(run.transcript.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn stops_once_the_transcript_exceeds_the_token_budget() {
+        let llm = MockLLM::new([Reply::call("add", r#"{"a": 1, "b": 1}"#)]);
+        let run = ToolRunner::new(&llm, registry())
+            .token_budget(5)
+            .run(&question())
+            .await
+            .unwrap();
+
+        assert_eq!(run.stop, StopReason::TokenBudget);
+        assert_eq!(run.iterations, 1);
+        assert!(transcript_tokens(&run.transcript) > 5);
+    }
+
+    #[tokio::test]
+    async fn provider_errors_abort_the_run() {
+        let llm = MockLLM::failing("unavailable");
+        let result = ToolRunner::new(&llm, registry()).run(&question()).await;
+        assert!(matches!(result, Err(LLMError::ProviderError(_))));
+    }
+}
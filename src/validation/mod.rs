@@ -0,0 +1,415 @@
+//! Validation of LLM responses with a repair loop.
+//!
+//! [`ValidatedLLM`] wraps a provider and checks each response with a validator. A rejected
+//! response is reported back to the model following a [`RepairStrategy`], using a
+//! customizable repair template, until a response passes or the attempts run out. The
+//! final error then lists every attempt, recoverable with [`ValidationAttempt::from_error`].
+//! Ready-made validators (JSON Schema, regex, length, allowed values and combinators) are
+//! provided by this module as well.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rllm::builder::{LLMBackend, LLMBuilder};
+//! use rllm::validation::RepairStrategy;
+//!
+//! let llm = LLMBuilder::new()
+//!     .backend(LLMBackend::OpenAI)
+//!     .validator(|response| {
+//!         serde_json::from_str::<serde_json::Value>(response)
+//!             .map(|_| ())
+//!             .map_err(|e| e.to_string())
+//!     })
+//!     .validator_attempts(3)
+//!     .repair_strategy(RepairStrategy::Conversation)
+//!     .repair_template("That was not valid JSON ({{error}}). Reply with the JSON only.")
+//!     .build()
+//!     .unwrap();
+//! ```
+
+mod validators;
+
+use std::sync::LazyLock;
+
+use async_trait::async_trait;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    builder::ValidatorFn,
+    chat::{ChatMessage, ChatProvider, ChatResponse, ChatRole, MessageType, Tool},
+    completion::{CompletionProvider, CompletionRequest, CompletionResponse},
+    embedding::EmbeddingProvider,
+    error::LLMError,
+    stt::SpeechToTextProvider,
+    LLMProvider,
+};
+
+pub use validators::{
+    all, any, full_match, json_schema, length, lenient_json, lenient_json_schema, not, one_of,
+    LengthUnit,
+};
+
+/// Repair message sent after a rejected response unless another template is set
+pub const DEFAULT_REPAIR_TEMPLATE: &str = "Your previous output was invalid because: {{error}}\n\
+     Please try again and produce a valid response.";
+
+/// How a rejected response is reported back to the model before the next attempt
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RepairStrategy {
+    /// Resends the original request, without telling the model what was wrong
+    Retry,
+    /// Appends the rendered repair template as a user turn
+    Feedback,
+    /// Appends the rejected output as an assistant turn, then the rendered repair
+    /// template as a user turn
+    #[default]
+    Conversation,
+}
+
+/// A rejected response and the reason the validator gave
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidationAttempt {
+    /// Response text
+    pub output: String,
+    /// Validator message
+    pub error: String,
+}
+
+impl ValidationAttempt {
+    /// Recovers the attempts from the error returned when every attempt was rejected
+    ///
+    /// That error is a [`LLMError::ResponseFormatError`] whose `raw_response` holds the
+    /// attempts as a JSON array.
+    pub fn from_error(error: &LLMError) -> Option<Vec<ValidationAttempt>> {
+        match error {
+            LLMError::ResponseFormatError { raw_response, .. } => {
+                serde_json::from_str(raw_response).ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// Builds the error reporting that every attempt was rejected
+    pub(crate) fn into_error(attempts: Vec<ValidationAttempt>) -> LLMError {
+        let message = format!(
+            "Validation failed after {} attempts:\n{}",
+            attempts.len(),
+            attempts
+                .iter()
+                .enumerate()
+                .map(|(i, a)| format!("attempt {}: {}", i + 1, a.error))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+        LLMError::ResponseFormatError {
+            message,
+            raw_response: serde_json::to_string(&attempts).unwrap_or_default(),
+        }
+    }
+}
+
+/// Placeholders of repair templates
+static REPAIR_PLACEHOLDER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{(output|error)\}\}").unwrap());
+
+/// Repair loop settings, shared by the validating wrappers of the crate
+#[derive(Debug, Clone)]
+pub(crate) struct Repair {
+    pub(crate) strategy: RepairStrategy,
+    pub(crate) template: String,
+    pub(crate) attempts: usize,
+}
+
+impl Repair {
+    pub(crate) fn new(attempts: usize) -> Self {
+        Self {
+            strategy: RepairStrategy::default(),
+            template: DEFAULT_REPAIR_TEMPLATE.to_string(),
+            attempts: attempts.max(1),
+        }
+    }
+
+    /// Renders the repair template for a rejected output
+    ///
+    /// Placeholders are substituted in a single pass, so `{{error}}` or `{{output}}`
+    /// appearing in the output or the error are left as is.
+    fn message(&self, output: &str, error: &str) -> String {
+        REPAIR_PLACEHOLDER
+            .replace_all(&self.template, |caps: &Captures| match &caps[1] {
+                "output" => output.to_string(),
+                _ => error.to_string(),
+            })
+            .into_owned()
+    }
+
+    /// Appends the repair turns for a rejected output to a conversation
+    pub(crate) fn push_feedback(&self, messages: &mut Vec<ChatMessage>, output: &str, error: &str) {
+        let text = |role, content| ChatMessage {
+            role,
+            message_type: MessageType::Text,
+            content,
+        };
+        match self.strategy {
+            RepairStrategy::Retry => {}
+            RepairStrategy::Feedback => {
+                messages.push(text(ChatRole::User, self.message(output, error)));
+            }
+            RepairStrategy::Conversation => {
+                messages.push(text(ChatRole::Assistant, output.to_string()));
+                messages.push(text(ChatRole::User, self.message(output, error)));
+            }
+        }
+    }
+
+    /// Chats until `validate` accepts the response text or the attempts run out
+    pub(crate) async fn chat<L, F>(
+        &self,
+        llm: &L,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+        validate: F,
+    ) -> Result<Box<dyn ChatResponse>, LLMError>
+    where
+        L: ChatProvider + ?Sized,
+        F: Fn(&str) -> Result<(), String> + Send + Sync,
+    {
+        let mut local_messages = messages.to_vec();
+        let mut attempts = Vec::new();
+
+        while attempts.len() < self.attempts {
+            let response = llm.chat_with_tools(&local_messages, tools).await?;
+            let output = response.text().unwrap_or_default();
+            match validate(&output) {
+                Ok(()) => return Ok(response),
+                Err(error) => {
+                    self.push_feedback(&mut local_messages, &output, &error);
+                    attempts.push(ValidationAttempt { output, error });
+                }
+            }
+        }
+        Err(ValidationAttempt::into_error(attempts))
+    }
+
+    /// Completes until `validate` accepts the completion or the attempts run out
+    ///
+    /// Unless the strategy is [`RepairStrategy::Retry`], the repair message (preceded by
+    /// the rejected output for [`RepairStrategy::Conversation`]) is appended to the prompt.
+    pub(crate) async fn complete<F>(
+        &self,
+        llm: &dyn LLMProvider,
+        req: &CompletionRequest,
+        validate: F,
+    ) -> Result<CompletionResponse, LLMError>
+    where
+        F: Fn(&str) -> Result<(), String> + Send + Sync,
+    {
+        let mut local_req = req.clone();
+        let mut attempts = Vec::new();
+
+        while attempts.len() < self.attempts {
+            let response = llm.complete(&local_req).await?;
+            match validate(&response.text) {
+                Ok(()) => return Ok(response),
+                Err(error) => {
+                    let feedback = match self.strategy {
+                        RepairStrategy::Retry => None,
+                        RepairStrategy::Feedback => Some(self.message(&response.text, &error)),
+                        RepairStrategy::Conversation => Some(format!(
+                            "{}\n\n{}",
+                            response.text,
+                            self.message(&response.text, &error)
+                        )),
+                    };
+                    if let Some(feedback) = feedback {
+                        local_req.prompt = format!("{}\n\n{}", req.prompt, feedback);
+                    }
+                    attempts.push(ValidationAttempt {
+                        output: response.text,
+                        error,
+                    });
+                }
+            }
+        }
+        Err(ValidationAttempt::into_error(attempts))
+    }
+}
+
+/// A wrapper around an LLM provider that validates responses before returning them.
+pub struct ValidatedLLM {
+    /// The wrapped LLM provider
+    inner: Box<dyn LLMProvider>,
+    /// Function used to validate responses, returns Ok(()) if valid or Err with message if invalid
+    validator: Box<ValidatorFn>,
+    /// Repair loop settings
+    repair: Repair,
+}
+
+impl ValidatedLLM {
+    /// Creates a new ValidatedLLM wrapper around an existing LLM provider.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - The LLM provider to wrap with validation
+    /// * `validator` - Function that takes a response string and returns Ok(()) if valid, or Err with error message if invalid
+    /// * `attempts` - Maximum number of attempts, at least 1
+    pub fn new(inner: Box<dyn LLMProvider>, validator: Box<ValidatorFn>, attempts: usize) -> Self {
+        Self::with_repair(inner, validator, Repair::new(attempts))
+    }
+
+    /// Creates a wrapper with the given repair loop settings
+    pub(crate) fn with_repair(
+        inner: Box<dyn LLMProvider>,
+        validator: Box<ValidatorFn>,
+        repair: Repair,
+    ) -> Self {
+        Self {
+            inner,
+            validator,
+            repair,
+        }
+    }
+
+    /// Sets how rejected responses are reported back to the model
+    pub fn repair_strategy(mut self, strategy: RepairStrategy) -> Self {
+        self.repair.strategy = strategy;
+        self
+    }
+
+    /// Sets the repair message, where `{{error}}` is replaced by the validator message and
+    /// `{{output}}` by the rejected response (defaults to [`DEFAULT_REPAIR_TEMPLATE`])
+    pub fn repair_template(mut self, template: impl Into<String>) -> Self {
+        self.repair.template = template.into();
+        self
+    }
+}
+
+impl LLMProvider for ValidatedLLM {
+    fn tools(&self) -> Option<&[Tool]> {
+        self.inner.tools()
+    }
+}
+
+#[async_trait]
+impl ChatProvider for ValidatedLLM {
+    /// Sends a chat request and repairs the response until it passes validation.
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: Option<&[Tool]>,
+    ) -> Result<Box<dyn ChatResponse>, LLMError> {
+        self.repair
+            .chat(self.inner.as_ref(), messages, tools, &self.validator)
+            .await
+    }
+}
+
+#[async_trait]
+impl CompletionProvider for ValidatedLLM {
+    /// Sends a completion request and repairs the completion until it passes validation.
+    async fn complete(&self, req: &CompletionRequest) -> Result<CompletionResponse, LLMError> {
+        self.repair
+            .complete(self.inner.as_ref(), req, &self.validator)
+            .await
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for ValidatedLLM {
+    /// Passes through embedding requests to the inner provider without validation.
+    async fn embed(&self, input: Vec<String>) -> Result<Vec<Vec<f32>>, LLMError> {
+        self.inner.embed(input).await
+    }
+}
+
+#[async_trait]
+impl SpeechToTextProvider for ValidatedLLM {
+    /// Passes through transcription requests to the inner provider without validation.
+    async fn transcribe(&self, audio: Vec<u8>) -> Result<String, LLMError> {
+        self.inner.transcribe(audio).await
+    }
+
+    async fn transcribe_file(&self, file_path: &str) -> Result<String, LLMError> {
+        self.inner.transcribe_file(file_path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockLLM;
+
+    fn digits(text: &str) -> Result<(), String> {
+        if !text.is_empty() && text.chars().all(|c| c.is_ascii_digit()) {
+            Ok(())
+        } else {
+            Err(format!("'{}' is not a number", text))
+        }
+    }
+
+    #[test]
+    fn placeholders_inside_substituted_text_are_kept() {
+        let mut repair = Repair::new(1);
+        repair.template = "Output: {{output}}\nError: {{error}}".to_string();
+
+        let message = repair.message("literally {{error}}", "bad {{output}}");
+
+        assert_eq!(
+            message,
+            "Output: literally {{error}}\nError: bad {{output}}"
+        );
+    }
+
+    #[tokio::test]
+    async fn conversation_strategy_replays_output_and_feedback() {
+        let llm = MockLLM::new(["four", "4"]);
+        let repair = Repair::new(3);
+        let messages = [ChatMessage::user().content("2+2?").build()];
+
+        let response = repair.chat(&llm, &messages, None, digits).await.unwrap();
+
+        assert_eq!(response.text().as_deref(), Some("4"));
+        let requests = llm.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1][1].content, "four");
+        assert_eq!(
+            requests[1][2].content,
+            "Your previous output was invalid because: 'four' is not a number\n\
+             Please try again and produce a valid response."
+        );
+    }
+
+    #[tokio::test]
+    async fn exhausted_attempts_are_recoverable_from_the_error() {
+        let llm = MockLLM::new(["one", "two"]);
+        let mut repair = Repair::new(2);
+        repair.strategy = RepairStrategy::Retry;
+        let messages = [ChatMessage::user().content("2+2?").build()];
+
+        let error = match repair.chat(&llm, &messages, None, digits).await {
+            Err(error) => error,
+            Ok(_) => panic!("invalid responses were accepted"),
+        };
+
+        let attempts = ValidationAttempt::from_error(&error).unwrap();
+        assert_eq!(attempts.len(), 2);
+        assert_eq!(attempts[1].output, "two");
+        assert_eq!(llm.requests.lock().unwrap()[1].len(), 1);
+    }
+
+    #[tokio::test]
+    async fn completions_get_feedback_appended_to_the_prompt() {
+        let llm = MockLLM::new(["four", "4"]);
+        let mut repair = Repair::new(2);
+        repair.strategy = RepairStrategy::Feedback;
+        repair.template = "Fix: {{error}}".to_string();
+        let validated = ValidatedLLM::with_repair(Box::new(llm), Box::new(digits), repair);
+
+        let completion = validated
+            .complete(&CompletionRequest::new("2+2="))
+            .await
+            .unwrap();
+
+        assert_eq!(completion.text, "4");
+    }
+}
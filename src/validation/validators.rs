@@ -0,0 +1,347 @@
+//! Ready-made validators for [`LLMBuilder::validator`](crate::builder::LLMBuilder::validator).
+//!
+//! Each function returns a boxed [`ValidatorFn`], which can be passed to the builder as is
+//! or combined with [`all`], [`any`] and [`not`].
+//!
+//! ```no_run
+//! use rllm::builder::{LLMBackend, LLMBuilder};
+//! use rllm::validation::{all, json_schema, length, LengthUnit};
+//! use serde_json::json;
+//!
+//! let schema = json!({
+//!     "type": "object",
+//!     "properties": { "name": { "type": "string" }, "color": { "type": "string" } },
+//!     "required": ["name", "color"]
+//! });
+//! let llm = LLMBuilder::new()
+//!     .backend(LLMBackend::OpenAI)
+//!     .validator(all(vec![
+//!         json_schema(&schema).unwrap(),
+//!         length(None, Some(200), LengthUnit::Tokens),
+//!     ]))
+//!     .validator_attempts(3)
+//!     .build()
+//!     .unwrap();
+//! ```
+
+use regex::Regex;
+use serde_json::Value;
+
+use crate::{
+    builder::ValidatorFn,
+    error::LLMError,
+    json::{extract_json, ExtractedJson, JsonRepair},
+};
+
+pub use crate::text::LengthUnit;
+
+/// Accepts JSON conforming to a JSON Schema (draft 2020-12)
+///
+/// Rejections list every violation with the JSON pointer of the offending value, e.g.
+/// `/color: "blue" is not one of ["orange","black"]`.
+pub fn json_schema(schema: &Value) -> Result<Box<ValidatorFn>, LLMError> {
+    let validator = jsonschema::draft202012::new(schema)
+        .map_err(|e| LLMError::InvalidRequest(format!("Invalid JSON schema: {}", e)))?;
+
+    Ok(Box::new(move |response: &str| {
+        let value: Value = serde_json::from_str(response.trim())
+            .map_err(|e| format!("Response is not valid JSON: {}", e))?;
+        let errors: Vec<String> = validator
+            .iter_errors(&value)
+            .map(|e| {
+                let path = e.instance_path.as_str();
+                format!("{}: {}", if path.is_empty() { "/" } else { path }, e)
+            })
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Response does not match the JSON schema: {}",
+                errors.join("; ")
+            ))
+        }
+    }))
+}
+
+/// Accepts responses entirely matched by a regex, ignoring surrounding whitespace
+pub fn full_match(pattern: &str) -> Result<Box<ValidatorFn>, LLMError> {
+    let re = Regex::new(&format!("^(?:{})$", pattern))
+        .map_err(|e| LLMError::InvalidRequest(format!("Invalid pattern: {}", e)))?;
+    let pattern = pattern.to_string();
+
+    Ok(Box::new(move |response: &str| {
+        if re.is_match(response.trim()) {
+            Ok(())
+        } else {
+            Err(format!("Response does not match the pattern {}", pattern))
+        }
+    }))
+}
+
+/// Accepts responses whose length lies within the bounds, both inclusive
+pub fn length(min: Option<usize>, max: Option<usize>, unit: LengthUnit) -> Box<ValidatorFn> {
+    Box::new(move |response: &str| {
+        let len = unit.measure(response);
+        let unit_name = format!("{:?}", unit).to_lowercase();
+        match (min, max) {
+            (Some(min), _) if len < min => Err(format!(
+                "Response is too short: {} {}, at least {} expected",
+                len, unit_name, min
+            )),
+            (_, Some(max)) if len > max => Err(format!(
+                "Response is too long: {} {}, at most {} expected",
+                len, unit_name, max
+            )),
+            _ => Ok(()),
+        }
+    })
+}
+
+/// Accepts responses equal to one of the allowed values, ignoring surrounding whitespace
+pub fn one_of<I, S>(allowed: I) -> Box<ValidatorFn>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let allowed: Vec<String> = allowed.into_iter().map(Into::into).collect();
+    Box::new(move |response: &str| {
+        let response = response.trim();
+        if allowed.iter().any(|a| a == response) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Response {:?} is not one of: {}",
+                response,
+                allowed.join(", ")
+            ))
+        }
+    })
+}
+
+/// Accepts responses accepted by every validator, reporting all rejections
+pub fn all(validators: Vec<Box<ValidatorFn>>) -> Box<ValidatorFn> {
+    Box::new(move |response: &str| {
+        let errors: Vec<String> = validators
+            .iter()
+            .filter_map(|v| v(response).err())
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    })
+}
+
+/// Accepts responses accepted by at least one validator
+pub fn any(validators: Vec<Box<ValidatorFn>>) -> Box<ValidatorFn> {
+    Box::new(move |response: &str| {
+        let mut errors = Vec::with_capacity(validators.len());
+        for validator in &validators {
+            match validator(response) {
+                Ok(()) => return Ok(()),
+                Err(e) => errors.push(e),
+            }
+        }
+        Err(format!(
+            "No alternative accepted the response: {}",
+            errors.join("; ")
+        ))
+    })
+}
+
+/// Accepts responses the validator rejects, rejecting the others with `message`
+pub fn not(validator: Box<ValidatorFn>, message: impl Into<String>) -> Box<ValidatorFn> {
+    let message = message.into();
+    Box::new(move |response: &str| match validator(response) {
+        Ok(()) => Err(message.clone()),
+        Err(_) => Ok(()),
+    })
+}
+
+/// Accepts responses containing a valid JSON value, see [`extract_json`]
+///
+/// Fenced JSON or JSON surrounded by prose passes; use [`json_schema`] to require the
+/// response to be exactly valid JSON. JSON that is only valid after a syntax repair
+/// (trailing commas, single quotes, truncation...) is rejected, since the validated
+/// response is returned as is: the JSON value of accepted responses can be obtained with
+/// [`extract_json`] without any repair.
+pub fn lenient_json() -> Box<ValidatorFn> {
+    Box::new(|response: &str| extract_valid_json(response).map(|_| ()))
+}
+
+/// Accepts responses containing a valid JSON value conforming to a JSON Schema (draft
+/// 2020-12), with the same leniency as [`lenient_json`]
+pub fn lenient_json_schema(schema: &Value) -> Result<Box<ValidatorFn>, LLMError> {
+    let strict = json_schema(schema)?;
+    Ok(Box::new(move |response: &str| {
+        strict(&extract_valid_json(response)?.json)
+    }))
+}
+
+/// Extracts the JSON value of a response, rejecting values that needed a syntax repair
+fn extract_valid_json(response: &str) -> Result<ExtractedJson, String> {
+    let extracted = extract_json(response).map_err(|e| e.to_string())?;
+    let repairs: Vec<String> = extracted
+        .repairs
+        .iter()
+        .filter(|r| !matches!(r, JsonRepair::Fence | JsonRepair::SurroundingText))
+        .map(ToString::to_string)
+        .collect();
+    if repairs.is_empty() {
+        Ok(extracted)
+    } else {
+        Err(format!(
+            "Response contains malformed JSON, fix it: {}",
+            repairs.join(", ")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn color_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "color": { "enum": ["orange", "black"] },
+                "tags": { "type": "array", "items": { "type": "string" } },
+            },
+            "required": ["name", "color"],
+        })
+    }
+
+    fn reject(validator: &ValidatorFn, response: &str) -> String {
+        validator(response).unwrap_err()
+    }
+
+    #[test]
+    fn json_schema_lists_violations_with_their_pointer() {
+        let validator = json_schema(&color_schema()).unwrap();
+        assert!(validator(" {\"name\": \"Tom\", \"color\": \"black\"}\n").is_ok());
+        assert_eq!(
+            reject(&validator, r#"{"color": "blue", "tags": ["a", 3]}"#),
+            "Response does not match the JSON schema: \
+             /color: \"blue\" is not one of [\"orange\",\"black\"]; \
+             /tags/1: 3 is not of type \"string\"; \
+             /: \"name\" is a required property"
+        );
+        assert!(reject(&validator, "{nope").starts_with("Response is not valid JSON: "));
+    }
+
+    #[test]
+    fn json_schema_rejects_invalid_schemas() {
+        let error = json_schema(&json!({ "type": 5 })).err().unwrap();
+        assert!(matches!(
+            error,
+            LLMError::InvalidRequest(message) if message.starts_with("Invalid JSON schema: ")
+        ));
+    }
+
+    #[test]
+    fn full_match_anchors_the_whole_pattern() {
+        let validator = full_match(r"yes|no").unwrap();
+        assert!(validator(" yes\n").is_ok());
+        assert!(validator("no").is_ok());
+        // Without the group, "^yes|no$" would accept both
+        assert_eq!(
+            reject(&validator, "yes, I think"),
+            "Response does not match the pattern yes|no"
+        );
+        assert!(validator("I say no").is_err());
+        assert!(matches!(full_match("("), Err(LLMError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn length_reports_the_unit_and_bounds() {
+        let validator = length(Some(2), Some(3), LengthUnit::Words);
+        assert!(validator("two words").is_ok());
+        assert_eq!(
+            reject(&validator, "one"),
+            "Response is too short: 1 words, at least 2 expected"
+        );
+        assert_eq!(
+            reject(&validator, "a b c d"),
+            "Response is too long: 4 words, at most 3 expected"
+        );
+        assert!(length(None, None, LengthUnit::Chars)("").is_ok());
+    }
+
+    #[test]
+    fn one_of_compares_trimmed_responses() {
+        let validator = one_of(["positive", "negative"]);
+        assert!(validator("  negative\n").is_ok());
+        assert_eq!(
+            reject(&validator, "Positive"),
+            "Response \"Positive\" is not one of: positive, negative"
+        );
+    }
+
+    #[test]
+    fn combinators_compose_validators() {
+        let short_label = all(vec![
+            one_of(["a", "bb", "ccc"]),
+            length(None, Some(2), LengthUnit::Chars),
+        ]);
+        assert!(short_label("bb").is_ok());
+        assert_eq!(
+            reject(&short_label, "ccc"),
+            "Response is too long: 3 chars, at most 2 expected"
+        );
+        assert_eq!(
+            reject(&short_label, "dddd"),
+            "Response \"dddd\" is not one of: a, bb, ccc; \
+             Response is too long: 4 chars, at most 2 expected"
+        );
+
+        let yes_or_number = any(vec![one_of(["yes"]), full_match(r"\d+").unwrap()]);
+        assert!(yes_or_number("yes").is_ok());
+        assert!(yes_or_number("42").is_ok());
+        assert_eq!(
+            reject(&yes_or_number, "no"),
+            "No alternative accepted the response: Response \"no\" is not one of: yes; \
+             Response does not match the pattern \\d+"
+        );
+
+        let no_apology = not(full_match(r"(?s).*sorry.*").unwrap(), "Do not apologize");
+        assert!(no_apology("Here it is").is_ok());
+        assert_eq!(reject(&no_apology, "I'm sorry"), "Do not apologize");
+    }
+
+    #[test]
+    fn lenient_json_accepts_fenced_valid_json() {
+        let validator = lenient_json();
+        assert!(validator("Here it is:\n```json\n{\"a\": 1}\n```").is_ok());
+        assert!(validator("no json here").is_err());
+    }
+
+    #[test]
+    fn lenient_json_rejects_json_needing_syntax_repairs() {
+        let error = lenient_json()("{'a': 1,}").unwrap_err();
+        assert!(error.contains("removed trailing commas"), "{}", error);
+        assert!(
+            error.contains("converted single-quoted strings"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn lenient_json_schema_checks_the_extracted_value() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": { "a": { "type": "integer" } },
+            "required": ["a"]
+        });
+        let validator = lenient_json_schema(&schema).unwrap();
+        assert!(validator("Result: {\"a\": 1} done").is_ok());
+        assert!(validator("Result: {\"a\": \"one\"}").is_err());
+        assert!(validator("{\"a\": 1").is_err());
+    }
+}
@@ -0,0 +1,131 @@
+//! MCP client tests against the example test server and the crate's own server.
+
+use std::process::Command;
+
+use rllm::{
+    chain::{LLMRegistryBuilder, MultiChainStepBuilder, MultiChainStepMode, MultiPromptChain},
+    evaluator::ReplayProvider,
+    mcp::{McpClient, McpServer, RegistryTools, StdioTransport},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::io::BufReader;
+
+/// Arguments of the shout chain tool
+#[derive(Serialize, Deserialize, schemars::JsonSchema)]
+struct Shout {
+    /// Text to shout
+    text: String,
+}
+
+/// Connects to `examples/mcp_test_server.rs`, built and started through cargo
+async fn test_server() -> McpClient {
+    let mut command = Command::new(env!("CARGO"));
+    command.args([
+        "run",
+        "--quiet",
+        "--manifest-path",
+        concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"),
+        "--example",
+        "mcp_test_server",
+    ]);
+    McpClient::connect(StdioTransport::spawn(command).unwrap())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn stdio_client_lists_and_calls_tools() {
+    let client = test_server().await;
+    assert_eq!(client.server_info().name, "rllm-test-server");
+    assert_eq!(client.instructions(), Some("Tools for testing MCP clients"));
+
+    let tools = client.list_tools().await.unwrap();
+    let names: Vec<&str> = tools.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, vec!["echo", "add", "fail"]);
+    assert_eq!(
+        tools[0].to_tool().unwrap().function.parameters.required,
+        vec!["text"]
+    );
+
+    let echo = client.call_tool("echo", json!({ "text": "hello" })).await;
+    assert_eq!(echo.unwrap(), "hello");
+
+    let sum = client.call_tool("add", json!({ "a": 2, "b": 3.5 })).await;
+    assert_eq!(sum.unwrap(), json!({ "sum": 5.5 }));
+
+    let error = client.call_tool("fail", json!({})).await.unwrap_err();
+    assert_eq!(error.message, "This tool always fails");
+    assert!(error.details.is_none());
+
+    assert!(client.call_tool("missing", json!({})).await.is_err());
+}
+
+#[tokio::test]
+async fn server_round_trips_through_the_client() {
+    let llms = LLMRegistryBuilder::new()
+        .register(
+            "replay",
+            Box::new(ReplayProvider::new().response("ping", "pong")),
+        )
+        .build();
+    let tools = RegistryTools::new(llms)
+        .provider("replay", "Answers ping")
+        .unwrap()
+        .chain::<Shout, _>("shout", "Shouts a text", "shout", |llms| {
+            MultiPromptChain::new(llms).step(
+                MultiChainStepBuilder::new(MultiChainStepMode::function(|context| {
+                    Ok(context.get("text").unwrap_or_default().to_uppercase())
+                }))
+                .id("shout")
+                .build()
+                .unwrap(),
+            )
+        })
+        .unwrap()
+        .build();
+    let server = McpServer::new("registry", "1.0.0")
+        .instructions("Ask the replay model")
+        .tools(tools);
+
+    let (client_side, server_side) = tokio::io::duplex(4096);
+    let (server_reader, server_writer) = tokio::io::split(server_side);
+    tokio::spawn(async move {
+        server
+            .serve(BufReader::new(server_reader), server_writer)
+            .await
+    });
+    let (client_reader, client_writer) = tokio::io::split(client_side);
+    let client = McpClient::connect(StdioTransport::streams(client_reader, client_writer))
+        .await
+        .unwrap();
+
+    assert_eq!(client.server_info().name, "registry");
+    assert_eq!(client.instructions(), Some("Ask the replay model"));
+
+    let tools = client.list_tools().await.unwrap();
+    let names: Vec<&str> = tools.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, vec!["replay", "shout"]);
+    assert_eq!(tools[0].input_schema["required"], json!(["prompt"]));
+
+    let shouted = client.call_tool("shout", json!({ "text": "hey" })).await;
+    assert_eq!(shouted.unwrap(), "HEY");
+
+    let answer = client
+        .call_tool("replay", json!({ "prompt": "ping" }))
+        .await;
+    assert_eq!(answer.unwrap(), "pong");
+
+    let error = client
+        .call_tool("replay", json!({ "prompt": "unknown" }))
+        .await
+        .unwrap_err();
+    assert!(
+        error.message.contains("No recorded response"),
+        "{}",
+        error.message
+    );
+
+    let invalid = client.call_tool("replay", json!({})).await.unwrap_err();
+    assert!(invalid.message.contains("prompt"), "{}", invalid.message);
+}
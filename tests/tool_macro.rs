@@ -0,0 +1,130 @@
+//! Tools generated by the `#[tool]` attribute.
+
+use rllm::{
+    tools::{tool, ToolError, ToolRegistry},
+    FunctionCall, ToolCall,
+};
+use serde_json::{json, Value};
+
+/// Convert a temperature from Celsius to Fahrenheit
+///
+/// # Arguments
+///
+/// * `celsius` - Temperature in degrees Celsius
+#[tool(name = "to_fahrenheit")]
+fn convert_temperature(celsius: f64) -> f64 {
+    celsius * 9.0 / 5.0 + 32.0
+}
+
+/// Repeat a word
+///
+/// # Arguments
+///
+/// * `word` - Word to repeat
+/// * `times` - Number of repetitions,
+///   1 by default
+#[tool]
+async fn repeat(word: String, times: Option<usize>) -> Result<Vec<String>, ToolError> {
+    if word.is_empty() {
+        return Err(ToolError::new("word must not be empty"));
+    }
+    Ok(vec![word; times.unwrap_or(1)])
+}
+
+/// Sum numbers
+#[tool]
+pub fn r#sum(r#values: Vec<i64>) -> i64 {
+    values.iter().sum()
+}
+
+fn call(name: &str, arguments: Value) -> ToolCall {
+    ToolCall {
+        id: "call_1".to_string(),
+        call_type: "function".to_string(),
+        function: FunctionCall {
+            name: name.to_string(),
+            arguments: arguments.to_string(),
+        },
+    }
+}
+
+fn registry() -> ToolRegistry {
+    ToolRegistry::new()
+        .register(convert_temperature_tool())
+        .register(repeat_tool())
+        .register(sum_tool())
+}
+
+#[test]
+fn definitions_describe_the_function() {
+    let registry = registry();
+    let names: Vec<_> = registry
+        .tools()
+        .iter()
+        .map(|tool| tool.function.name.as_str())
+        .collect();
+    assert_eq!(names, ["to_fahrenheit", "repeat", "sum"]);
+
+    let repeat = &registry.tools()[1].function;
+    assert_eq!(repeat.description, "Repeat a word");
+    assert_eq!(repeat.parameters.required, vec!["word"]);
+    assert_eq!(
+        repeat.parameters.properties["word"].description,
+        "Word to repeat"
+    );
+    assert_eq!(
+        repeat.parameters.properties["times"].description,
+        "Number of repetitions, 1 by default"
+    );
+    assert_eq!(
+        repeat.parameters.properties["times"].property_type,
+        "integer"
+    );
+}
+
+#[tokio::test]
+async fn calls_sync_functions() {
+    let registry = registry();
+    let result = registry
+        .call(&call("to_fahrenheit", json!({ "celsius": 100.0 })))
+        .await;
+    assert_eq!(result, Ok(json!(212.0)));
+
+    let result = registry
+        .call(&call("sum", json!({ "values": [1, 2, 3] })))
+        .await;
+    assert_eq!(result, Ok(json!(6)));
+}
+
+#[tokio::test]
+async fn calls_async_functions_with_optional_parameters() {
+    let registry = registry();
+    let result = registry
+        .call(&call("repeat", json!({ "word": "hi" })))
+        .await;
+    assert_eq!(result, Ok(json!(["hi"])));
+
+    let result = registry
+        .call(&call("repeat", json!({ "word": "hi", "times": 2 })))
+        .await;
+    assert_eq!(result, Ok(json!(["hi", "hi"])));
+}
+
+#[tokio::test]
+async fn reports_errors_to_the_model() {
+    let registry = registry();
+    let result = registry.call(&call("repeat", json!({ "word": "" }))).await;
+    assert_eq!(result, Err(ToolError::new("word must not be empty")));
+
+    let error = registry
+        .call(&call("repeat", json!({ "times": 2 })))
+        .await
+        .unwrap_err();
+    assert!(error.message.contains("word"), "{}", error.message);
+}
+
+#[test]
+fn keeps_the_function_callable() {
+    assert_eq!(convert_temperature(0.0), 32.0);
+    assert_eq!(sum(vec![1, 2]), 3);
+}